@@ -31,8 +31,8 @@ use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
 use jj_lib::store::Store;
 use jj_lib::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError, SnapshotOptions,
-    WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError,
+    SnapshotOptions, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
 };
 use jj_lib::workspace::{WorkingCopyFactories, Workspace, WorkspaceInitError};
 
@@ -227,14 +227,18 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.snapshot(options)
     }
 
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError> {
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         let conflicts = commit
             .tree()?
             .conflicts()
             .map(|(path, _value)| format!("{}\n", path.as_internal_file_string()))
             .join("");
         std::fs::write(self.wc_path.join(".conflicts"), conflicts).unwrap();
-        self.inner.check_out(commit)
+        self.inner.check_out(commit, options)
     }
 
     fn reset(&mut self, commit: &Commit) -> Result<(), ResetError> {
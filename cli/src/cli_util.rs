@@ -36,6 +36,7 @@ use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use jj_lib::backend::{ChangeId, CommitId, MergedTreeId, TreeValue};
 use jj_lib::commit::Commit;
+use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::fileset::FilesetExpression;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::gitignore::{GitIgnoreError, GitIgnoreFile};
@@ -65,7 +66,8 @@ use jj_lib::str_util::StringPattern;
 use jj_lib::transaction::Transaction;
 use jj_lib::view::View;
 use jj_lib::working_copy::{
-    CheckoutStats, LockedWorkingCopy, SnapshotOptions, WorkingCopy, WorkingCopyFactory,
+    CheckoutOptions, CheckoutStats, LockedWorkingCopy, SnapshotOptions, WorkingCopy,
+    WorkingCopyFactory,
 };
 use jj_lib::workspace::{
     default_working_copy_factories, LockedWorkspace, WorkingCopyFactories, Workspace,
@@ -489,6 +491,7 @@ pub struct WorkspaceCommandHelper {
     may_update_working_copy: bool,
     working_copy_shared_with_git: bool,
     path_converter: RepoPathUiConverter,
+    revset_workspace_id: Option<WorkspaceId>,
 }
 
 impl WorkspaceCommandHelper {
@@ -525,6 +528,7 @@ impl WorkspaceCommandHelper {
             may_update_working_copy,
             working_copy_shared_with_git,
             path_converter,
+            revset_workspace_id: None,
         };
         // Parse commit_summary template (and short-prefixes revset) early to
         // report error before starting mutable operation.
@@ -624,7 +628,7 @@ impl WorkspaceCommandHelper {
             }
         } else {
             // Unlikely, but the HEAD ref got deleted by git?
-            self.finish_transaction(ui, tx, "import git head")?;
+            self.finish_transaction(ui, tx, "import git head", &CheckoutOptions::default())?;
         }
         Ok(())
     }
@@ -659,7 +663,7 @@ impl WorkspaceCommandHelper {
                 "Rebased {num_rebased} descendant commits off of commits rewritten from git"
             )?;
         }
-        self.finish_transaction(ui, tx, "import git refs")?;
+        self.finish_transaction(ui, tx, "import git refs", &CheckoutOptions::default())?;
         writeln!(
             ui.status(),
             "Done importing changes from the underlying Git repo."
@@ -712,6 +716,22 @@ impl WorkspaceCommandHelper {
         self.repo().view().get_wc_commit_id(self.workspace_id())
     }
 
+    /// Makes `@` in revsets refer to the working-copy commit of the given
+    /// workspace instead of the current one.
+    pub fn set_revset_workspace_id(
+        &mut self,
+        workspace_id: WorkspaceId,
+    ) -> Result<(), CommandError> {
+        if self.repo().view().get_wc_commit_id(&workspace_id).is_none() {
+            return Err(user_error(format!(
+                "No such workspace: {}",
+                workspace_id.as_str()
+            )));
+        }
+        self.revset_workspace_id = Some(workspace_id);
+        Ok(())
+    }
+
     pub fn working_copy_shared_with_git(&self) -> bool {
         self.working_copy_shared_with_git
     }
@@ -989,7 +1009,10 @@ impl WorkspaceCommandHelper {
     pub(crate) fn revset_parse_context(&self) -> RevsetParseContext {
         let workspace_context = RevsetWorkspaceContext {
             path_converter: &self.path_converter,
-            workspace_id: self.workspace_id(),
+            workspace_id: self
+                .revset_workspace_id
+                .as_ref()
+                .unwrap_or_else(|| self.workspace_id()),
         };
         RevsetParseContext::new(
             &self.revset_aliases_map,
@@ -1268,6 +1291,7 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
         ui: &mut Ui,
         maybe_old_commit: Option<&Commit>,
         new_commit: &Commit,
+        checkout_options: &CheckoutOptions,
     ) -> Result<(), CommandError> {
         assert!(self.may_update_working_copy);
         let stats = update_working_copy(
@@ -1275,6 +1299,7 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
             &mut self.workspace,
             maybe_old_commit,
             new_commit,
+            checkout_options,
         )?;
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
@@ -1316,6 +1341,7 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
         ui: &mut Ui,
         mut tx: Transaction,
         description: impl Into<String>,
+        checkout_options: &CheckoutOptions,
     ) -> Result<(), CommandError> {
         if !tx.mut_repo().has_changes() {
             writeln!(ui.status(), "Nothing changed.")?;
@@ -1374,7 +1400,12 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
 
         if self.may_update_working_copy {
             if let Some(new_commit) = &maybe_new_wc_commit {
-                self.update_working_copy(ui, maybe_old_wc_commit.as_ref(), new_commit)?;
+                self.update_working_copy(
+                    ui,
+                    maybe_old_wc_commit.as_ref(),
+                    new_commit,
+                    checkout_options,
+                )?;
             } else {
                 // It seems the workspace was deleted, so we shouldn't try to
                 // update it.
@@ -1663,7 +1694,19 @@ impl WorkspaceCommandTransaction<'_> {
     }
 
     pub fn finish(self, ui: &mut Ui, description: impl Into<String>) -> Result<(), CommandError> {
-        self.helper.finish_transaction(ui, self.tx, description)
+        self.finish_with_checkout_options(ui, description, &CheckoutOptions::default())
+    }
+
+    /// Like `finish()`, but checks out the new working-copy commit with the
+    /// given `checkout_options`.
+    pub fn finish_with_checkout_options(
+        self,
+        ui: &mut Ui,
+        description: impl Into<String>,
+        checkout_options: &CheckoutOptions,
+    ) -> Result<(), CommandError> {
+        self.helper
+            .finish_transaction(ui, self.tx, description, checkout_options)
     }
 
     /// Returns the wrapped [`Transaction`] for circumstances where
@@ -2018,13 +2061,19 @@ pub fn update_working_copy(
     workspace: &mut Workspace,
     old_commit: Option<&Commit>,
     new_commit: &Commit,
+    checkout_options: &CheckoutOptions,
 ) -> Result<Option<CheckoutStats>, CommandError> {
     let old_tree_id = old_commit.map(|commit| commit.tree_id().clone());
     let stats = if Some(new_commit.tree_id()) != old_tree_id.as_ref() {
         // TODO: CheckoutError::ConcurrentCheckout should probably just result in a
         // warning for most commands (but be an error for the checkout command)
         let stats = workspace
-            .check_out(repo.op_id().clone(), old_tree_id.as_ref(), new_commit)
+            .check_out(
+                repo.op_id().clone(),
+                old_tree_id.as_ref(),
+                new_commit,
+                checkout_options,
+            )
             .map_err(|err| {
                 internal_error_with_message(
                     format!("Failed to check out commit {}", new_commit.id().hex()),
@@ -2071,6 +2120,27 @@ fn load_template_aliases(
     Ok(aliases_map)
 }
 
+/// Style of the conflict markers to materialize conflicts with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum ConflictMarkerStyleArg {
+    /// Show each side as a diff from the base it's most similar to
+    Diff,
+    /// Show the full contents of each side and base
+    Snapshot,
+    /// Use Git's "diff3" style markers (only for conflicts with 2 sides)
+    Git,
+}
+
+impl From<ConflictMarkerStyleArg> for ConflictMarkerStyle {
+    fn from(arg: ConflictMarkerStyleArg) -> Self {
+        match arg {
+            ConflictMarkerStyleArg::Diff => ConflictMarkerStyle::Diff,
+            ConflictMarkerStyleArg::Snapshot => ConflictMarkerStyle::Snapshot,
+            ConflictMarkerStyleArg::Git => ConflictMarkerStyle::Git,
+        }
+    }
+}
+
 /// Helper to reformat content of log-like commands.
 #[derive(Clone, Debug)]
 pub enum LogContentFormat {
@@ -2278,10 +2348,11 @@ impl FromStr for RemoteBranchNamePattern {
         // weird that unanchored patterns like substring:branch@remote is split
         // into two, but I can't think of a better syntax.
         // TODO: should we disable substring pattern? what if we added regex?
-        let (maybe_kind, pat) = src
-            .split_once(':')
-            .map_or((None, src), |(kind, pat)| (Some(kind), pat));
-        let to_pattern = |pat: &str| {
+        fn split_kind(pat: &str) -> (Option<&str>, &str) {
+            pat.split_once(':')
+                .map_or((None, pat), |(kind, pat)| (Some(kind), pat))
+        }
+        let to_pattern = |maybe_kind: Option<&str>, pat: &str| {
             if let Some(kind) = maybe_kind {
                 StringPattern::from_str_kind(pat, kind).map_err(|err| err.to_string())
             } else {
@@ -2289,12 +2360,20 @@ impl FromStr for RemoteBranchNamePattern {
             }
         };
         // TODO: maybe reuse revset parser to handle branch/remote name containing @
-        let (branch, remote) = pat
+        let (branch, remote) = src
             .rsplit_once('@')
             .ok_or_else(|| "remote branch must be specified in branch@remote form".to_owned())?;
+        let (branch_kind, branch) = split_kind(branch);
+        // The remote fragment may have its own kind prefix (e.g.
+        // "main@glob:*"). Since remote names can't contain ':', this isn't
+        // ambiguous.
+        let (remote_kind, remote) = match split_kind(remote) {
+            (Some(kind), remote) => (Some(kind), remote),
+            (None, remote) => (branch_kind, remote),
+        };
         Ok(RemoteBranchNamePattern {
-            branch: to_pattern(branch)?,
-            remote: to_pattern(remote)?,
+            branch: to_pattern(branch_kind, branch)?,
+            remote: to_pattern(remote_kind, remote)?,
         })
     }
 }
@@ -2312,6 +2391,36 @@ impl fmt::Display for RemoteBranchNamePattern {
     }
 }
 
+/// Pattern matching either local branches by name, or remote branches in
+/// `branch@remote` form.
+#[derive(Clone, Debug)]
+pub enum LocalOrRemoteBranchNamePattern {
+    Local(StringPattern),
+    Remote(RemoteBranchNamePattern),
+}
+
+impl FromStr for LocalOrRemoteBranchNamePattern {
+    type Err = Box<dyn std::error::Error + Send + Sync>;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        // An explicit "exact:" kind always names a local branch, so local
+        // branches containing '@' can still be specified.
+        if let Some(name) = src.strip_prefix("exact:") {
+            return Ok(LocalOrRemoteBranchNamePattern::Local(StringPattern::exact(
+                name,
+            )));
+        }
+        match src.rsplit_once('@') {
+            Some((branch, remote)) if !branch.is_empty() && !remote.is_empty() => {
+                Ok(LocalOrRemoteBranchNamePattern::Remote(src.parse()?))
+            }
+            _ => Ok(LocalOrRemoteBranchNamePattern::Local(StringPattern::parse(
+                src,
+            )?)),
+        }
+    }
+}
+
 /// Jujutsu (An experimental VCS)
 ///
 /// To get started, see the tutorial at https://github.com/martinvonz/jj/blob/main/docs/tutorial.md.
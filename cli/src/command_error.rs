@@ -561,6 +561,7 @@ fn file_pattern_parse_error_hint(err: &FilePatternParseError) -> Option<String>
         }
         FilePatternParseError::RelativePath(_) => None,
         FilePatternParseError::GlobPattern(_) => None,
+        FilePatternParseError::EmptyGitIgnoreLine => None,
     }
 }
 
@@ -12,14 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::slice;
+use std::time::Duration;
+
 use itertools::Itertools as _;
+use jj_lib::git::REMOTE_NAME_FOR_LOCAL_GIT_REPO;
 use jj_lib::op_store::{BranchTarget, RefTarget, RemoteRef};
+use jj_lib::repo::Repo;
 use jj_lib::str_util::StringPattern;
 use jj_lib::view::View;
 
-use super::find_branches_with;
-use crate::cli_util::CommandHelper;
-use crate::command_error::CommandError;
+use super::{find_branches_with, find_remote_branches};
+use crate::cli_util::{
+    CommandHelper, LocalOrRemoteBranchNamePattern, RemoteBranchName, RemoteBranchNamePattern,
+};
+use crate::command_error::{user_error, CommandError, CommandErrorKind};
 use crate::ui::Ui;
 
 /// Forget everything about a branch, including its local and remote
@@ -27,6 +34,11 @@ use crate::ui::Ui;
 ///
 /// A forgotten branch will not impact remotes on future pushes. It will be
 /// recreated on future pulls if it still exists in the remote.
+///
+/// If a branch is specified in `branch@remote` form, only the remote branch
+/// is forgotten. A Git-tracking branch (`branch@git`) is recreated when the
+/// local branch is exported to Git again, which happens immediately in a
+/// colocated repo.
 #[derive(clap::Args, Clone, Debug)]
 pub struct BranchForgetArgs {
     /// The branches to forget
@@ -34,8 +46,49 @@ pub struct BranchForgetArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(required = true, value_parser = StringPattern::parse)]
-    names: Vec<StringPattern>,
+    ///
+    /// Names containing `@` are parsed as `branch@remote`. Use `exact:` prefix
+    /// to specify a local branch whose name contains `@`.
+    ///
+    /// Examples: main, glob:feature/*, glob:feature/*@*, main@glob:up*
+    #[arg(required_unless_present = "older_than")]
+    names: Vec<LocalOrRemoteBranchNamePattern>,
+    /// Only forget branches whose target commits were committed longer ago
+    /// than the given duration
+    ///
+    /// The duration is a number followed by a unit: `s`, `m`, `h`, `d` or `w`
+    /// (e.g. `90d`). If no branch names are given, all local branches are
+    /// considered.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    older_than: Option<Duration>,
+    /// Only forget the corresponding remote branches of the given remote
+    ///
+    /// The corresponding branches of the other remotes are untracked instead
+    /// of forgotten.
+    #[arg(long, value_name = "REMOTE")]
+    include_remotes: Option<String>,
+    /// Warn about names that don't match any branch instead of failing
+    ///
+    /// The branches matching the other names are still forgotten.
+    #[arg(long)]
+    continue_on_error: bool,
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit()).unwrap_or(0));
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err("expected a number followed by one of s, m, h, d, w".to_owned()),
+    };
+    let number: u64 = number.parse().map_err(|err| format!("{err}"))?;
+    number
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| "duration is too long".to_owned())
 }
 
 pub fn cmd_branch_forget(
@@ -45,29 +98,187 @@ pub fn cmd_branch_forget(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let repo = workspace_command.repo().clone();
-    let matched_branches = find_forgettable_branches(repo.view(), &args.names)?;
+    if let Some(remote_name) = &args.include_remotes {
+        if remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+            return Err(user_error(format!(
+                "Git-tracking branches can't be selected with --include-remotes: {remote_name}"
+            )));
+        }
+        if repo.view().remote_branches(remote_name).next().is_none() {
+            return Err(user_error(format!(
+                "No branches found for remote: {remote_name}"
+            )));
+        }
+    }
+    let mut local_patterns = vec![];
+    let mut remote_patterns = vec![];
+    for pattern in &args.names {
+        match pattern {
+            LocalOrRemoteBranchNamePattern::Local(pattern) => local_patterns.push(pattern.clone()),
+            LocalOrRemoteBranchNamePattern::Remote(pattern) => {
+                remote_patterns.push(pattern.clone());
+            }
+        }
+    }
+    let mut matched_branches = if args.names.is_empty() {
+        repo.view().branches().collect()
+    } else if local_patterns.is_empty() {
+        vec![]
+    } else if args.continue_on_error {
+        let mut branches = find_each_or_warn(ui, &local_patterns, |patterns| {
+            find_forgettable_branches(repo.view(), patterns)
+        })?;
+        branches.sort_unstable_by_key(|(name, _)| *name);
+        branches.dedup_by_key(|(name, _)| *name);
+        branches
+    } else {
+        find_forgettable_branches(repo.view(), &local_patterns)?
+    };
+    let mut matched_remote_branches = if remote_patterns.is_empty() {
+        vec![]
+    } else if args.continue_on_error {
+        let mut names = find_each_or_warn(ui, &remote_patterns, |patterns| {
+            find_forgettable_remote_branches(repo.view(), patterns)
+        })?;
+        names.sort_unstable();
+        names.dedup();
+        names
+    } else {
+        find_forgettable_remote_branches(repo.view(), &remote_patterns)?
+    };
+    if args.continue_on_error && matched_branches.is_empty() && matched_remote_branches.is_empty() {
+        writeln!(ui.status(), "No branches to forget.")?;
+        return Ok(());
+    }
+    if let Some(older_than) = args.older_than {
+        let now = command.settings().signature().timestamp;
+        let cutoff = now.timestamp.0 - i64::try_from(older_than.as_millis()).unwrap_or(i64::MAX);
+        // A branch is stale only if all of its (possibly conflicting) targets are.
+        let is_stale = |target: &RefTarget| -> Result<bool, CommandError> {
+            if target.is_absent() {
+                return Ok(false);
+            }
+            for id in target.added_ids() {
+                let commit = repo.store().get_commit(id)?;
+                if commit.committer().timestamp.timestamp.0 >= cutoff {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        };
+        let mut stale_branches = vec![];
+        for (name, branch_target) in matched_branches {
+            if is_stale(branch_target.local_target)? {
+                stale_branches.push((name, branch_target));
+            }
+        }
+        matched_branches = stale_branches;
+        let mut stale_remote_branches = vec![];
+        for name in matched_remote_branches {
+            let remote_ref = repo.view().get_remote_branch(&name.branch, &name.remote);
+            if is_stale(&remote_ref.target)? {
+                stale_remote_branches.push(name);
+            }
+        }
+        matched_remote_branches = stale_remote_branches;
+        if matched_branches.is_empty() && matched_remote_branches.is_empty() {
+            writeln!(ui.status(), "No branches to forget.")?;
+            return Ok(());
+        }
+    }
     let mut tx = workspace_command.start_transaction();
     for (name, branch_target) in &matched_branches {
         tx.mut_repo()
             .set_local_branch_target(name, RefTarget::absent());
         for (remote_name, _) in &branch_target.remote_refs {
-            tx.mut_repo()
-                .set_remote_branch(name, remote_name, RemoteRef::absent());
+            // The Git-tracking branch can't be untracked, so it's always
+            // forgotten along with the local branch.
+            let forget = match &args.include_remotes {
+                Some(include_remote) => {
+                    remote_name == include_remote || *remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO
+                }
+                None => true,
+            };
+            if forget {
+                tx.mut_repo()
+                    .set_remote_branch(name, remote_name, RemoteRef::absent());
+            } else {
+                tx.mut_repo().untrack_remote_branch(name, remote_name);
+            }
         }
     }
+    for name in &matched_remote_branches {
+        tx.mut_repo()
+            .set_remote_branch(&name.branch, &name.remote, RemoteRef::absent());
+    }
     tx.finish(
         ui,
         format!(
             "forget branch {}",
-            matched_branches.iter().map(|(name, _)| name).join(", ")
+            matched_branches
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .chain(matched_remote_branches.iter().map(|name| name.to_string()))
+                .join(", ")
         ),
     )?;
     if matched_branches.len() > 1 {
         writeln!(ui.status(), "Forgot {} branches.", matched_branches.len())?;
     }
+    if matched_remote_branches.len() > 1 {
+        writeln!(
+            ui.status(),
+            "Forgot {} remote branches.",
+            matched_remote_branches.len()
+        )?;
+    }
+    // A Git-tracking branch mirrors the ref in the underlying Git repo, which
+    // is exported from the local branch.
+    let view = workspace_command.repo().view();
+    let reexported_names = matched_remote_branches
+        .iter()
+        .filter(|name| name.remote == REMOTE_NAME_FOR_LOCAL_GIT_REPO)
+        .filter(|name| view.get_local_branch(&name.branch).is_present())
+        .collect_vec();
+    if !reexported_names.is_empty() {
+        let names = reexported_names.iter().join(", ");
+        if workspace_command.working_copy_shared_with_git() {
+            writeln!(
+                ui.hint_default(),
+                "Git-tracking branches were exported again from the local branches to the \
+                 colocated Git repo, so forgetting them had no effect: {names}"
+            )?;
+        } else {
+            writeln!(
+                ui.hint_default(),
+                "Git-tracking branches will be recreated from the local branches by the next \
+                 `jj git export`: {names}"
+            )?;
+        }
+    }
     Ok(())
 }
 
+/// Looks up the matches of each pattern separately, and warns about the
+/// patterns that don't match instead of failing.
+fn find_each_or_warn<P, T>(
+    ui: &Ui,
+    patterns: &[P],
+    mut find_matches: impl FnMut(&[P]) -> Result<Vec<T>, CommandError>,
+) -> Result<Vec<T>, CommandError> {
+    let mut matches = vec![];
+    for pattern in patterns {
+        match find_matches(slice::from_ref(pattern)) {
+            Ok(found) => matches.extend(found),
+            Err(err) if matches!(err.kind, CommandErrorKind::User) => {
+                writeln!(ui.warning_default(), "{}", err.error)?;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(matches)
+}
+
 fn find_forgettable_branches<'a>(
     view: &'a View,
     name_patterns: &[StringPattern],
@@ -76,3 +287,27 @@ fn find_forgettable_branches<'a>(
         view.branches().filter(|(name, _)| pattern.matches(name))
     })
 }
+
+fn find_forgettable_remote_branches(
+    view: &View,
+    name_patterns: &[RemoteBranchNamePattern],
+) -> Result<Vec<RemoteBranchName>, CommandError> {
+    let names = find_remote_branches(view, name_patterns)
+        .map_err(|mut err| {
+            let local_names = name_patterns
+                .iter()
+                .filter(|pattern| pattern.is_exact())
+                .map(|pattern| pattern.to_string())
+                .filter(|name| view.get_local_branch(name).is_present());
+            for name in local_names {
+                err.add_hint(format!(
+                    "Use `exact:{name}` to forget the local branch named {name}."
+                ));
+            }
+            err
+        })?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    Ok(names)
+}
@@ -16,6 +16,7 @@ use std::fmt::Debug;
 use std::io::Write as _;
 
 use jj_lib::fileset;
+use jj_lib::fileset::FilesetDiagnostics;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
@@ -36,7 +37,12 @@ pub fn cmd_debug_fileset(
     let workspace_command = command.workspace_helper(ui)?;
     let path_converter = workspace_command.path_converter();
 
-    let expression = fileset::parse_maybe_bare(&args.path, path_converter)?;
+    let mut diagnostics = FilesetDiagnostics::new();
+    let expression =
+        fileset::parse_maybe_bare_with_diagnostics(&mut diagnostics, &args.path, path_converter)?;
+    for warning in diagnostics.warnings() {
+        writeln!(ui.warning_default(), "{warning}")?;
+    }
     writeln!(ui.stdout(), "-- Parsed:")?;
     writeln!(ui.stdout(), "{expression:#?}")?;
     writeln!(ui.stdout())?;
@@ -12,13 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::iter;
+use std::path::PathBuf;
+
+use itertools::Itertools as _;
 use jj_lib::backend::TreeValue;
-use jj_lib::merged_tree::MergedTreeBuilder;
+use jj_lib::commit::{Commit, CommitIteratorExt as _};
+use jj_lib::matchers::Matcher;
+use jj_lib::merge::MergedTreeValue;
+use jj_lib::merged_tree::{MergedTree, MergedTreeBuilder};
 use jj_lib::object_id::ObjectId;
+use jj_lib::repo_path::RepoPathBuf;
 use tracing::instrument;
 
-use crate::cli_util::{print_unmatched_explicit_paths, CommandHelper, RevisionArg};
-use crate::command_error::{user_error, CommandError};
+use crate::cli_util::{
+    print_unmatched_explicit_paths, CommandHelper, RevisionArg, WorkspaceCommandHelper,
+};
+use crate::command_error::{user_error, user_error_with_message, CommandError};
 use crate::ui::Ui;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
@@ -36,15 +48,37 @@ enum ChmodMode {
 ///
 /// Unlike the POSIX `chmod`, `jj file chmod` also works on Windows, on
 /// conflicted files, and on arbitrary revisions.
+///
+/// If multiple revisions are given, each of them is updated, and a summary
+/// line is printed for every revision. Revisions where none of the paths exist
+/// are skipped.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileChmodArgs {
     mode: ChmodMode,
-    /// The revision to update
-    #[arg(long, short, default_value = "@")]
-    revision: RevisionArg,
+    /// The revision(s) to update
+    #[arg(long, short, default_value = "@", alias = "revision")]
+    revisions: Vec<RevisionArg>,
+    /// Update the parent of the working-copy commit (`@-`) instead
+    #[arg(long, conflicts_with = "revisions")]
+    parent: bool,
     /// Paths to change the executable bit for
-    #[arg(required = true, value_hint = clap::ValueHint::AnyPath)]
+    #[arg(
+        required_unless_present = "paths_from_file",
+        value_hint = clap::ValueHint::AnyPath
+    )]
     paths: Vec<String>,
+    /// Read additional paths to change the executable bit for from a file
+    ///
+    /// Each line of the file is a path like the positional arguments. Blank
+    /// lines and lines starting with `#` are ignored.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    paths_from_file: Option<PathBuf>,
+    /// Choose which of the matched files to change
+    ///
+    /// The matched files are listed along with their current executable bit,
+    /// and you will be asked for the indices of the files to change.
+    #[arg(long, short)]
+    interactive: bool,
 }
 
 #[instrument(skip_all)]
@@ -75,25 +109,173 @@ pub(crate) fn cmd_file_chmod(
         ChmodMode::Normal => false,
     };
 
+    let mut paths = args.paths.clone();
+    if let Some(file_path) = &args.paths_from_file {
+        let content = std::fs::read_to_string(command.cwd().join(file_path)).map_err(|err| {
+            user_error_with_message(
+                format!("Failed to read paths from {}", file_path.display()),
+                err,
+            )
+        })?;
+        paths.extend(
+            content
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.to_owned()),
+        );
+    }
+    if paths.is_empty() {
+        // Don't fall back to matching all files if the file lists no paths.
+        writeln!(ui.status(), "No paths to change the executable bit for")?;
+        return Ok(());
+    }
+
     let mut workspace_command = command.workspace_helper(ui)?;
-    let commit = workspace_command.resolve_single_rev(&args.revision)?;
-    workspace_command.check_rewritable([commit.id()])?;
-    let tree = commit.tree()?;
+    let commits: Vec<Commit> = if args.parent {
+        vec![workspace_command.resolve_single_rev(&RevisionArg::from("@-".to_owned()))?]
+    } else {
+        workspace_command
+            .parse_union_revsets(&args.revisions)?
+            .evaluate_to_commits()?
+            .try_collect()?
+    };
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to change the executable bit in.")?;
+        return Ok(());
+    }
+    if args.interactive && commits.len() > 1 {
+        return Err(user_error(
+            "--interactive can only be used with a single revision",
+        ));
+    }
+    workspace_command.check_rewritable(commits.iter().ids())?;
     // TODO: No need to add special case for empty paths when switching to
     // parse_union_filesets(). paths = [] should be "none()" if supported.
-    let fileset_expression = workspace_command.parse_file_patterns(&args.paths)?;
+    let fileset_expression = workspace_command.parse_file_patterns(&paths)?;
     let matcher = fileset_expression.to_matcher();
-    print_unmatched_explicit_paths(ui, &workspace_command, &fileset_expression, [&tree])?;
+    let trees: Vec<_> = commits.iter().map(|commit| commit.tree()).try_collect()?;
+    print_unmatched_explicit_paths(ui, &workspace_command, &fileset_expression, &trees)?;
+
+    let mut commit_entries = HashMap::new();
+    for (commit, tree) in iter::zip(&commits, &trees) {
+        let mut entries = collect_entries(&workspace_command, tree, matcher.as_ref())?;
+        if args.interactive && !entries.is_empty() {
+            entries = choose_entries(ui, &workspace_command, entries)?;
+        }
+        commit_entries.insert(commit.id().clone(), entries);
+    }
 
+    enum ChmodResult {
+        Changed(Commit),
+        Unchanged(Commit),
+        Skipped(Commit),
+    }
     let mut tx = workspace_command.start_transaction();
-    let store = tree.store();
-    let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
-    for (repo_path, result) in tree.entries_matching(matcher.as_ref()) {
+    let mut results = vec![];
+    let mut changed_ids = vec![];
+    tx.mut_repo().transform_descendants(
+        command.settings(),
+        commits.iter().ids().cloned().collect(),
+        |rewriter| {
+            let Some(entries) = commit_entries.remove(rewriter.old_commit().id()) else {
+                return Ok(());
+            };
+            let old_commit = rewriter.old_commit().clone();
+            if entries.is_empty() {
+                results.push(ChmodResult::Skipped(old_commit));
+                return Ok(());
+            }
+            let mut tree_builder = MergedTreeBuilder::new(old_commit.tree_id().clone());
+            for (repo_path, tree_value) in entries {
+                let new_tree_value = tree_value.map(|value| match value {
+                    Some(TreeValue::File { id, executable: _ }) => Some(TreeValue::File {
+                        id: id.clone(),
+                        executable: executable_bit,
+                    }),
+                    Some(TreeValue::Conflict(_)) => {
+                        panic!("Conflict sides must not themselves be conflicts")
+                    }
+                    value => value.clone(),
+                });
+                tree_builder.set_or_remove(repo_path, new_tree_value);
+            }
+            // Only the executable bits are changed, so preserve the shape of the
+            // conflicts even if some of the sides become identical.
+            let new_tree_id = tree_builder.write_tree_unsimplified(old_commit.store())?;
+            if &new_tree_id == old_commit.tree_id() {
+                // Descendants of rewritten commits will be rebased later.
+                results.push(ChmodResult::Unchanged(old_commit));
+                return Ok(());
+            }
+            let new_commit = rewriter
+                .reparent(command.settings())?
+                .set_tree_id(new_tree_id)
+                .write()?;
+            changed_ids.push(old_commit.id().clone());
+            results.push(ChmodResult::Changed(new_commit));
+            Ok(())
+        },
+    )?;
+    if changed_ids.is_empty() && commits.len() == 1 {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    let transaction_target = match changed_ids.as_slice() {
+        [id] => format!("commit {}", id.hex()),
+        ids => format!("{} commits", ids.len()),
+    };
+    if commits.len() > 1 {
+        // Rebase descendants early so the summaries don't show the rewritten
+        // commits as divergent.
+        let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+        if let Some(mut formatter) = ui.status_formatter() {
+            for result in &results {
+                let (message, commit) = match result {
+                    ChmodResult::Changed(commit) => ("Changed", commit),
+                    ChmodResult::Unchanged(commit) => ("Nothing changed in", commit),
+                    ChmodResult::Skipped(commit) => ("Skipped (no matching paths)", commit),
+                };
+                write!(formatter, "{message} ")?;
+                tx.write_commit_summary(formatter.as_mut(), commit)?;
+                writeln!(formatter)?;
+            }
+            if num_rebased > 0 {
+                writeln!(formatter, "Rebased {num_rebased} descendant commits")?;
+            }
+        }
+        if changed_ids.is_empty() {
+            writeln!(ui.status(), "Nothing changed.")?;
+            return Ok(());
+        }
+    }
+    tx.finish(
+        ui,
+        format!(
+            "make paths {} in {transaction_target}",
+            if executable_bit {
+                "executable"
+            } else {
+                "non-executable"
+            },
+        ),
+    )
+}
+
+/// Collects the entries in `tree` matching `matcher`, which must all be files
+/// or conflicts of files.
+fn collect_entries(
+    workspace_command: &WorkspaceCommandHelper,
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+) -> Result<Vec<(RepoPathBuf, MergedTreeValue)>, CommandError> {
+    let mut entries = vec![];
+    for (repo_path, result) in tree.entries_matching(matcher) {
         let tree_value = result?;
         let user_error_with_path = |msg: &str| {
             user_error(format!(
                 "{msg} at '{}'.",
-                tx.base_workspace_helper().format_file_path(&repo_path)
+                workspace_command.format_file_path(&repo_path)
             ))
         };
         let all_files = tree_value
@@ -108,34 +290,65 @@ pub(crate) fn cmd_file_chmod(
             };
             return Err(user_error_with_path(message));
         }
-        let new_tree_value = tree_value.map(|value| match value {
-            Some(TreeValue::File { id, executable: _ }) => Some(TreeValue::File {
-                id: id.clone(),
-                executable: executable_bit,
-            }),
-            Some(TreeValue::Conflict(_)) => {
-                panic!("Conflict sides must not themselves be conflicts")
-            }
-            value => value.clone(),
-        });
-        tree_builder.set_or_remove(repo_path, new_tree_value);
+        entries.push((repo_path, tree_value));
     }
+    Ok(entries)
+}
 
-    let new_tree_id = tree_builder.write_tree(store)?;
-    tx.mut_repo()
-        .rewrite_commit(command.settings(), &commit)
-        .set_tree_id(new_tree_id)
-        .write()?;
-    tx.finish(
-        ui,
-        format!(
-            "make paths {} in commit {}",
-            if executable_bit {
-                "executable"
-            } else {
-                "non-executable"
-            },
-            commit.id().hex(),
-        ),
-    )
+fn choose_entries(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    entries: Vec<(RepoPathBuf, MergedTreeValue)>,
+) -> Result<Vec<(RepoPathBuf, MergedTreeValue)>, CommandError> {
+    writeln!(ui.stdout(), "matched files:")?;
+    for (i, (repo_path, tree_value)) in entries.iter().enumerate() {
+        let bits: HashSet<bool> = tree_value
+            .adds()
+            .flatten()
+            .map(|value| {
+                matches!(
+                    value,
+                    TreeValue::File {
+                        executable: true,
+                        ..
+                    }
+                )
+            })
+            .collect();
+        let mode = match bits.into_iter().exactly_one() {
+            Ok(true) => "x",
+            Ok(false) => "n",
+            Err(_) => "mixed",
+        };
+        writeln!(
+            ui.stdout(),
+            "{}: {mode} {}",
+            i + 1,
+            workspace_command.format_file_path(repo_path)
+        )?;
+    }
+    let mut choices = (1..=entries.len()).map(|i| i.to_string()).collect_vec();
+    writeln!(ui.stdout(), "q: quit the prompt")?;
+    choices.push("q".to_string());
+
+    let selected = ui.prompt_choices(
+        "enter the indices of the files to change (empty for all)",
+        &choices,
+    )?;
+    if selected.iter().any(|choice| choice == "q") {
+        return Err(user_error("No files selected"));
+    }
+    if selected.is_empty() {
+        return Ok(entries);
+    }
+    let indices: HashSet<usize> = selected
+        .iter()
+        .map(|choice| choice.parse::<usize>().unwrap() - 1)
+        .collect();
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| indices.contains(i))
+        .map(|(_, entry)| entry)
+        .collect())
 }
@@ -29,6 +29,9 @@ pub(crate) struct FileListArgs {
     /// Only list files matching these prefixes (instead of all files)
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Print the number of matching files instead of their paths
+    #[arg(long)]
+    count: bool,
 }
 
 #[instrument(skip_all)]
@@ -57,9 +60,18 @@ pub(crate) fn cmd_file_list(
     let workspace_command = command.workspace_helper(ui)?;
     let commit = workspace_command.resolve_single_rev(&args.revision)?;
     let tree = commit.tree()?;
-    let matcher = workspace_command
-        .parse_file_patterns(&args.paths)?
-        .to_matcher();
+    let fileset_expression = workspace_command.parse_file_patterns(&args.paths)?;
+    let matcher = fileset_expression.to_matcher();
+    if args.count {
+        // Don't walk the tree if the fileset can't match any path.
+        let count = if fileset_expression.covering_prefixes() == Some(vec![]) {
+            0
+        } else {
+            tree.entries_matching(matcher.as_ref()).count()
+        };
+        writeln!(ui.stdout(), "{count}")?;
+        return Ok(());
+    }
     ui.request_pager();
     for (name, _value) in tree.entries_matching(matcher.as_ref()) {
         writeln!(
@@ -16,7 +16,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
-use jj_lib::git::{self, GitFetchError, GitFetchStats};
+use jj_lib::git::{self, GitFetchError, GitFetchStats, GitFetchTags};
 use jj_lib::repo::Repo;
 use jj_lib::str_util::StringPattern;
 use jj_lib::workspace::Workspace;
@@ -191,6 +191,7 @@ fn do_git_clone(
             &git_repo,
             remote_name,
             &[StringPattern::everything()],
+            GitFetchTags::Auto,
             cb,
             &command.settings().git_settings(),
         )
@@ -13,8 +13,8 @@
 // limitations under the License.
 
 use itertools::Itertools;
-use jj_lib::git::{self, GitFetchError};
-use jj_lib::repo::Repo;
+use jj_lib::git::{self, GitFetchError, GitFetchTags};
+use jj_lib::repo::{MutableRepo, Repo};
 use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use jj_lib::str_util::StringPattern;
 
@@ -28,6 +28,10 @@ use crate::ui::Ui;
 ///
 /// If a working-copy commit gets abandoned, it will be given a new, empty
 /// commit. This is true in general; it is not specific to this command.
+///
+/// When fetching from multiple remotes, a remote that can't be fetched from
+/// doesn't prevent fetching from the other remotes. The command still fails
+/// after updating the branches from the other remotes.
 #[derive(clap::Args, Clone, Debug)]
 pub struct GitFetchArgs {
     /// Fetch only some of the branches
@@ -43,6 +47,25 @@ pub struct GitFetchArgs {
     /// Fetch from all remotes
     #[arg(long, conflicts_with = "remotes")]
     all_remotes: bool,
+    /// Track the default branch of a remote when fetching from it for the
+    /// first time
+    ///
+    /// The default branch is the branch pointed to by the remote's `HEAD`. A
+    /// local branch is created for it unless one already exists.
+    #[arg(long)]
+    track_default_branch: bool,
+    /// Track the fetched remote branches matching the given pattern
+    ///
+    /// A local branch is created for each matching remote branch that isn't
+    /// tracked yet, as if `jj branch track` were run. By default, the
+    /// specified name matches exactly. Use `glob:` prefix to select branches
+    /// by wildcard pattern.
+    #[arg(long, value_name = "BRANCH", value_parser = StringPattern::parse)]
+    track: Vec<StringPattern>,
+    /// Don't fetch tags from the remote, nor import tags from the underlying
+    /// Git repo
+    #[arg(long)]
+    no_tags: bool,
 }
 
 #[tracing::instrument(skip(ui, command))]
@@ -61,45 +84,137 @@ pub fn cmd_git_fetch(
         args.remotes.clone()
     };
     let mut tx = workspace_command.start_transaction();
+    let mut failed_remotes = vec![];
     for remote in &remotes {
-        let stats = with_remote_git_callbacks(ui, None, |cb| {
+        let is_new_remote = tx.repo().view().remote_branches(remote).next().is_none();
+        let result = with_remote_git_callbacks(ui, None, |cb| {
             git::fetch(
                 tx.mut_repo(),
                 &git_repo,
                 remote,
                 &args.branch,
+                if args.no_tags {
+                    GitFetchTags::Skip
+                } else {
+                    GitFetchTags::Auto
+                },
                 cb,
                 &command.settings().git_settings(),
             )
-        })
-        .map_err(|err| match err {
-            GitFetchError::InvalidBranchPattern => {
-                if args
-                    .branch
-                    .iter()
-                    .any(|pattern| pattern.as_exact().map_or(false, |s| s.contains('*')))
-                {
-                    user_error_with_hint(
-                        err,
-                        "Prefix the pattern with `glob:` to expand `*` as a glob",
-                    )
-                } else {
-                    user_error(err)
-                }
+        });
+        let stats = match result {
+            Ok(stats) => stats,
+            // A remote that can't be fetched from (e.g. because it's unreachable)
+            // shouldn't prevent fetching from the other remotes.
+            Err(GitFetchError::InternalGitError(err)) if remotes.len() > 1 => {
+                writeln!(
+                    ui.warning_default(),
+                    "Failed to fetch from remote {remote}: {err}"
+                )?;
+                failed_remotes.push(remote);
+                continue;
             }
-            GitFetchError::GitImportError(err) => err.into(),
-            GitFetchError::InternalGitError(err) => map_git_error(err),
-            _ => user_error(err),
-        })?;
+            Err(err) => return Err(map_fetch_error(err, &args.branch)),
+        };
         print_git_import_stats(ui, tx.repo(), &stats.import_stats, true)?;
+        if args.track_default_branch && is_new_remote {
+            // The default branch is unknown if the remote's HEAD is detached or
+            // points to an unborn branch.
+            if let Some(default_branch) = &stats.default_branch {
+                track_default_branch(ui, tx.mut_repo(), default_branch, remote)?;
+            }
+        }
+        if remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+            track_matching_branches(ui, tx.mut_repo(), &args.track, remote)?;
+        }
+    }
+    if failed_remotes.len() < remotes.len() {
+        let fetched_remotes = remotes
+            .iter()
+            .filter(|remote| !failed_remotes.contains(remote))
+            .join(",");
+        tx.finish(ui, format!("fetch from git remote(s) {fetched_remotes}"))?;
+    }
+    if !failed_remotes.is_empty() {
+        return Err(user_error(format!(
+            "Failed to fetch from remote(s) {}",
+            failed_remotes.iter().join(",")
+        )));
+    }
+    Ok(())
+}
+
+fn track_default_branch(
+    ui: &Ui,
+    mut_repo: &mut MutableRepo,
+    branch_name: &str,
+    remote_name: &str,
+) -> Result<(), CommandError> {
+    let view = mut_repo.view();
+    let remote_ref = view.get_remote_branch(branch_name, remote_name);
+    if remote_ref.is_absent()
+        || remote_ref.is_tracking()
+        || view.get_local_branch(branch_name).is_present()
+    {
+        return Ok(());
     }
-    tx.finish(
-        ui,
-        format!("fetch from git remote(s) {}", remotes.iter().join(",")),
+    mut_repo.track_remote_branch(branch_name, remote_name);
+    writeln!(
+        ui.status(),
+        "Started tracking the default branch of remote {remote_name}: {branch_name}"
     )?;
     Ok(())
 }
 
+fn track_matching_branches(
+    ui: &Ui,
+    mut_repo: &mut MutableRepo,
+    branch_patterns: &[StringPattern],
+    remote_name: &str,
+) -> Result<(), CommandError> {
+    let remote_pattern = StringPattern::exact(remote_name);
+    let branch_names = branch_patterns
+        .iter()
+        .flat_map(|pattern| {
+            mut_repo
+                .view()
+                .remote_branches_matching(pattern, &remote_pattern)
+        })
+        .filter(|(_, remote_ref)| remote_ref.is_present() && !remote_ref.is_tracking())
+        .map(|((branch_name, _), _)| branch_name.to_owned())
+        .unique()
+        .collect_vec();
+    for branch_name in &branch_names {
+        mut_repo.track_remote_branch(branch_name, remote_name);
+        writeln!(
+            ui.status(),
+            "Started tracking remote branch {branch_name}@{remote_name}"
+        )?;
+    }
+    Ok(())
+}
+
+fn map_fetch_error(err: GitFetchError, branch_patterns: &[StringPattern]) -> CommandError {
+    match err {
+        GitFetchError::InvalidBranchPattern => {
+            if branch_patterns
+                .iter()
+                .any(|pattern| pattern.as_exact().map_or(false, |s| s.contains('*')))
+            {
+                user_error_with_hint(
+                    err,
+                    "Prefix the pattern with `glob:` to expand `*` as a glob",
+                )
+            } else {
+                user_error(err)
+            }
+        }
+        GitFetchError::GitImportError(err) => err.into(),
+        GitFetchError::InternalGitError(err) => map_git_error(err),
+        _ => user_error(err),
+    }
+}
+
 const DEFAULT_REMOTE: &str = "origin";
 
 fn get_default_fetch_remotes(
@@ -380,6 +380,21 @@ pub fn cmd_git_push(
             "Try fetching from the remote, then make the branch point to where you want it to be, \
              and push again.",
         ),
+        GitPushError::RefUpdateRejected(ref rejections) => {
+            let hints = rejections
+                .iter()
+                .filter_map(|rejection| {
+                    let reason = rejection.reason.as_ref()?;
+                    Some(format!(
+                        "The remote rejected {}: {reason}",
+                        rejection.qualified_name
+                    ))
+                })
+                .collect_vec();
+            let mut cmd_err = user_error(err);
+            cmd_err.extend_hints(hints);
+            cmd_err
+        }
         _ => user_error(err),
     })?;
     writer.flush(ui)?;
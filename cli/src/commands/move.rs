@@ -16,7 +16,7 @@ use clap::ArgGroup;
 use jj_lib::object_id::ObjectId;
 use tracing::instrument;
 
-use super::squash::{move_diff, SquashedDescription};
+use super::squash::{move_diff, MoveDiffOptions, SourceMatchers, SquashedDescription};
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::{user_error, CommandError};
 use crate::ui::Ui;
@@ -95,11 +95,14 @@ pub(crate) fn cmd_move(
         command.settings(),
         &[source],
         &destination,
-        matcher.as_ref(),
+        &SourceMatchers::new(matcher),
         &diff_selector,
         SquashedDescription::Combine,
-        false,
-        &args.paths,
+        &MoveDiffOptions {
+            keep_emptied: false,
+            source_predecessors: true,
+        },
+        &[],
     )?;
     tx.finish(ui, tx_description)?;
     Ok(())
@@ -13,14 +13,20 @@
 // limitations under the License.
 
 use std::io::Write;
+use std::rc::Rc;
 
 use itertools::Itertools;
+use jj_lib::backend::CommitId;
 use jj_lib::commit::Commit;
+use jj_lib::matchers::Matcher;
 use jj_lib::repo::Repo;
 use jj_lib::revset::{RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
+use jj_lib::working_copy::CheckoutOptions;
 
-use crate::cli_util::{short_commit_hash, CommandHelper, WorkspaceCommandHelper};
-use crate::command_error::{user_error, CommandError};
+use crate::cli_util::{
+    short_commit_hash, CommandHelper, ConflictMarkerStyleArg, RevisionArg, WorkspaceCommandHelper,
+};
+use crate::command_error::{user_error, CommandError, CommandErrorKind};
 use crate::ui::Ui;
 
 /// Move the working-copy commit to the child revision
@@ -53,6 +59,11 @@ use crate::ui::Ui;
 /// ```
 /// If your working-copy commit already has visible children, then `--edit` is
 /// implied.
+///
+/// An offset of 0 stays at the current position: the parent of the working
+/// copy revision is edited if `--edit` is passed, or else a new working copy
+/// revision is created on top of it. If `--edit` is implied, the working copy
+/// revision is left as is.
 #[derive(clap::Args, Clone, Debug)]
 #[command(verbatim_doc_comment)]
 pub(crate) struct NextArgs {
@@ -68,27 +79,172 @@ pub(crate) struct NextArgs {
     /// Jump to the next conflicted descendant.
     #[arg(long, conflicts_with = "offset")]
     conflict: bool,
+    /// Jump to the next descendant with conflicts in the given paths.
+    #[arg(
+        long,
+        value_name = "FILESETS",
+        conflicts_with_all = ["offset", "conflict"]
+    )]
+    conflict_in: Vec<String>,
+    /// Print the numbered list of candidate commits instead of moving to one
+    /// of them.
+    #[arg(long, conflicts_with = "edit")]
+    list: bool,
+    /// Stop early at the first commit matching this revset.
+    ///
+    /// The offset is the maximum number of revisions to move forward.
+    #[arg(long, value_name = "REVSET", conflicts_with_all = ["conflict", "conflict_in"])]
+    stop_at: Option<RevisionArg>,
+    /// Don't snapshot the working copy before moving.
+    ///
+    /// This makes the command faster, but any changes in the working copy
+    /// since the last snapshot may be lost.
+    #[arg(long)]
+    no_snapshot: bool,
+    /// Style of the conflict markers in the working copy
+    ///
+    /// Conflicted files in the target commit are written to the working copy
+    /// with conflict markers of this style.
+    #[arg(long, value_enum, default_value_t = ConflictMarkerStyleArg::Diff)]
+    conflict_marker_style: ConflictMarkerStyleArg,
 }
 
-pub fn choose_commit<'a>(
-    ui: &mut Ui,
+/// Narrows down the `candidates` to the commits which have a conflict in a
+/// path matching `matcher`.
+pub(crate) fn conflicted_in(
     workspace_command: &WorkspaceCommandHelper,
-    cmd: &str,
-    commits: &'a [Commit],
-) -> Result<&'a Commit, CommandError> {
-    writeln!(ui.stdout(), "ambiguous {cmd} commit, choose one to target:")?;
+    candidates: Rc<RevsetExpression>,
+    matcher: &dyn Matcher,
+) -> Result<Rc<RevsetExpression>, CommandError> {
+    let repo = workspace_command.repo();
+    let mut commit_ids = vec![];
+    for commit in candidates
+        .filtered(RevsetFilterPredicate::HasConflict)
+        .evaluate_programmatic(repo.as_ref())?
+        .iter()
+        .commits(repo.store())
+    {
+        let commit = commit?;
+        if commit
+            .tree()?
+            .conflicts()
+            .any(|(path, _)| matcher.matches(&path))
+        {
+            commit_ids.push(commit.id().clone());
+        }
+    }
+    Ok(RevsetExpression::commits(commit_ids))
+}
+
+/// Returns the commits the working copy is currently positioned at, which are
+/// targeted by an offset of 0.
+///
+/// If the working-copy commit is a head, it's a new commit on top of its
+/// parents, so they are the current position. Otherwise, the working-copy
+/// commit is being edited.
+pub(crate) fn stay_revset(wc_revset: &Rc<RevsetExpression>, is_head: bool) -> Rc<RevsetExpression> {
+    if is_head {
+        wc_revset.parents()
+    } else {
+        wc_revset.clone()
+    }
+}
+
+/// Evaluates the `--stop-at` revset once, returning the commits at which
+/// `jj next` or `jj prev` stops early.
+pub(crate) fn resolve_stop_at(
+    workspace_command: &WorkspaceCommandHelper,
+    stop_at: Option<&RevisionArg>,
+) -> Result<Option<Vec<CommitId>>, CommandError> {
+    let Some(stop_at) = stop_at else {
+        return Ok(None);
+    };
+    let commit_ids = workspace_command
+        .parse_revset(stop_at)?
+        .evaluate_to_commit_ids()?
+        .collect();
+    Ok(Some(commit_ids))
+}
+
+/// Tells the user that `target` was reached early because it matched
+/// `--stop-at`.
+pub(crate) fn write_stopped_at(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    stop_ids: Option<&[CommitId]>,
+    target: &Commit,
+) -> Result<(), CommandError> {
+    if !stop_ids.is_some_and(|ids| ids.contains(target.id())) {
+        return Ok(());
+    }
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Stopped at commit matching --stop-at: ")?;
+        workspace_command.write_commit_summary(formatter.as_mut(), target)?;
+        writeln!(formatter)?;
+    }
+    Ok(())
+}
+
+/// Checks that `target` is rewritable before `jj next` or `jj prev` edits it.
+///
+/// The error names the commit being edited. If `--edit` was passed
+/// explicitly, it also hints that the command can create a new working-copy
+/// commit on top of `target` instead.
+pub(crate) fn check_editable(
+    workspace_command: &WorkspaceCommandHelper,
+    target: &Commit,
+    command_name: &str,
+    explicit_edit: bool,
+) -> Result<(), CommandError> {
+    workspace_command
+        .check_rewritable([target.id()])
+        .map_err(|err| {
+            if err.kind != CommandErrorKind::User {
+                return err;
+            }
+            let mut new_err = user_error(format!(
+                "Cannot edit immutable commit {}",
+                short_commit_hash(target.id())
+            ));
+            new_err.hints = err.hints;
+            if explicit_edit {
+                new_err.add_hint(format!(
+                    "Run `jj {command_name}` without `--edit` to create a new working-copy \
+                     commit on top of it instead."
+                ));
+            }
+            new_err
+        })
+}
+
+/// Prints the numbered list of candidate `commits` as shown by
+/// [`choose_commit()`].
+pub(crate) fn write_commit_choices(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    commits: &[Commit],
+) -> Result<(), CommandError> {
     let mut formatter = ui.stdout_formatter();
     let template = workspace_command.commit_summary_template();
-    let mut choices: Vec<String> = Default::default();
     for (i, commit) in commits.iter().enumerate() {
         write!(formatter, "{}: ", i + 1)?;
         template.format(commit, formatter.as_mut())?;
         writeln!(formatter)?;
-        choices.push(format!("{}", i + 1));
     }
-    writeln!(formatter, "q: quit the prompt")?;
+    Ok(())
+}
+
+pub fn choose_commit<'a>(
+    ui: &mut Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    cmd: &str,
+    commits: &'a [Commit],
+) -> Result<&'a Commit, CommandError> {
+    writeln!(ui.stdout(), "ambiguous {cmd} commit, choose one to target:")?;
+    write_commit_choices(ui, workspace_command, commits)?;
+    let mut choices = (1..=commits.len()).map(|i| i.to_string()).collect_vec();
+    writeln!(ui.stdout(), "q: quit the prompt")?;
     choices.push("q".to_string());
-    drop(formatter);
 
     let choice = ui.prompt_choice(
         "enter the index of the commit you want to target",
@@ -107,16 +263,20 @@ pub(crate) fn cmd_next(
     command: &CommandHelper,
     args: &NextArgs,
 ) -> Result<(), CommandError> {
-    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut workspace_command = if args.no_snapshot {
+        command.workspace_helper_no_snapshot(ui)?
+    } else {
+        command.workspace_helper(ui)?
+    };
     let current_wc_id = workspace_command
         .get_wc_commit_id()
         .ok_or_else(|| user_error("This command requires a working copy"))?;
-    let edit = args.edit
-        || !workspace_command
-            .repo()
-            .view()
-            .heads()
-            .contains(current_wc_id);
+    let is_head = workspace_command
+        .repo()
+        .view()
+        .heads()
+        .contains(current_wc_id);
+    let edit = args.edit || !is_head;
     let wc_revset = RevsetExpression::commit(current_wc_id.clone());
     // If we're editing, start at the working-copy commit. Otherwise, start from
     // its direct parent(s).
@@ -126,16 +286,43 @@ pub(crate) fn cmd_next(
         wc_revset.parents()
     };
 
-    let target_revset = if args.conflict {
+    let stop_ids = resolve_stop_at(&workspace_command, args.stop_at.as_ref())?;
+    let target_revset = if !args.conflict_in.is_empty() {
+        let matcher = workspace_command
+            .parse_file_patterns(&args.conflict_in)?
+            .to_matcher();
+        conflicted_in(
+            &workspace_command,
+            start_revset.children().descendants(),
+            matcher.as_ref(),
+        )?
+        .roots()
+        .minus(&wc_revset)
+    } else if args.conflict {
         start_revset
             .children()
             .descendants()
             .filtered(RevsetFilterPredicate::HasConflict)
             .roots()
+            .minus(&wc_revset)
+    } else if args.offset == 0 {
+        stay_revset(&wc_revset, is_head)
+    } else if let Some(stop_ids) = &stop_ids {
+        // Each path forward ends at its first commit matching the guard, or
+        // at the offset if there is none.
+        let stops = start_revset
+            .descendants_range(1..args.offset + 1)
+            .minus(&wc_revset)
+            .intersection(&RevsetExpression::commits(stop_ids.clone()))
+            .roots();
+        start_revset
+            .descendants_at(args.offset)
+            .minus(&stops.descendants())
+            .union(&stops)
+            .minus(&wc_revset)
     } else {
-        start_revset.descendants_at(args.offset)
-    }
-    .minus(&wc_revset);
+        start_revset.descendants_at(args.offset).minus(&wc_revset)
+    };
 
     let targets: Vec<Commit> = target_revset
         .evaluate_programmatic(workspace_command.repo().as_ref())?
@@ -143,8 +330,17 @@ pub(crate) fn cmd_next(
         .commits(workspace_command.repo().store())
         .try_collect()?;
 
+    if args.list && !targets.is_empty() {
+        write_commit_choices(ui, &workspace_command, &targets)?;
+        return Ok(());
+    }
     let target = match targets.as_slice() {
         [target] => target,
+        [] if !args.conflict_in.is_empty() => {
+            return Err(user_error(
+                "No descendant found with conflicts in the given paths",
+            ));
+        }
         [] => {
             // We found no descendant.
             return Err(user_error(format!(
@@ -155,23 +351,32 @@ pub(crate) fn cmd_next(
         }
         commits => choose_commit(ui, &workspace_command, "next", commits)?,
     };
+    write_stopped_at(ui, &workspace_command, stop_ids.as_deref(), target)?;
     let current_short = short_commit_hash(current_wc_id);
     let target_short = short_commit_hash(target.id());
+    let checkout_options = CheckoutOptions {
+        conflict_marker_style: args.conflict_marker_style.into(),
+    };
     // We're editing, just move to the target commit.
     if edit {
         // We're editing, the target must be rewritable.
-        workspace_command.check_rewritable([target.id()])?;
+        check_editable(&workspace_command, target, "next", args.edit)?;
         let mut tx = workspace_command.start_transaction();
         tx.edit(target)?;
-        tx.finish(
+        tx.finish_with_checkout_options(
             ui,
             format!("next: {current_short} -> editing {target_short}"),
+            &checkout_options,
         )?;
         return Ok(());
     }
     let mut tx = workspace_command.start_transaction();
     // Move the working-copy commit to the new parent.
     tx.check_out(target)?;
-    tx.finish(ui, format!("next: {current_short} -> {target_short}"))?;
+    tx.finish_with_checkout_options(
+        ui,
+        format!("next: {current_short} -> {target_short}"),
+        &checkout_options,
+    )?;
     Ok(())
 }
@@ -12,20 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
+use std::slice;
+
 use itertools::Itertools;
+use jj_lib::backend::{BackendResult, CommitId};
 use jj_lib::commit::Commit;
 use jj_lib::dag_walk::topo_order_reverse_ok;
-use jj_lib::matchers::EverythingMatcher;
-use jj_lib::repo::Repo;
+use jj_lib::matchers::Matcher;
+use jj_lib::op_store::OperationId;
+use jj_lib::op_walk;
+use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::revset::RevsetExpression;
 use jj_lib::rewrite::rebase_to_dest_parent;
 use tracing::instrument;
 
-use crate::cli_util::{format_template, CommandHelper, LogContentFormat, RevisionArg};
-use crate::command_error::CommandError;
+use crate::cli_util::{
+    format_template, short_operation_hash, CommandHelper, ConflictMarkerStyleArg, LogContentFormat,
+    RevisionArg, WorkspaceCommandHelper,
+};
+use crate::command_error::{user_error, CommandError};
 use crate::commit_templater::CommitTemplateLanguage;
-use crate::diff_util::{DiffFormatArgs, DiffRenderer};
+use crate::diff_util::{DiffFormatArgs, DiffRenderError, DiffRenderer};
 use crate::formatter::Formatter;
 use crate::graphlog::{get_graphlog, Edge};
+use crate::merge_tools::{self, ExternalMergeTool};
 use crate::ui::Ui;
 
 /// Show how a change has evolved over time
@@ -33,11 +44,15 @@ use crate::ui::Ui;
 /// Lists the previous commits which a change has pointed to. The current commit
 /// of a change evolves when the change is updated, rebased, etc.
 ///
+/// If several revisions are given, their histories are shown together in one
+/// graph.
+///
 /// Name is derived from Merciual's obsolescence markers.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct ObslogArgs {
-    #[arg(long, short, default_value = "@")]
-    revision: RevisionArg,
+    /// Show the evolution of these revisions
+    #[arg(long, short, default_value = "@", alias = "revision")]
+    revisions: Vec<RevisionArg>,
     /// Limit number of revisions to show
     #[arg(long, short = 'n')]
     limit: Option<usize>,
@@ -64,6 +79,43 @@ pub(crate) struct ObslogArgs {
     /// contaminated by unrelated changes.
     #[arg(long, short = 'p')]
     patch: bool,
+    /// Show the id of the operation that created each version
+    ///
+    /// This has to check the view at each operation, so it may be slow if the
+    /// operation log is long.
+    #[arg(long)]
+    op_id: bool,
+    /// Collapse versions that only differ from the next version in metadata
+    ///
+    /// Versions with the same tree and description as the version that
+    /// replaced them (e.g. after `jj describe --reset-author`) are not shown.
+    /// Instead, the newest of them is annotated with the number of collapsed
+    /// versions.
+    #[arg(long)]
+    hide_noop: bool,
+    /// Style of the conflict markers in the patch
+    ///
+    /// Conflicted files are shown with conflict markers of this style.
+    #[arg(long, value_enum, default_value_t = ConflictMarkerStyleArg::Diff)]
+    conflict_marker_style: ConflictMarkerStyleArg,
+    /// Open the changes of each version in the given external diff tool
+    /// instead of showing the log
+    ///
+    /// The tool is run interactively once for every version that has a
+    /// previous version, newest first.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = [
+            "patch", "summary", "stat", "types", "name_only", "git", "color_words", "tool"
+        ]
+    )]
+    diff_tool: Option<String>,
+    /// Show only changes to these paths in the patch (instead of all paths)
+    ///
+    /// Implies `--patch` if no diff format is specified.
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
     #[command(flatten)]
     diff_format: DiffFormatArgs,
 }
@@ -77,9 +129,21 @@ pub(crate) fn cmd_obslog(
     let workspace_command = command.workspace_helper(ui)?;
     let repo = workspace_command.repo().as_ref();
 
-    let start_commit = workspace_command.resolve_single_rev(&args.revision)?;
+    let start_commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(&args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    // Histories of separate changes would otherwise be rendered as one chain.
+    let terminate_chains = start_commits.len() > 1;
 
-    let diff_renderer = workspace_command.diff_renderer_for_log(&args.diff_format, args.patch)?;
+    // Paths would otherwise be silently ignored, so they imply --patch.
+    let patch = args.patch || (args.diff_tool.is_none() && !args.paths.is_empty());
+    let diff_renderer = workspace_command
+        .diff_renderer_for_log(&args.diff_format, patch)?
+        .map(|renderer| renderer.with_conflict_marker_style(args.conflict_marker_style.into()));
+    let matcher = workspace_command
+        .parse_file_patterns(&args.paths)?
+        .to_matcher();
     let with_content_format = LogContentFormat::new(ui, command.settings())?;
 
     let template;
@@ -106,12 +170,8 @@ pub(crate) fn cmd_obslog(
             .labeled("node");
     }
 
-    ui.request_pager();
-    let mut formatter = ui.stdout_formatter();
-    let formatter = formatter.as_mut();
-
     let mut commits = topo_order_reverse_ok(
-        vec![Ok(start_commit)],
+        start_commits.into_iter().map(Ok),
         |commit: &Commit| commit.id().clone(),
         |commit: &Commit| commit.predecessors().collect_vec(),
     )?;
@@ -121,28 +181,114 @@ pub(crate) fn cmd_obslog(
             "The -l shorthand is deprecated, use -n instead."
         )?;
     }
+    let collapsed_rewrites = if args.hide_noop {
+        collapse_noop_rewrites(&mut commits)?
+    } else {
+        HashMap::new()
+    };
     if let Some(n) = args.limit.or(args.deprecated_limit) {
         commits.truncate(n);
     }
+    if let Some(name) = &args.diff_tool {
+        if !Ui::can_prompt() {
+            return Err(user_error(
+                "Cannot open an external diff tool without a terminal",
+            ));
+        }
+        let tool = merge_tools::get_external_tool_config(command.settings(), name)?
+            .unwrap_or_else(|| ExternalMergeTool::with_program(name));
+        for commit in &commits {
+            let oldest = collapsed_rewrites
+                .get(commit.id())
+                .and_then(|rewrites| rewrites.last())
+                .unwrap_or(commit);
+            view_predecessor_diff(
+                ui,
+                &workspace_command,
+                &tool,
+                commit,
+                oldest,
+                matcher.as_ref(),
+            )?;
+        }
+        return Ok(());
+    }
+    let op_ids = if args.op_id {
+        let commit_ids = commits.iter().map(|commit| commit.id().clone()).collect();
+        find_creating_operations(workspace_command.repo(), commit_ids)?
+    } else {
+        HashMap::new()
+    };
+    let write_op_id = |formatter: &mut dyn Formatter, commit: &Commit| {
+        if !args.op_id {
+            return Ok(());
+        }
+        match op_ids.get(commit.id()) {
+            Some(op_id) => write!(
+                formatter.labeled("op_id"),
+                "{}",
+                short_operation_hash(op_id)
+            )?,
+            None => write!(formatter.labeled("op_id"), "(unknown op)")?,
+        }
+        write!(formatter, " ")
+    };
+    // The version whose predecessors and patch are shown for `commit`
+    let oldest_rewrite = |commit: &Commit| {
+        collapsed_rewrites
+            .get(commit.id())
+            .and_then(|rewrites| rewrites.last())
+            .cloned()
+            .unwrap_or_else(|| commit.clone())
+    };
+    let write_collapsed_rewrites = |formatter: &mut dyn Formatter, commit: &Commit| {
+        match collapsed_rewrites.get(commit.id()) {
+            Some(rewrites) => writeln!(
+                formatter.labeled("collapsed_rewrites"),
+                "({} metadata-only rewrites)",
+                rewrites.len()
+            ),
+            None => Ok(()),
+        }
+    };
+
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
     if !args.no_graph {
         let mut graph = get_graphlog(command.settings(), formatter.raw());
         for commit in commits {
+            let oldest = oldest_rewrite(&commit);
             let mut edges = vec![];
-            for predecessor in commit.predecessors() {
+            for predecessor in oldest.predecessors() {
                 edges.push(Edge::Direct(predecessor?.id().clone()));
             }
+            if edges.is_empty() && terminate_chains {
+                edges.push(Edge::Missing);
+            }
             let mut buffer = vec![];
             with_content_format.write_graph_text(
                 ui.new_formatter(&mut buffer).as_mut(),
-                |formatter| template.format(&commit, formatter),
+                |formatter| {
+                    write_op_id(formatter, &commit)?;
+                    template.format(&commit, formatter)
+                },
                 || graph.width(commit.id(), &edges),
             )?;
             if !buffer.ends_with(b"\n") {
                 buffer.push(b'\n');
             }
+            write_collapsed_rewrites(ui.new_formatter(&mut buffer).as_mut(), &commit)?;
             if let Some(renderer) = &diff_renderer {
                 let mut formatter = ui.new_formatter(&mut buffer);
-                show_predecessor_patch(ui, repo, renderer, formatter.as_mut(), &commit)?;
+                show_predecessor_patch(
+                    ui,
+                    repo,
+                    renderer,
+                    formatter.as_mut(),
+                    &oldest,
+                    matcher.as_ref(),
+                )?;
             }
             let node_symbol = format_template(ui, &Some(commit.clone()), &node_template);
             graph.add_node(
@@ -154,14 +300,137 @@ pub(crate) fn cmd_obslog(
         }
     } else {
         for commit in commits {
-            with_content_format
-                .write(formatter, |formatter| template.format(&commit, formatter))?;
+            with_content_format.write(formatter, |formatter| {
+                write_op_id(formatter, &commit)?;
+                template.format(&commit, formatter)
+            })?;
+            write_collapsed_rewrites(formatter, &commit)?;
             if let Some(renderer) = &diff_renderer {
-                show_predecessor_patch(ui, repo, renderer, formatter, &commit)?;
+                let oldest = oldest_rewrite(&commit);
+                show_predecessor_patch(ui, repo, renderer, formatter, &oldest, matcher.as_ref())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes versions with the same tree and description as the version that
+/// replaced them from `commits`, which must be in reverse topological order.
+///
+/// Returns the removed versions keyed by the id of the version they were
+/// collapsed into, newest first. A version is only collapsed if it is the sole
+/// predecessor of exactly one version.
+fn collapse_noop_rewrites(
+    commits: &mut Vec<Commit>,
+) -> BackendResult<HashMap<CommitId, Vec<Commit>>> {
+    let mut successor_counts: HashMap<&CommitId, usize> = HashMap::new();
+    for commit in commits.iter() {
+        for predecessor_id in commit.predecessor_ids() {
+            *successor_counts.entry(predecessor_id).or_default() += 1;
+        }
+    }
+    let mut collapsed_rewrites = HashMap::new();
+    let mut collapsed_ids = HashSet::new();
+    for commit in commits.iter() {
+        if collapsed_ids.contains(commit.id()) {
+            continue;
+        }
+        let mut rewrites: Vec<Commit> = vec![];
+        while let [predecessor_id] = rewrites.last().unwrap_or(commit).predecessor_ids() {
+            if successor_counts.get(predecessor_id) != Some(&1) {
+                break;
+            }
+            let predecessor = commit.store().get_commit(predecessor_id)?;
+            if predecessor.tree_id() != commit.tree_id()
+                || predecessor.description() != commit.description()
+            {
+                break;
             }
+            collapsed_ids.insert(predecessor_id.clone());
+            rewrites.push(predecessor);
+        }
+        if !rewrites.is_empty() {
+            collapsed_rewrites.insert(commit.id().clone(), rewrites);
         }
     }
+    commits.retain(|commit| !collapsed_ids.contains(commit.id()));
+    Ok(collapsed_rewrites)
+}
 
+/// Finds the operation that created each of the `commit_ids`, which is the
+/// oldest operation whose view makes the commit visible.
+///
+/// The views are checked against the index of the current operation, which
+/// contains the commits of all ancestor operations, so this works across
+/// concurrent operations without reloading the index at each operation.
+/// Commits that were never visible (e.g. because they were rewritten by the
+/// same operation that created them) are left out.
+///
+/// Once all the commits have been found, only the parents of the operations
+/// in which some of them were visible are checked, so the walk stops at the
+/// operations before the commits were created.
+fn find_creating_operations(
+    repo: &ReadonlyRepo,
+    commit_ids: Vec<CommitId>,
+) -> Result<HashMap<CommitId, OperationId>, CommandError> {
+    let num_commits = commit_ids.len();
+    let commits_expression = RevsetExpression::commits(commit_ids);
+    let mut op_ids = HashMap::new();
+    let mut pending_op_ids = HashSet::new();
+    // Operations are visited in reverse topological order, so the last one
+    // seen for each commit is the oldest.
+    for op in op_walk::walk_ancestors(slice::from_ref(repo.operation())) {
+        let op = op?;
+        let is_pending = pending_op_ids.remove(op.id());
+        if op_ids.len() == num_commits && !is_pending {
+            if pending_op_ids.is_empty() {
+                break;
+            }
+            continue;
+        }
+        let view = op.view()?;
+        let heads_expression = RevsetExpression::commits(view.heads().iter().cloned().collect());
+        let visible_ids = commits_expression
+            .intersection(&heads_expression.ancestors())
+            .evaluate_programmatic(repo)?
+            .iter()
+            .collect_vec();
+        if !visible_ids.is_empty() {
+            pending_op_ids.extend(op.parent_ids().iter().cloned());
+        }
+        for id in visible_ids {
+            op_ids.insert(id, op.id().clone());
+        }
+    }
+    Ok(op_ids)
+}
+
+/// Opens the changes from the predecessor of `oldest` to `commit` in `tool`.
+/// `oldest` is the oldest of the versions collapsed into `commit`, or `commit`
+/// itself.
+fn view_predecessor_diff(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    tool: &ExternalMergeTool,
+    commit: &Commit,
+    oldest: &Commit,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let Some(predecessor) = oldest.predecessors().next() else {
+        return Ok(());
+    };
+    let predecessor = predecessor?;
+    let predecessor_tree =
+        rebase_to_dest_parent(workspace_command.repo().as_ref(), &predecessor, commit)?;
+    let tree = commit.tree()?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Opening changes in ")?;
+        workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+        writeln!(formatter)?;
+    }
+    merge_tools::view_diff(ui, &predecessor_tree, &tree, matcher, tool)
+        .map_err(DiffRenderError::DiffGenerate)?;
     Ok(())
 }
 
@@ -171,6 +440,7 @@ fn show_predecessor_patch(
     renderer: &DiffRenderer,
     formatter: &mut dyn Formatter,
     commit: &Commit,
+    matcher: &dyn Matcher,
 ) -> Result<(), CommandError> {
     let mut predecessors = commit.predecessors();
     let predecessor = match predecessors.next() {
@@ -179,6 +449,6 @@ fn show_predecessor_patch(
     };
     let predecessor_tree = rebase_to_dest_parent(repo, &predecessor, commit)?;
     let tree = commit.tree()?;
-    renderer.show_diff(ui, formatter, &predecessor_tree, &tree, &EverythingMatcher)?;
+    renderer.show_diff(ui, formatter, &predecessor_tree, &tree, matcher)?;
     Ok(())
 }
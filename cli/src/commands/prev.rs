@@ -15,10 +15,14 @@
 use itertools::Itertools;
 use jj_lib::repo::Repo;
 use jj_lib::revset::{RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
+use jj_lib::working_copy::CheckoutOptions;
 
-use crate::cli_util::{short_commit_hash, CommandHelper};
+use crate::cli_util::{short_commit_hash, CommandHelper, ConflictMarkerStyleArg, RevisionArg};
 use crate::command_error::{user_error, CommandError};
-use crate::commands::next::choose_commit;
+use crate::commands::next::{
+    check_editable, choose_commit, conflicted_in, resolve_stop_at, stay_revset,
+    write_commit_choices, write_stopped_at,
+};
 use crate::ui::Ui;
 /// Change the working copy revision relative to the parent revision
 ///
@@ -50,6 +54,8 @@ use crate::ui::Ui;
 /// ```
 /// If the working copy revision already has visible children, then `--edit` is
 /// implied.
+///
+/// An offset of 0 stays at the current position in the same way as `jj next 0`.
 #[derive(clap::Args, Clone, Debug)]
 #[command(verbatim_doc_comment)]
 pub(crate) struct PrevArgs {
@@ -62,6 +68,34 @@ pub(crate) struct PrevArgs {
     /// Jump to the previous conflicted ancestor.
     #[arg(long, conflicts_with = "offset")]
     conflict: bool,
+    /// Jump to the previous ancestor with conflicts in the given paths.
+    #[arg(
+        long,
+        value_name = "FILESETS",
+        conflicts_with_all = ["offset", "conflict"]
+    )]
+    conflict_in: Vec<String>,
+    /// Print the numbered list of candidate commits instead of moving to one
+    /// of them.
+    #[arg(long, conflicts_with = "edit")]
+    list: bool,
+    /// Stop early at the first commit matching this revset.
+    ///
+    /// The offset is the maximum number of revisions to move backward.
+    #[arg(long, value_name = "REVSET", conflicts_with_all = ["conflict", "conflict_in"])]
+    stop_at: Option<RevisionArg>,
+    /// Don't snapshot the working copy before moving.
+    ///
+    /// This makes the command faster, but any changes in the working copy
+    /// since the last snapshot may be lost.
+    #[arg(long)]
+    no_snapshot: bool,
+    /// Style of the conflict markers in the working copy
+    ///
+    /// Conflicted files in the target commit are written to the working copy
+    /// with conflict markers of this style.
+    #[arg(long, value_enum, default_value_t = ConflictMarkerStyleArg::Diff)]
+    conflict_marker_style: ConflictMarkerStyleArg,
 }
 
 pub(crate) fn cmd_prev(
@@ -69,16 +103,20 @@ pub(crate) fn cmd_prev(
     command: &CommandHelper,
     args: &PrevArgs,
 ) -> Result<(), CommandError> {
-    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut workspace_command = if args.no_snapshot {
+        command.workspace_helper_no_snapshot(ui)?
+    } else {
+        command.workspace_helper(ui)?
+    };
     let current_wc_id = workspace_command
         .get_wc_commit_id()
         .ok_or_else(|| user_error("This command requires a working copy"))?;
-    let edit = args.edit
-        || !workspace_command
-            .repo()
-            .view()
-            .heads()
-            .contains(current_wc_id);
+    let is_head = workspace_command
+        .repo()
+        .view()
+        .heads()
+        .contains(current_wc_id);
+    let edit = args.edit || !is_head;
     let wc_revset = RevsetExpression::commit(current_wc_id.clone());
     // If we're editing, start at the working-copy commit. Otherwise, start from
     // its direct parent(s).
@@ -88,7 +126,18 @@ pub(crate) fn cmd_prev(
         wc_revset.parents()
     };
 
-    let target_revset = if args.conflict {
+    let stop_ids = resolve_stop_at(&workspace_command, args.stop_at.as_ref())?;
+    let target_revset = if !args.conflict_in.is_empty() {
+        let matcher = workspace_command
+            .parse_file_patterns(&args.conflict_in)?
+            .to_matcher();
+        conflicted_in(
+            &workspace_command,
+            start_revset.parents().ancestors(),
+            matcher.as_ref(),
+        )?
+        .heads()
+    } else if args.conflict {
         // If people desire to move to the root conflict, replace the `heads()` below
         // with `roots(). But let's wait for feedback.
         start_revset
@@ -96,6 +145,19 @@ pub(crate) fn cmd_prev(
             .ancestors()
             .filtered(RevsetFilterPredicate::HasConflict)
             .heads()
+    } else if args.offset == 0 {
+        stay_revset(&wc_revset, is_head)
+    } else if let Some(stop_ids) = &stop_ids {
+        // Each path backward ends at its first commit matching the guard, or
+        // at the offset if there is none.
+        let stops = start_revset
+            .ancestors_range(1..args.offset + 1)
+            .intersection(&RevsetExpression::commits(stop_ids.clone()))
+            .heads();
+        start_revset
+            .ancestors_at(args.offset)
+            .minus(&stops.ancestors())
+            .union(&stops)
     } else {
         start_revset.ancestors_at(args.offset)
     };
@@ -104,8 +166,17 @@ pub(crate) fn cmd_prev(
         .iter()
         .commits(workspace_command.repo().store())
         .try_collect()?;
+    if args.list && !targets.is_empty() {
+        write_commit_choices(ui, &workspace_command, &targets)?;
+        return Ok(());
+    }
     let target = match targets.as_slice() {
         [target] => target,
+        [] if !args.conflict_in.is_empty() => {
+            return Err(user_error(
+                "No ancestor found with conflicts in the given paths",
+            ))
+        }
         [] => {
             return Err(user_error(format!(
                 "No ancestor found {} commit{} back",
@@ -115,24 +186,33 @@ pub(crate) fn cmd_prev(
         }
         commits => choose_commit(ui, &workspace_command, "prev", commits)?,
     };
+    write_stopped_at(ui, &workspace_command, stop_ids.as_deref(), target)?;
 
     // Generate a short commit hash, to make it readable in the op log.
     let current_short = short_commit_hash(current_wc_id);
     let target_short = short_commit_hash(target.id());
+    let checkout_options = CheckoutOptions {
+        conflict_marker_style: args.conflict_marker_style.into(),
+    };
     // If we're editing, just move to the revision directly.
     if edit {
         // The target must be rewritable if we're editing.
-        workspace_command.check_rewritable([target.id()])?;
+        check_editable(&workspace_command, target, "prev", args.edit)?;
         let mut tx = workspace_command.start_transaction();
         tx.edit(target)?;
-        tx.finish(
+        tx.finish_with_checkout_options(
             ui,
             format!("prev: {current_short} -> editing {target_short}"),
+            &checkout_options,
         )?;
         return Ok(());
     }
     let mut tx = workspace_command.start_transaction();
     tx.check_out(target)?;
-    tx.finish(ui, format!("prev: {current_short} -> {target_short}"))?;
+    tx.finish_with_checkout_options(
+        ui,
+        format!("prev: {current_short} -> {target_short}"),
+        &checkout_options,
+    )?;
     Ok(())
 }
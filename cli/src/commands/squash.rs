@@ -12,25 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{BTreeSet, HashMap};
+
 use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
 use jj_lib::commit::{Commit, CommitIteratorExt};
-use jj_lib::matchers::Matcher;
+use jj_lib::matchers::{EverythingMatcher, Matcher};
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_store::WorkspaceId;
 use jj_lib::repo::Repo;
+use jj_lib::rewrite::restore_tree;
 use jj_lib::settings::UserSettings;
 use tracing::instrument;
 
-use crate::cli_util::{CommandHelper, DiffSelector, RevisionArg, WorkspaceCommandTransaction};
-use crate::command_error::{user_error, CommandError};
+use crate::cli_util::{
+    short_commit_hash, CommandHelper, DiffSelector, RevisionArg, WorkspaceCommandHelper,
+    WorkspaceCommandTransaction,
+};
+use crate::command_error::{user_error, user_error_with_hint, CommandError};
 use crate::description_util::{combine_messages, join_message_paragraphs};
+use crate::diff_util::DiffFormat;
+use crate::merge_tools::MergeEditor;
 use crate::ui::Ui;
 
 /// Move changes from a revision into another revision
 ///
 /// With the `-r` option, moves the changes from the specified revision to the
-/// parent revision. Fails if there are several parent revisions (i.e., the
-/// given revision is a merge).
+/// parent revision. If the given revision is a merge, the parent to move the
+/// changes into must be specified with `--into`.
 ///
 /// With the `--from` and/or `--into` options, moves changes from/to the given
 /// revisions. If either is left out, it defaults to the working-copy commit.
@@ -38,8 +48,8 @@ use crate::ui::Ui;
 /// commit to the grandparent.
 ///
 /// If, after moving changes out, the source revision is empty compared to its
-/// parent(s), it will be abandoned. Without `--interactive`, the source
-/// revision will always be empty.
+/// parent(s), it will be abandoned unless `--keep-emptied` is given. Without
+/// `--interactive`, the source revision will always be empty.
 ///
 /// If the source became empty and both the source and destination had a
 /// non-empty description, you will be asked for the combined description. If
@@ -56,7 +66,9 @@ pub(crate) struct SquashArgs {
     #[arg(long, conflicts_with = "revision")]
     from: Vec<RevisionArg>,
     /// Revision to squash into (default: @)
-    #[arg(long, conflicts_with = "revision", visible_alias = "to")]
+    ///
+    /// If `--revision` is specified, this must be one of its parents.
+    #[arg(long, visible_alias = "to")]
     into: Option<RevisionArg>,
     /// The description to use for squashed revision (don't open editor)
     #[arg(long = "message", short, value_name = "MESSAGE")]
@@ -65,15 +77,68 @@ pub(crate) struct SquashArgs {
     /// description(s) of the source revision(s)
     #[arg(long, short, conflicts_with = "message_paragraphs")]
     use_destination_message: bool,
+    /// Keep the source revision(s) even if they become empty
+    ///
+    /// By default, a source revision which becomes empty is abandoned, and any
+    /// branches pointing to it are moved to its parent.
+    #[arg(long)]
+    keep_emptied: bool,
+    /// Don't record the source revision(s) as predecessors of the squashed
+    /// revision
+    ///
+    /// By default, the evolution log of the squashed revision includes the
+    /// source revisions. With this flag, the squashed revision only has the
+    /// old destination revision as its predecessor.
+    #[arg(long)]
+    no_source_predecessors: bool,
+    /// Show a diffstat of the changes that would be moved, without moving
+    /// them
+    #[arg(long)]
+    stat: bool,
     /// Interactively choose which parts to squash
     #[arg(long, short)]
     interactive: bool,
     /// Specify diff editor to be used (implies --interactive)
     #[arg(long, value_name = "NAME")]
     tool: Option<String>,
+    /// Resolve the conflicts in the destination revision after squashing, if
+    /// there are any
+    ///
+    /// Each conflicted file is resolved in turn with the merge tool, like
+    /// `jj resolve` does.
+    #[arg(long)]
+    resolve: bool,
+    /// Specify 3-way merge tool to be used to resolve the conflicts (implies
+    /// --resolve)
+    #[arg(long, value_name = "NAME")]
+    resolve_tool: Option<String>,
     /// Move only changes to these paths (instead of all paths)
-    #[arg(conflicts_with_all = ["interactive", "tool"], value_hint = clap::ValueHint::AnyPath)]
+    ///
+    /// With `--interactive`, only changes to these paths will be shown in the
+    /// diff editor.
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Move only changes to the given paths out of the given source revision
+    ///
+    /// The source revision is separated from the fileset by the first `=`, as
+    /// in `--from-paths 'xyz=src/*.rs'`. Can be repeated. Sources without
+    /// `--from-paths` use the paths given as positional arguments.
+    #[arg(long, value_name = "SOURCE=FILESET", value_parser = parse_source_paths)]
+    from_paths: Vec<(RevisionArg, String)>,
+    /// Resolve `@` in the revisions to the working-copy commit of the given
+    /// workspace instead of the current one
+    ///
+    /// This also changes the default source or destination to that
+    /// working-copy commit.
+    #[arg(long, value_name = "NAME")]
+    workspace: Option<String>,
+}
+
+fn parse_source_paths(value: &str) -> Result<(RevisionArg, String), String> {
+    let (source, fileset) = value
+        .split_once('=')
+        .ok_or("missing `=` between the source revision and the fileset")?;
+    Ok((RevisionArg::from(source.to_owned()), fileset.to_owned()))
 }
 
 #[instrument(skip_all)]
@@ -83,10 +148,35 @@ pub(crate) fn cmd_squash(
     args: &SquashArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
+    if let Some(name) = &args.workspace {
+        workspace_command.set_revset_workspace_id(WorkspaceId::new(name.clone()))?;
+    }
 
     let mut sources: Vec<Commit>;
     let destination;
-    if !args.from.is_empty() || args.into.is_some() {
+    if let Some(revision) = &args.revision {
+        let source = workspace_command.resolve_single_rev(revision)?;
+        let mut parents: Vec<_> = source.parents().try_collect()?;
+        if let Some(into) = &args.into {
+            let into_commit = workspace_command.resolve_single_rev(into)?;
+            if !parents.iter().any(|parent| parent.id() == into_commit.id()) {
+                return Err(user_error(format!(
+                    "Commit {} is not a parent of {}",
+                    short_commit_hash(into_commit.id()),
+                    short_commit_hash(source.id()),
+                )));
+            }
+            destination = into_commit;
+        } else if parents.len() != 1 {
+            return Err(user_error_with_hint(
+                "Cannot squash merge commits",
+                "Use `--into` to specify which parent to squash into.",
+            ));
+        } else {
+            destination = parents.pop().unwrap();
+        }
+        sources = vec![source];
+    } else if !args.from.is_empty() || args.into.is_some() {
         sources = if args.from.is_empty() {
             workspace_command.parse_revset(&RevisionArg::AT)?
         } else {
@@ -104,36 +194,151 @@ pub(crate) fn cmd_squash(
         // a little faster.
         sources.reverse();
     } else {
-        let source = workspace_command
-            .resolve_single_rev(args.revision.as_ref().unwrap_or(&RevisionArg::AT))?;
+        let source = workspace_command.resolve_single_rev(&RevisionArg::AT)?;
         let mut parents: Vec<_> = source.parents().try_collect()?;
         if parents.len() != 1 {
-            return Err(user_error("Cannot squash merge commits"));
+            return Err(user_error_with_hint(
+                "Cannot squash merge commits",
+                "Use `--into` to specify which parent to squash into.",
+            ));
         }
         sources = vec![source];
         destination = parents.pop().unwrap();
     }
+    if destination.id() == workspace_command.repo().store().root_commit_id() {
+        return Err(user_error("Cannot squash into the root commit"));
+    }
 
-    let matcher = workspace_command
-        .parse_file_patterns(&args.paths)?
-        .to_matcher();
+    let mut matchers = SourceMatchers::new(
+        workspace_command
+            .parse_file_patterns(&args.paths)?
+            .to_matcher(),
+    );
+    let mut source_paths: HashMap<CommitId, Vec<String>> = HashMap::new();
+    for (source_arg, fileset) in &args.from_paths {
+        let source = workspace_command.resolve_single_rev(source_arg)?;
+        if !sources.iter().any(|commit| commit.id() == source.id()) {
+            return Err(user_error(format!(
+                "Commit {} is not a source of the squash",
+                short_commit_hash(source.id()),
+            )));
+        }
+        source_paths
+            .entry(source.id().clone())
+            .or_default()
+            .push(fileset.clone());
+    }
+    for (source_id, filesets) in source_paths {
+        let matcher = workspace_command
+            .parse_file_patterns(&filesets)?
+            .to_matcher();
+        matchers.insert(source_id, matcher);
+    }
     let diff_selector =
         workspace_command.diff_selector(ui, args.tool.as_deref(), args.interactive)?;
+    if args.stat {
+        let source_commits = select_diffs(
+            &workspace_command,
+            &sources,
+            &destination,
+            &matchers,
+            &diff_selector,
+            args.keep_emptied,
+        )?;
+        if source_commits.is_empty() {
+            writeln!(ui.status(), "No changes to move.")?;
+            return Ok(());
+        }
+        let renderer = workspace_command.diff_renderer(vec![DiffFormat::Stat]);
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        for source in &source_commits {
+            write!(formatter, "Changes to move from ")?;
+            workspace_command.write_commit_summary(formatter.as_mut(), source.commit)?;
+            writeln!(formatter, ":")?;
+            renderer.show_diff(
+                ui,
+                formatter.as_mut(),
+                &source.parent_tree,
+                &source.selected_tree,
+                matchers.get(source.commit.id()),
+            )?;
+        }
+        return Ok(());
+    }
+    // Look up the merge tool first so a misconfigured tool is reported before
+    // anything is squashed
+    let merge_editor = if args.resolve || args.resolve_tool.is_some() {
+        Some(workspace_command.merge_editor(ui, args.resolve_tool.as_deref())?)
+    } else {
+        None
+    };
     let mut tx = workspace_command.start_transaction();
     let tx_description = format!("squash commits into {}", destination.id().hex());
-    move_diff(
+    let new_destination = move_diff(
         ui,
         &mut tx,
         command.settings(),
         &sources,
         &destination,
-        matcher.as_ref(),
+        &matchers,
         &diff_selector,
         SquashedDescription::from_args(args),
-        args.revision.is_none() && args.from.is_empty() && args.into.is_none(),
-        &args.paths,
+        &MoveDiffOptions {
+            keep_emptied: args.keep_emptied,
+            source_predecessors: !args.no_source_predecessors,
+        },
+        if args.revision.is_none() && args.from.is_empty() && args.into.is_none() {
+            &args.paths
+        } else {
+            &[]
+        },
     )?;
     tx.finish(ui, tx_description)?;
+    if let (Some(merge_editor), Some(new_destination)) = (merge_editor, new_destination) {
+        resolve_conflicts(
+            ui,
+            command,
+            &mut workspace_command,
+            &new_destination,
+            &merge_editor,
+        )?;
+    }
+    Ok(())
+}
+
+/// Resolves all conflicted files of `commit` with the merge tool, and rewrites
+/// the commit with the result in a new transaction.
+fn resolve_conflicts(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_command: &mut WorkspaceCommandHelper,
+    commit: &Commit,
+    merge_editor: &MergeEditor,
+) -> Result<(), CommandError> {
+    let mut tree = commit.tree()?;
+    let conflicts = tree.conflicts().collect_vec();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let mut tx = workspace_command.start_transaction();
+    for (repo_path, _) in &conflicts {
+        writeln!(
+            ui.status(),
+            "Resolving conflicts in: {}",
+            tx.base_workspace_helper().format_file_path(repo_path)
+        )?;
+        let new_tree_id = merge_editor.edit_file(ui, &tree, repo_path)?;
+        tree = tx.repo().store().get_root_tree(&new_tree_id)?;
+    }
+    tx.mut_repo()
+        .rewrite_commit(command.settings(), commit)
+        .set_tree_id(tree.id())
+        .write()?;
+    tx.finish(
+        ui,
+        format!("Resolve conflicts in commit {}", commit.id().hex()),
+    )?;
     Ok(())
 }
 
@@ -165,6 +370,46 @@ impl SquashedDescription {
     }
 }
 
+// TODO(#2882): Remove public visibility once `jj move` is deleted.
+/// Matchers selecting the paths to move out of each source commit.
+pub(crate) struct SourceMatchers {
+    default: Box<dyn Matcher>,
+    per_source: HashMap<CommitId, Box<dyn Matcher>>,
+}
+
+impl SourceMatchers {
+    /// Creates matchers which use `default` for every source.
+    pub(crate) fn new(default: Box<dyn Matcher>) -> Self {
+        SourceMatchers {
+            default,
+            per_source: HashMap::new(),
+        }
+    }
+
+    /// Uses `matcher` instead of the default for the source `commit_id`.
+    fn insert(&mut self, commit_id: CommitId, matcher: Box<dyn Matcher>) {
+        self.per_source.insert(commit_id, matcher);
+    }
+
+    fn get(&self, commit_id: &CommitId) -> &dyn Matcher {
+        self.per_source
+            .get(commit_id)
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+// TODO(#2882): Remove public visibility once `jj move` is deleted.
+pub(crate) struct MoveDiffOptions {
+    // Keep the source revisions if they become empty.
+    pub keep_emptied: bool,
+    // Record the source revisions as predecessors of the destination.
+    pub source_predecessors: bool,
+}
+
+/// Moves the selected changes from `sources` into `destination`. If nothing
+/// was moved and `ambiguous_paths` is a single path that also parses as a
+/// revset, warns that it was interpreted as a path.
 #[allow(clippy::too_many_arguments)]
 pub fn move_diff(
     ui: &mut Ui,
@@ -172,71 +417,33 @@ pub fn move_diff(
     settings: &UserSettings,
     sources: &[Commit],
     destination: &Commit,
-    matcher: &dyn Matcher,
+    matchers: &SourceMatchers,
     diff_selector: &DiffSelector,
     description: SquashedDescription,
-    no_rev_arg: bool,
-    path_arg: &[String],
-) -> Result<(), CommandError> {
+    options: &MoveDiffOptions,
+    ambiguous_paths: &[String],
+) -> Result<Option<Commit>, CommandError> {
     tx.base_workspace_helper()
         .check_rewritable(sources.iter().chain(std::iter::once(destination)).ids())?;
 
-    struct SourceCommit<'a> {
-        commit: &'a Commit,
-        parent_tree: MergedTree,
-        selected_tree: MergedTree,
-        abandon: bool,
-    }
-    let mut source_commits = vec![];
-    for source in sources {
-        let parent_tree = source.parent_tree(tx.repo())?;
-        let source_tree = source.tree()?;
-        let instructions = format!(
-            "\
-You are moving changes from: {}
-into commit: {}
-
-The left side of the diff shows the contents of the parent commit. The
-right side initially shows the contents of the commit you're moving
-changes from.
-
-Adjust the right side until the diff shows the changes you want to move
-to the destination. If you don't make any changes, then all the changes
-from the source will be moved into the destination.
-",
-            tx.format_commit_summary(source),
-            tx.format_commit_summary(destination)
-        );
-        let selected_tree_id =
-            diff_selector.select(&parent_tree, &source_tree, matcher, Some(&instructions))?;
-        let selected_tree = tx.repo().store().get_root_tree(&selected_tree_id)?;
-        let abandon = selected_tree.id() == source_tree.id();
-        if !abandon && selected_tree_id == parent_tree.id() {
-            // Nothing selected from this commit. If it's abandoned (i.e. already empty), we
-            // still include it so `jj squash` can be used for abandoning an empty commit in
-            // the middle of a stack.
-            continue;
-        }
-        // TODO: Do we want to optimize the case of moving to the parent commit (`jj
-        // squash -r`)? The source tree will be unchanged in that case.
-        source_commits.push(SourceCommit {
-            commit: source,
-            parent_tree,
-            selected_tree,
-            abandon,
-        });
-    }
+    let source_commits = select_diffs(
+        tx.base_workspace_helper(),
+        sources,
+        destination,
+        matchers,
+        diff_selector,
+        options.keep_emptied,
+    )?;
     if source_commits.is_empty() {
         if diff_selector.is_interactive() {
             return Err(user_error("No changes selected"));
         }
 
-        if let [only_path] = path_arg {
-            if no_rev_arg
-                && tx
-                    .base_workspace_helper()
-                    .parse_revset(&RevisionArg::from(only_path.to_owned()))
-                    .is_ok()
+        if let [only_path] = ambiguous_paths {
+            if tx
+                .base_workspace_helper()
+                .parse_revset(&RevisionArg::from(only_path.to_owned()))
+                .is_ok()
             {
                 writeln!(
                     ui.warning_default(),
@@ -246,11 +453,28 @@ from the source will be moved into the destination.
             }
         }
 
-        return Ok(());
+        return Ok(None);
     }
 
     for source in &source_commits {
         if source.abandon {
+            let branch_names = tx
+                .base_repo()
+                .view()
+                .local_branches_for_commit(source.commit.id())
+                .map(|(name, _)| name)
+                .join(", ");
+            if !branch_names.is_empty() {
+                writeln!(
+                    ui.warning_default(),
+                    "Moving branches {branch_names} from abandoned commit {} to its parent",
+                    tx.format_commit_summary(source.commit)
+                )?;
+                writeln!(
+                    ui.hint_default(),
+                    "Use `jj squash --keep-emptied` to keep the emptied commit."
+                )?;
+            }
             tx.mut_repo()
                 .record_abandoned_commit(source.commit.id().clone());
         } else {
@@ -277,6 +501,10 @@ from the source will be moved into the destination.
         let rebased_destination_id = rebase_map.get(destination.id()).unwrap().clone();
         rewritten_destination = tx.mut_repo().store().get_commit(&rebased_destination_id)?;
     }
+    // If we're moving changes to an ancestor, the rewritten sources still contain
+    // the destination's old tree. They will be rebased onto the rewritten
+    // destination when the transaction finishes, which removes the moved changes
+    // from them a second time and leaves them with only the unselected changes.
     // Apply the selected changes onto the destination
     let mut destination_tree = rewritten_destination.tree()?;
     for source in &source_commits {
@@ -294,16 +522,113 @@ from the source will be moved into the destination.
         }
     };
     let mut predecessors = vec![destination.id().clone()];
-    predecessors.extend(
-        source_commits
-            .iter()
-            .map(|source| source.commit.id().clone()),
-    );
-    tx.mut_repo()
+    if options.source_predecessors {
+        predecessors.extend(
+            source_commits
+                .iter()
+                .map(|source| source.commit.id().clone()),
+        );
+    }
+    let new_destination = tx
+        .mut_repo()
         .rewrite_commit(settings, &rewritten_destination)
         .set_tree_id(destination_tree.id().clone())
         .set_predecessors(predecessors)
         .set_description(description)
         .write()?;
-    Ok(())
+
+    let mut moved_paths = BTreeSet::new();
+    for source in &source_commits {
+        for (path, diff) in source
+            .parent_tree
+            .diff(&source.selected_tree, &EverythingMatcher)
+        {
+            diff?;
+            moved_paths.insert(path);
+        }
+    }
+    if !moved_paths.is_empty() {
+        let workspace_helper = tx.base_workspace_helper();
+        writeln!(
+            ui.status(),
+            "Moved changes to: {}",
+            moved_paths
+                .iter()
+                .map(|path| workspace_helper.format_file_path(path))
+                .join(", ")
+        )?;
+    }
+    Ok(Some(new_destination))
+}
+
+struct SourceCommit<'a> {
+    commit: &'a Commit,
+    parent_tree: MergedTree,
+    selected_tree: MergedTree,
+    abandon: bool,
+}
+
+/// Selects the changes to move out of each of the `sources`. Sources from which
+/// nothing is selected are left out, unless they will be abandoned.
+fn select_diffs<'a>(
+    workspace_command: &WorkspaceCommandHelper,
+    sources: &'a [Commit],
+    destination: &Commit,
+    matchers: &SourceMatchers,
+    diff_selector: &DiffSelector,
+    keep_emptied: bool,
+) -> Result<Vec<SourceCommit<'a>>, CommandError> {
+    let mut source_commits = vec![];
+    for source in sources {
+        let parent_tree = source.parent_tree(workspace_command.repo().as_ref())?;
+        let source_tree = source.tree()?;
+        let instructions = format!(
+            "\
+You are moving changes from: {}
+into commit: {}
+
+The left side of the diff shows the contents of the parent commit. The
+right side initially shows the contents of the commit you're moving
+changes from.
+
+Adjust the right side until the diff shows the changes you want to move
+to the destination. If you don't make any changes, then all the changes
+from the source will be moved into the destination.
+",
+            workspace_command.format_commit_summary(source),
+            workspace_command.format_commit_summary(destination)
+        );
+        let matcher = matchers.get(source.id());
+        let selected_tree_id =
+            diff_selector.select(&parent_tree, &source_tree, matcher, Some(&instructions))?;
+        let mut selected_tree = workspace_command
+            .repo()
+            .store()
+            .get_root_tree(&selected_tree_id)?;
+        if diff_selector.is_interactive() {
+            // The diff editor may leave changes outside of the paths as is, so
+            // only keep the selected changes to the paths.
+            let restricted_tree_id = restore_tree(&selected_tree, &parent_tree, matcher)?;
+            selected_tree = workspace_command
+                .repo()
+                .store()
+                .get_root_tree(&restricted_tree_id)?;
+        }
+        let abandon = !keep_emptied && selected_tree.id() == source_tree.id();
+        if !abandon && selected_tree.id() == parent_tree.id() {
+            // Nothing selected from this commit. If it's abandoned (i.e. already empty), we
+            // still include it so `jj squash` can be used for abandoning an empty commit in
+            // the middle of a stack.
+            continue;
+        }
+        // TODO: Do we want to optimize the case of moving to the parent commit (`jj
+        // squash -r`)? The source tree will be unchanged in that case.
+        source_commits.push(SourceCommit {
+            commit: source,
+            parent_tree,
+            selected_tree,
+            abandon,
+        });
+    }
+    Ok(source_commits)
 }
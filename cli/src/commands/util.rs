@@ -12,12 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Write;
+use std::io::{self, Read as _, Write};
+use std::path::PathBuf;
 use std::slice;
 use std::time::{Duration, SystemTime};
 
 use clap::{Command, Subcommand};
+use jj_lib::conflicts::{self, ConflictHunk};
+use jj_lib::fileset::{self, FilesetDiagnostics};
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathUiConverter;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -32,6 +36,8 @@ pub(crate) enum UtilCommand {
     Mangen(UtilMangenArgs),
     MarkdownHelp(UtilMarkdownHelp),
     ConfigSchema(UtilConfigSchemaArgs),
+    ParseFileset(UtilParseFilesetArgs),
+    ParseConflict(UtilParseConflictArgs),
 }
 
 // Using an explicit `doc` attribute prevents rustfmt from mangling the list
@@ -96,6 +102,39 @@ pub(crate) struct UtilMarkdownHelp {}
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct UtilConfigSchemaArgs {}
 
+/// Parse and print a fileset expression without loading a repository
+///
+/// This is useful for validating filesets embedded in configuration files.
+/// Paths in the expression are resolved relative to the `--cwd` directory,
+/// which must be inside the `--root` directory.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct UtilParseFilesetArgs {
+    /// The fileset expression to parse
+    expression: String,
+    /// Directory to resolve relative paths from [default: current directory]
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    cwd: Option<PathBuf>,
+    /// Workspace root directory [default: same as `--cwd`]
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    root: Option<PathBuf>,
+}
+
+/// Parse conflict markers read from stdin and print the resulting hunks
+///
+/// This is useful for reproducing issues with the conflict parser without
+/// setting up a repository.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct UtilParseConflictArgs {
+    /// The number of sides each conflict is expected to have
+    #[arg(long, default_value_t = 2)]
+    sides: usize,
+    /// The minimum length of the conflict markers
+    ///
+    /// Shorter lines that look like conflict markers are parsed as content.
+    #[arg(long, default_value_t = conflicts::MIN_CONFLICT_MARKER_LEN)]
+    marker_len: usize,
+}
+
 /// Available shell completions
 #[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum ShellCompletion {
@@ -119,6 +158,8 @@ pub(crate) fn cmd_util(
         UtilCommand::Mangen(args) => cmd_util_mangen(ui, command, args),
         UtilCommand::MarkdownHelp(args) => cmd_util_markdownhelp(ui, command, args),
         UtilCommand::ConfigSchema(args) => cmd_util_config_schema(ui, command, args),
+        UtilCommand::ParseFileset(args) => cmd_util_parse_fileset(ui, command, args),
+        UtilCommand::ParseConflict(args) => cmd_util_parse_conflict(ui, command, args),
     }
 }
 
@@ -227,6 +268,71 @@ fn cmd_util_config_schema(
     Ok(())
 }
 
+fn cmd_util_parse_fileset(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UtilParseFilesetArgs,
+) -> Result<(), CommandError> {
+    let cwd = match &args.cwd {
+        Some(path) => command.cwd().join(path),
+        None => command.cwd().to_owned(),
+    };
+    let base = match &args.root {
+        Some(path) => command.cwd().join(path),
+        None => cwd.clone(),
+    };
+    let path_converter = RepoPathUiConverter::Fs { cwd, base };
+    let mut diagnostics = FilesetDiagnostics::new();
+    let expression = fileset::parse_maybe_bare_with_diagnostics(
+        &mut diagnostics,
+        &args.expression,
+        &path_converter,
+    )?;
+    for warning in diagnostics.warnings() {
+        writeln!(ui.warning_default(), "{warning}")?;
+    }
+    writeln!(ui.stdout(), "{expression:#?}")?;
+    Ok(())
+}
+
+fn cmd_util_parse_conflict(
+    ui: &mut Ui,
+    _command: &CommandHelper,
+    args: &UtilParseConflictArgs,
+) -> Result<(), CommandError> {
+    let mut input = vec![];
+    io::stdin().read_to_end(&mut input)?;
+    let Some(parsed) = conflicts::parse_conflict_explicit(&input, args.sides, args.marker_len)
+    else {
+        writeln!(ui.stdout(), "no valid conflicts")?;
+        return Ok(());
+    };
+    let mut stdout = ui.stdout();
+    for hunk in &parsed.hunks {
+        match hunk {
+            ConflictHunk::Resolved(content) => {
+                writeln!(
+                    stdout,
+                    "resolved: {:?}",
+                    String::from_utf8_lossy(&content.0)
+                )?;
+            }
+            ConflictHunk::Conflict(merge) => {
+                writeln!(stdout, "conflict:")?;
+                for (i, content) in merge.iter().enumerate() {
+                    let kind = if i % 2 == 0 { "add" } else { "remove" };
+                    writeln!(
+                        stdout,
+                        "  {kind}: {:?}",
+                        String::from_utf8_lossy(&content.0)
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 impl ShellCompletion {
     fn generate(&self, cmd: &mut Command) -> Vec<u8> {
         use clap_complete::{generate, Shell};
@@ -26,6 +26,7 @@ use jj_lib::op_store::{OpStoreError, WorkspaceId};
 use jj_lib::operation::Operation;
 use jj_lib::repo::{ReadonlyRepo, Repo};
 use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::workspace::Workspace;
 use tracing::instrument;
 
@@ -418,7 +419,7 @@ fn cmd_workspace_update_stale(
             }
             let stats = locked_ws
                 .locked_wc()
-                .check_out(&desired_wc_commit)
+                .check_out(&desired_wc_commit, &CheckoutOptions::default())
                 .map_err(|err| {
                     internal_error_with_message(
                         format!(
@@ -21,7 +21,10 @@ use futures::{try_join, Stream, StreamExt};
 use itertools::Itertools;
 use jj_lib::backend::{BackendError, BackendResult, TreeValue};
 use jj_lib::commit::Commit;
-use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::conflicts::{
+    materialize_tree_value_with_options, ConflictMarkerStyle, ConflictMaterializeOptions,
+    MaterializedTreeValue,
+};
 use jj_lib::diff::{Diff, DiffHunk};
 use jj_lib::files::DiffLine;
 use jj_lib::matchers::Matcher;
@@ -218,6 +221,7 @@ pub struct DiffRenderer<'a> {
     repo: &'a dyn Repo,
     path_converter: &'a RepoPathUiConverter,
     formats: Vec<DiffFormat>,
+    conflict_marker_style: ConflictMarkerStyle,
 }
 
 impl<'a> DiffRenderer<'a> {
@@ -230,6 +234,15 @@ impl<'a> DiffRenderer<'a> {
             repo,
             formats,
             path_converter,
+            conflict_marker_style: ConflictMarkerStyle::default(),
+        }
+    }
+
+    /// Materializes conflicts in file contents with the given `style`.
+    pub fn with_conflict_marker_style(self, style: ConflictMarkerStyle) -> Self {
+        DiffRenderer {
+            conflict_marker_style: style,
+            ..self
         }
     }
 
@@ -244,6 +257,7 @@ impl<'a> DiffRenderer<'a> {
     ) -> Result<(), DiffRenderError> {
         let repo = self.repo;
         let path_converter = self.path_converter;
+        let marker_style = self.conflict_marker_style;
         for format in &self.formats {
             match format {
                 DiffFormat::Summary => {
@@ -254,7 +268,14 @@ impl<'a> DiffRenderer<'a> {
                     let tree_diff = from_tree.diff_stream(to_tree, matcher);
                     // TODO: In graph log, graph width should be subtracted
                     let width = usize::from(ui.term_width().unwrap_or(80));
-                    show_diff_stat(repo, formatter, tree_diff, path_converter, width)?;
+                    show_diff_stat(
+                        repo,
+                        formatter,
+                        tree_diff,
+                        path_converter,
+                        width,
+                        marker_style,
+                    )?;
                 }
                 DiffFormat::Types => {
                     let tree_diff = from_tree.diff_stream(to_tree, matcher);
@@ -266,11 +287,18 @@ impl<'a> DiffRenderer<'a> {
                 }
                 DiffFormat::Git { context } => {
                     let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                    show_git_diff(repo, formatter, *context, tree_diff)?;
+                    show_git_diff(repo, formatter, *context, tree_diff, marker_style)?;
                 }
                 DiffFormat::ColorWords { context } => {
                     let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                    show_color_words_diff(repo, formatter, *context, tree_diff, path_converter)?;
+                    show_color_words_diff(
+                        repo,
+                        formatter,
+                        *context,
+                        tree_diff,
+                        path_converter,
+                        marker_style,
+                    )?;
                 }
                 DiffFormat::Tool(tool) => {
                     merge_tools::generate_diff(
@@ -520,9 +548,10 @@ pub fn show_color_words_diff(
     num_context_lines: usize,
     tree_diff: TreeDiffStream,
     path_converter: &RepoPathUiConverter,
+    marker_style: ConflictMarkerStyle,
 ) -> Result<(), DiffRenderError> {
     formatter.push_label("diff")?;
-    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff);
+    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff, marker_style);
     async {
         while let Some((path, diff)) = diff_stream.next().await {
             let ui_path = path_converter.format_file_path(&path);
@@ -862,19 +891,26 @@ fn show_unified_diff_hunks(
 fn materialized_diff_stream<'a>(
     store: &'a Store,
     tree_diff: TreeDiffStream<'a>,
+    marker_style: ConflictMarkerStyle,
 ) -> impl Stream<
     Item = (
         RepoPathBuf,
         BackendResult<(MaterializedTreeValue, MaterializedTreeValue)>,
     ),
 > + 'a {
+    let options = ConflictMaterializeOptions {
+        marker_style,
+        ..Default::default()
+    };
     tree_diff
-        .map(|(path, diff)| async {
+        .map(move |(path, diff)| async move {
             match diff {
                 Err(err) => (path, Err(err)),
                 Ok((before, after)) => {
-                    let before_future = materialize_tree_value(store, &path, before);
-                    let after_future = materialize_tree_value(store, &path, after);
+                    let before_future =
+                        materialize_tree_value_with_options(store, &path, before, &options);
+                    let after_future =
+                        materialize_tree_value_with_options(store, &path, after, &options);
                     let values = try_join!(before_future, after_future);
                     (path, values)
                 }
@@ -888,10 +924,11 @@ pub fn show_git_diff(
     formatter: &mut dyn Formatter,
     num_context_lines: usize,
     tree_diff: TreeDiffStream,
+    marker_style: ConflictMarkerStyle,
 ) -> Result<(), DiffRenderError> {
     formatter.push_label("diff")?;
 
-    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff);
+    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff, marker_style);
     async {
         while let Some((path, diff)) = diff_stream.next().await {
             let path_string = path.as_internal_file_string();
@@ -1024,12 +1061,13 @@ pub fn show_diff_stat(
     tree_diff: TreeDiffStream,
     path_converter: &RepoPathUiConverter,
     display_width: usize,
+    marker_style: ConflictMarkerStyle,
 ) -> Result<(), DiffRenderError> {
     let mut stats: Vec<DiffStat> = vec![];
     let mut max_path_width = 0;
     let mut max_diffs = 0;
 
-    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff);
+    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff, marker_style);
     async {
         while let Some((repo_path, diff)) = diff_stream.next().await {
             let (left, right) = diff?;
@@ -13,7 +13,7 @@ use jj_lib::matchers::Matcher;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::store::Store;
-use jj_lib::working_copy::{CheckoutError, SnapshotOptions};
+use jj_lib::working_copy::{CheckoutError, CheckoutOptions, SnapshotOptions};
 use pollster::FutureExt;
 use tempfile::TempDir;
 use thiserror::Error;
@@ -83,7 +83,7 @@ fn check_out(
     std::fs::create_dir(&state_dir).map_err(DiffCheckoutError::SetUpDir)?;
     let mut tree_state = TreeState::init(store, wc_dir, state_dir)?;
     tree_state.set_sparse_patterns(sparse_patterns)?;
-    tree_state.check_out(tree)?;
+    tree_state.check_out(tree, &CheckoutOptions::default())?;
     Ok(tree_state)
 }
 
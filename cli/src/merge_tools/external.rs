@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 use itertools::Itertools;
 use jj_lib::backend::{FileId, MergedTreeId, TreeValue};
-use jj_lib::conflicts::{self, materialize_merge_result};
+use jj_lib::conflicts::{self, materialize_merge_result, ConflictMaterializeOptions};
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::{Merge, MergedTreeValue};
@@ -122,6 +122,7 @@ pub enum ExternalToolError {
 }
 
 pub fn run_mergetool_external(
+    ui: &Ui,
     editor: &ExternalMergeTool,
     file_merge: Merge<Option<FileId>>,
     content: Merge<jj_lib::files::ContentHunk>,
@@ -193,13 +194,23 @@ pub fn run_mergetool_external(
     }
 
     let new_file_ids = if editor.merge_tool_edits_conflict_markers {
-        conflicts::update_from_content(
+        let (new_file_ids, errors) = conflicts::update_from_content_with_errors(
             &file_merge,
             tree.store(),
             repo_path,
             output_file_contents.as_slice(),
+            &ConflictMaterializeOptions::default(),
         )
-        .block_on()?
+        .block_on()?;
+        for (line_number, err) in errors {
+            writeln!(
+                ui.warning_default(),
+                "Treating conflict markers at line {line_number} of the output file as resolved \
+                 content: {err}"
+            )
+            .map_err(ExternalToolError::Io)?;
+        }
+        new_file_ids
     } else {
         let new_file_id = tree
             .store()
@@ -303,6 +314,44 @@ pub fn generate_diff(
     Ok(())
 }
 
+/// Opens the diff between the trees in the specified `tool`, which is run
+/// interactively with the terminal inherited.
+pub fn view_diff(
+    ui: &Ui,
+    left_tree: &MergedTree,
+    right_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    tool: &ExternalMergeTool,
+) -> Result<(), DiffGenerateError> {
+    let store = left_tree.store();
+    let diff_wc = check_out_trees(store, left_tree, right_tree, matcher, None)?;
+    set_readonly_recursively(diff_wc.left_working_copy_path())
+        .map_err(ExternalToolError::SetUpDir)?;
+    set_readonly_recursively(diff_wc.right_working_copy_path())
+        .map_err(ExternalToolError::SetUpDir)?;
+    let patterns = diff_wc.to_command_variables();
+    let mut cmd = Command::new(&tool.program);
+    cmd.args(interpolate_variables(&tool.diff_args, &patterns));
+    tracing::info!(?cmd, "Invoking the external diff viewer:");
+    let exit_status = cmd
+        .status()
+        .map_err(|source| ExternalToolError::FailedToExecute {
+            tool_binary: tool.program.clone(),
+            source,
+        })?;
+    tracing::info!(?cmd, ?exit_status, "The external diff viewer exited:");
+    // As with generate_diff(), a non-zero exit code may just mean that the
+    // inputs are different.
+    if !exit_status.success() {
+        writeln!(
+            ui.warning_default(),
+            "Tool exited with {exit_status} (run with --debug to see the exact invocation)",
+        )
+        .ok();
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
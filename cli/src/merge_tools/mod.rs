@@ -33,7 +33,7 @@ use thiserror::Error;
 use self::builtin::{edit_diff_builtin, edit_merge_builtin, BuiltinToolError};
 use self::diff_working_copies::DiffCheckoutError;
 use self::external::{edit_diff_external, ExternalToolError};
-pub use self::external::{generate_diff, ExternalMergeTool};
+pub use self::external::{generate_diff, view_diff, ExternalMergeTool};
 use crate::config::CommandNameAndArgs;
 use crate::ui::Ui;
 
@@ -278,6 +278,7 @@ impl MergeEditor {
     /// Starts a merge editor for the specified file.
     pub fn edit_file(
         &self,
+        ui: &Ui,
         tree: &MergedTree,
         repo_path: &RepoPath,
     ) -> Result<MergedTreeId, ConflictResolveError> {
@@ -313,7 +314,7 @@ impl MergeEditor {
                 Ok(tree_id)
             }
             MergeTool::External(editor) => external::run_mergetool_external(
-                editor, file_merge, content, repo_path, conflict, tree,
+                ui, editor, file_merge, content, repo_path, conflict, tree,
             ),
         }
     }
@@ -551,6 +551,32 @@ impl Ui {
         }
     }
 
+    /// Repeat the given prompt until the input is a list of the specified
+    /// choices, separated by whitespace or commas. Empty input results in an
+    /// empty list.
+    pub fn prompt_choices(
+        &self,
+        prompt: &str,
+        choices: &[impl AsRef<str>],
+    ) -> io::Result<Vec<String>> {
+        loop {
+            let input = self.prompt(prompt)?;
+            let selected: Vec<String> = input
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|choice| !choice.is_empty())
+                .map(|choice| choice.to_owned())
+                .collect();
+            if selected
+                .iter()
+                .all(|choice| choices.iter().any(|c| choice == c.as_ref()))
+            {
+                return Ok(selected);
+            }
+
+            writeln!(self.warning_no_heading(), "unrecognized response")?;
+        }
+    }
+
     /// Prompts for a yes-or-no response, with yes = true and no = false.
     pub fn prompt_yes_no(&self, prompt: &str, default: Option<bool>) -> io::Result<bool> {
         let default_str = match &default {
@@ -26,6 +26,7 @@ mod test_diffedit_command;
 mod test_duplicate_command;
 mod test_edit_command;
 mod test_file_chmod_command;
+mod test_file_list_command;
 mod test_file_print_command;
 mod test_fix_command;
 mod test_generate_md_cli_help;
@@ -454,7 +454,7 @@ fn test_branch_forget_glob() {
     // Malformed glob
     let stderr = test_env.jj_cmd_cli_error(&repo_path, &["branch", "forget", "glob:foo-[1-3"]);
     insta::assert_snapshot!(stderr, @r###"
-    error: invalid value 'glob:foo-[1-3' for '<NAMES>...': Pattern syntax error near position 4: invalid range pattern
+    error: invalid value 'glob:foo-[1-3' for '[NAMES]...': Pattern syntax error near position 4: invalid range pattern
 
     For more information, try '--help'.
     "###);
@@ -558,7 +558,7 @@ fn test_branch_delete_glob() {
     // Unknown pattern kind
     let stderr = test_env.jj_cmd_cli_error(&repo_path, &["branch", "forget", "whatever:branch"]);
     insta::assert_snapshot!(stderr, @r###"
-    error: invalid value 'whatever:branch' for '<NAMES>...': Invalid string pattern kind "whatever:"
+    error: invalid value 'whatever:branch' for '[NAMES]...': Invalid string pattern kind "whatever:"
 
     For more information, try '--help'.
     Hint: Try prefixing with one of `exact:`, `glob:` or `substring:`
@@ -590,6 +590,64 @@ fn test_branch_delete_export() {
     "###);
 }
 
+#[test]
+fn test_branch_forget_older_than() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Each command advances the clock by one second
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "old"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "old-1", "old-2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "new-1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()"]);
+    let template = r#"separate(" ", branches, committer.timestamp()) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r=branches()", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    new-1 2001-02-03 04:05:10.000 +07:00
+    old-1 old-2 2001-02-03 04:05:08.000 +07:00
+    "###);
+
+    // Nothing is old enough
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "--older-than=1h"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    No branches to forget.
+    "###);
+
+    // Only the old branches matching the pattern are forgotten
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "forget", "--older-than=6s", "old-1", "new-1"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    new-1: zsuskuln a00b4f32 (empty) new
+    old-2: rlvkpnrz 8b0dc2d1 (empty) old
+    "###);
+
+    // Without names, all old branches are forgotten
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "--older-than=8s"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    new-1: zsuskuln a00b4f32 (empty) new
+    "###);
+
+    // Malformed duration
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["branch", "forget", "--older-than=90"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value '90' for '--older-than <DURATION>': expected a number followed by one of s, m, h, d, w
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_branch_forget_export() {
     let test_env = TestEnvironment::default();
@@ -627,6 +685,46 @@ fn test_branch_forget_export() {
     insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @"");
 }
 
+#[test]
+fn test_branch_forget_git_tracking_branch() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "foo"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "export"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: rlvkpnrz 65b6b74e (empty) (no description set)
+      @git: rlvkpnrz 65b6b74e (empty) (no description set)
+    "###);
+
+    // Only the Git-tracking branch is forgotten
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "foo@git"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Hint: Git-tracking branches will be recreated from the local branches by the next `jj git export`: foo@git
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: rlvkpnrz 65b6b74e (empty) (no description set)
+    "###);
+
+    // The next export recreates it from the local branch
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "export"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: rlvkpnrz 65b6b74e (empty) (no description set)
+      @git: rlvkpnrz 65b6b74e (empty) (no description set)
+    "###);
+
+    // No hint if the local branch is forgotten as well
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "foo", "foo@git"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @"");
+}
+
 #[test]
 fn test_branch_forget_fetched_branch() {
     // Much of this test is borrowed from `test_git_fetch_remote_only_branch` in
@@ -748,6 +846,208 @@ fn test_branch_forget_fetched_branch() {
     "###);
 }
 
+#[test]
+fn test_branch_forget_remote_glob() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("git.auto-local-branch = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Set up two remotes with the same branches
+    let signature =
+        git2::Signature::new("Some One", "some.one@example.com", &git2::Time::new(0, 0)).unwrap();
+    for remote in ["origin", "upstream"] {
+        let git_repo_path = test_env.env_root().join(remote);
+        let git_repo = git2::Repository::init_bare(git_repo_path).unwrap();
+        let mut tree_builder = git_repo.treebuilder(None).unwrap();
+        let file_oid = git_repo.blob(b"content").unwrap();
+        tree_builder
+            .insert("file", file_oid, git2::FileMode::Blob.into())
+            .unwrap();
+        let tree_oid = tree_builder.write().unwrap();
+        let tree = git_repo.find_tree(tree_oid).unwrap();
+        let git_commit_oid = git_repo
+            .commit(None, &signature, &signature, "commit", &tree, &[])
+            .unwrap();
+        for name in ["feature/a", "feature/b", "main"] {
+            git_repo
+                .reference(&format!("refs/heads/{name}"), git_commit_oid, true, "")
+                .unwrap();
+        }
+        test_env.jj_cmd_ok(
+            &repo_path,
+            &["git", "remote", "add", remote, &format!("../{remote}")],
+        );
+    }
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    feature/a: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    feature/b: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    main: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    "###);
+
+    // Forget the matching remote branches of all remotes, but not the local
+    // branches
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "glob:feature/*@*"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Forgot 4 remote branches.
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    feature/a: omvolwpu 1336caed commit
+    feature/b: omvolwpu 1336caed commit
+    main: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    "###);
+
+    // The remote fragment can have its own pattern kind
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "main@glob:up*"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    feature/a: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    feature/b: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+      @upstream: omvolwpu 1336caed commit
+    main: omvolwpu 1336caed commit
+      @origin: omvolwpu 1336caed commit
+    "###);
+
+    // Local and remote patterns can be mixed
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "forget", "glob:feature/*", "main@origin"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Forgot 2 branches.
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    main: omvolwpu 1336caed commit
+    "###);
+
+    // Unknown pattern kind in the remote fragment
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["branch", "forget", "main@regex:.*"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'main@regex:.*' for '[NAMES]...': Invalid string pattern kind "regex:"
+
+    For more information, try '--help'.
+    "###);
+
+    // No matching remote branches
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["branch", "forget", "glob:feature/*@*"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No matching remote branches for patterns: feature/*@*
+    "###);
+}
+
+#[test]
+fn test_branch_forget_local_name_with_at() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "foo@bar", "baz@"]);
+
+    // Without a kind, "foo@bar" is parsed as a remote branch
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["branch", "forget", "foo@bar"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No such remote branch: foo@bar
+    Hint: Use `exact:foo@bar` to forget the local branch named foo@bar.
+    "###);
+
+    // An explicit "exact:" kind names the local branch
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "exact:foo@bar"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+
+    // A trailing '@' doesn't specify a remote
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "baz@"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @"");
+}
+
+#[test]
+fn test_branch_forget_include_remotes() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("git.auto-local-branch = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Set up two remotes with the same branch
+    let signature =
+        git2::Signature::new("Some One", "some.one@example.com", &git2::Time::new(0, 0)).unwrap();
+    for remote in ["origin", "upstream"] {
+        let git_repo_path = test_env.env_root().join(remote);
+        let git_repo = git2::Repository::init_bare(git_repo_path).unwrap();
+        let mut tree_builder = git_repo.treebuilder(None).unwrap();
+        let file_oid = git_repo.blob(b"content").unwrap();
+        tree_builder
+            .insert("file", file_oid, git2::FileMode::Blob.into())
+            .unwrap();
+        let tree_oid = tree_builder.write().unwrap();
+        let tree = git_repo.find_tree(tree_oid).unwrap();
+        git_repo
+            .commit(
+                Some("refs/heads/feature1"),
+                &signature,
+                &signature,
+                "commit",
+                &tree,
+                &[],
+            )
+            .unwrap();
+        test_env.jj_cmd_ok(
+            &repo_path,
+            &["git", "remote", "add", remote, &format!("../{remote}")],
+        );
+    }
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+
+    // Only the branch of the specified remote is forgotten, and the other is
+    // untracked
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "forget", "feature1", "--include-remotes=origin"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["branch", "list", "--all"]);
+    insta::assert_snapshot!(stdout, @r###"
+    feature1@upstream: omvolwpu 1336caed commit
+    "###);
+
+    // Unknown remote
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["branch", "forget", "feature1", "--include-remotes=unknown"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No branches found for remote: unknown
+    "###);
+
+    // The Git-tracking branches aren't a remote
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["branch", "forget", "feature1", "--include-remotes=git"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Git-tracking branches can't be selected with --include-remotes: git
+    "###);
+}
+
 #[test]
 fn test_branch_forget_deleted_or_nonexistent_branch() {
     // Much of this test is borrowed from `test_git_fetch_remote_only_branch` in
@@ -807,6 +1107,54 @@ fn test_branch_forget_deleted_or_nonexistent_branch() {
     "###);
 }
 
+#[test]
+fn test_branch_forget_continue_on_error() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "foo-1", "bar-1"]);
+
+    // Without the flag, nothing is forgotten if any name doesn't match
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["branch", "forget", "foo-1", "i_do_not_exist"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No such branch: i_do_not_exist
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "branch",
+            "forget",
+            "--continue-on-error",
+            "foo-1",
+            "i_do_not_exist",
+            "glob:baz-*",
+            "foo-1@origin",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: No such branch: i_do_not_exist
+    Warning: No matching branches for patterns: baz-*
+    Warning: No such remote branch: foo-1@origin
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    bar-1: qpvuntsm 230dd059 (empty) (no description set)
+    "###);
+
+    // Nothing to forget if no name matches
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "forget", "--continue-on-error", "foo-1"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: No such branch: foo-1
+    No branches to forget.
+    "###);
+}
+
 #[test]
 fn test_branch_track_untrack() {
     let test_env = TestEnvironment::default();
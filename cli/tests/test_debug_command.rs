@@ -45,6 +45,13 @@ fn test_debug_fileset() {
     2: Path ".." is not in the repo "."
     3: Invalid component ".." in repo-relative path "../"
     "###);
+
+    // Redundant patterns are reported as warnings
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&workspace_path, &["debug", "fileset", "src | src/a.rs"]);
+    assert_snapshot!(stderr, @r###"
+    Warning: The pattern `src/a.rs` is redundant given `src`
+    "###);
 }
 
 #[test]
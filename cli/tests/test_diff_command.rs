@@ -365,6 +365,44 @@ fn test_diff_name_only() {
     "###);
 }
 
+#[test]
+fn test_diff_file_to_dir() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "file\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::remove_file(repo_path.join("file")).unwrap();
+    std::fs::create_dir(repo_path.join("file")).unwrap();
+    std::fs::write(repo_path.join("file").join("sub"), "sub\n").unwrap();
+
+    // Trees are expanded into their files, so replacing a file with a
+    // directory shows up as a removed file and an added file
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--types"]);
+    insta::assert_snapshot!(stdout.replace('\\', "/"), @r###"
+    F- file
+    -F file/sub
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file b/file
+    deleted file mode 100644
+    index f73f3093ff..0000000000
+    --- a/file
+    +++ /dev/null
+    @@ -1,1 +1,0 @@
+    -file
+    diff --git a/file/sub b/file/sub
+    new file mode 100644
+    index 0000000000..62e0af52c1
+    --- /dev/null
+    +++ b/file/sub
+    @@ -1,0 +1,1 @@
+    +sub
+    "###);
+}
+
 #[test]
 fn test_diff_bad_args() {
     let test_env = TestEnvironment::default();
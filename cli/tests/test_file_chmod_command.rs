@@ -14,7 +14,7 @@
 
 use std::path::Path;
 
-use crate::common::TestEnvironment;
+use crate::common::{get_stderr_string, get_stdout_string, TestEnvironment};
 
 fn create_commit(
     test_env: &TestEnvironment,
@@ -130,9 +130,201 @@ fn test_chmod_regular_conflict() {
     "###);
 }
 
+#[test]
+fn test_chmod_conflict_preserves_arity() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "base", &[], &[("file", "base\n")]);
+    create_commit(&test_env, &repo_path, "a", &["base"], &[("file", "a\n")]);
+    create_commit(&test_env, &repo_path, "b", &["base"], &[("file", "b\n")]);
+    // Only the executable bit differs from the base
+    create_commit(&test_env, &repo_path, "c", &["base"], &[]);
+    test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "file", "-r=c"]);
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b", "c"], &[]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "base"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "-r=conflict"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file: Ok(Conflicted([Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false }), Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: true })]))
+    "###);
+
+    // Making the sides non-executable shouldn't simplify the conflict even
+    // though the side from "c" becomes identical to the base.
+    test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "n", "file", "-r=conflict"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "-r=conflict"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file: Ok(Conflicted([Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false }), Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false })]))
+    "###);
+}
+
 // TODO: Test demonstrating that conflicts whose *base* is not a file are
 // chmod-dable
 
+#[test]
+fn test_chmod_no_changes() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "base", &[], &[("file", "a\n")]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "base"]);
+
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "-r=base", "file"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 3926d7db (empty) (no description set)
+    Parent commit      : rlvkpnrz baee8f9b base | base
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let commit_id = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r=base", "-T=commit_id"],
+    );
+
+    // The file is already executable, so the commit isn't rewritten
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "-r=base", "file"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+    assert_eq!(
+        test_env.jj_cmd_success(
+            &repo_path,
+            &["log", "--no-graph", "-r=base", "-T=commit_id"]
+        ),
+        commit_id
+    );
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "--no-graph", "-n=1", "-T=description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    make paths executable in commit a2ec5e7f3217f60740e82a3bf78571f36eec699f
+    "###);
+}
+
+#[test]
+fn test_chmod_parent() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "base", &[], &[("file", "a\n")]);
+    create_commit(&test_env, &repo_path, "child", &["base"], &[]);
+
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "--parent", "file"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 230f3809 child | (empty) child
+    Parent commit      : rlvkpnrz 0ad3d783 base | base
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-r=base", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file b/file
+    new file mode 100755
+    index 0000000000..7898192261
+    --- /dev/null
+    +++ b/file
+    @@ -1,0 +1,1 @@
+    +a
+    "###);
+
+    // --parent can't be combined with --revision
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["file", "chmod", "x", "--parent", "-r=base", "file"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--parent' cannot be used with '--revisions <REVISIONS>'
+
+    Usage: jj file chmod --parent <MODE> <PATHS>...
+
+    For more information, try '--help'.
+    "###);
+
+    // An immutable parent is refused before anything is changed
+    test_env.add_config(r#"revset-aliases."immutable_heads()" = "base""#);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "chmod", "n", "--parent", "file"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 0ad3d783b05a is immutable
+    Hint: Pass `--ignore-immutable` or configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    "###);
+}
+
+#[test]
+fn test_chmod_multiple_revisions() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "a", &[], &[("build.sh", "a\n")]);
+    create_commit(&test_env, &repo_path, "b", &["a"], &[("build.sh", "b\n")]);
+    create_commit(&test_env, &repo_path, "c", &[], &[("other", "c\n")]);
+    create_commit(&test_env, &repo_path, "d", &["b"], &[]);
+
+    // Immutable commits in the set are refused before anything is changed
+    test_env.add_config(r#"revset-aliases."immutable_heads()" = "a""#);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["file", "chmod", "x", "-r=a | b | c", "build.sh"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 25fa2824c471 is immutable
+    Hint: Pass `--ignore-immutable` or configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    "###);
+
+    // Each commit is updated, and descendants are rebased once
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "chmod", "x", "-r=b", "-r=c", "build.sh"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Changed zsuskuln 772655af b | b
+    Skipped (no matching paths) royxmykx 6d267b80 c | c
+    Rebased 1 descendant commits
+    Working copy now at: vruxwmqv f40cdf2c d | (empty) d
+    Parent commit      : zsuskuln 772655af b | b
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-r=b", "--summary"]);
+    insta::assert_snapshot!(stdout, @r###"
+    M build.sh
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "-r=d", "build.sh"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-r=d", "--git"]);
+    insta::assert_snapshot!(stdout, @"");
+
+    // Commits which already have the executable bit set are reported
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "-r=b | d", "build.sh"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed in zsuskuln 772655af b | b
+    Nothing changed in vruxwmqv f40cdf2c d | (empty) d
+    Nothing changed.
+    "###);
+
+    // --interactive only supports a single revision
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["file", "chmod", "n", "-i", "-r=b | d", "build.sh"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: --interactive can only be used with a single revision
+    "###);
+}
+
 #[test]
 fn test_chmod_file_dir_deletion_conflicts() {
     let test_env = TestEnvironment::default();
@@ -246,3 +438,211 @@ fn test_chmod_file_dir_deletion_conflicts() {
     >>>>>>> Conflict 1 of 1 ends
     "###);
 }
+
+#[test]
+fn test_chmod_paths_from_file() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("a"), "a\n").unwrap();
+    std::fs::write(repo_path.join("b"), "b\n").unwrap();
+    std::fs::write(repo_path.join("c"), "c\n").unwrap();
+    // The list is outside the repo so it isn't snapshotted
+    std::fs::write(
+        test_env.env_root().join("paths.txt"),
+        "# Paths to make executable\na\n\n  b  \n",
+    )
+    .unwrap();
+
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "chmod", "x", "--paths-from-file", "../paths.txt"],
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a: Ok(Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: true })))
+    b: Ok(Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: true })))
+    c: Ok(Resolved(Some(File { id: FileId("f2ad6c76f0115a6ba5b00456a849810e7ec0af20"), executable: false })))
+    "###);
+
+    // Paths from the file are combined with the positional arguments
+    std::fs::write(test_env.env_root().join("b.txt"), "b\n").unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "chmod", "n", "c", "--paths-from-file", "../b.txt"],
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a: Ok(Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: true })))
+    b: Ok(Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false })))
+    c: Ok(Resolved(Some(File { id: FileId("f2ad6c76f0115a6ba5b00456a849810e7ec0af20"), executable: false })))
+    "###);
+
+    // A file without any paths doesn't change anything
+    std::fs::write(test_env.env_root().join("empty.txt"), "# Nothing\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "chmod", "x", "--paths-from-file", "../empty.txt"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    No paths to change the executable bit for
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["file", "chmod", "x", "--paths-from-file", "../missing.txt"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to read paths from ../missing.txt
+    Caused by: No such file or directory (os error 2)
+    "###);
+}
+
+#[test]
+fn test_chmod_fileset_expression() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    test_env.add_config("ui.allow-filesets = true");
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::create_dir(repo_path.join("vendor")).unwrap();
+    std::fs::write(repo_path.join("a.sh"), "a\n").unwrap();
+    std::fs::write(repo_path.join("b.sh"), "b\n").unwrap();
+    std::fs::write(repo_path.join("c.txt"), "c\n").unwrap();
+    std::fs::write(repo_path.join("vendor").join("d.sh"), "d\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "a.sh", "vendor"]);
+
+    // Functions and operators can be combined to select the files
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "file",
+            "chmod",
+            "n",
+            r#"subtract(glob:"**/*.sh", vendor) ~ b.sh"#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm a68a5a7a (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a.sh: Ok(Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false })))
+    b.sh: Ok(Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false })))
+    c.txt: Ok(Resolved(Some(File { id: FileId("f2ad6c76f0115a6ba5b00456a849810e7ec0af20"), executable: false })))
+    vendor/d.sh: Ok(Resolved(Some(File { id: FileId("4bcfe98e640c8284511312660fb8709b0afa888e"), executable: true })))
+    "###);
+
+    // Predicates on the file contents or mode aren't supported
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "chmod", "n", "executable()"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset: Function "executable" doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | executable()
+      | ^--------^
+      |
+      = Function "executable" doesn't exist
+    "###);
+}
+
+#[test]
+fn test_chmod_interactive() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(
+        &test_env,
+        &repo_path,
+        "base",
+        &[],
+        &[("file1", "a\n"), ("file2", "b\n"), ("file3", "c\n")],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["file", "chmod", "x", "file2"]);
+
+    // Only the selected files are changed
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(
+        &repo_path,
+        &["file", "chmod", "x", "-i", "file1", "file2", "file3"],
+        "1 3\n",
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    matched files:
+    1: n file1
+    2: x file2
+    3: n file3
+    q: quit the prompt
+    enter the indices of the files to change (empty for all):
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 2ca4acb7 base | base
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file1 b/file1
+    new file mode 100755
+    index 0000000000..7898192261
+    --- /dev/null
+    +++ b/file1
+    @@ -1,0 +1,1 @@
+    +a
+    diff --git a/file2 b/file2
+    new file mode 100755
+    index 0000000000..6178079822
+    --- /dev/null
+    +++ b/file2
+    @@ -1,0 +1,1 @@
+    +b
+    diff --git a/file3 b/file3
+    new file mode 100755
+    index 0000000000..f2ad6c76f0
+    --- /dev/null
+    +++ b/file3
+    @@ -1,0 +1,1 @@
+    +c
+    "###);
+
+    // Empty input selects all files
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(
+        &repo_path,
+        &["file", "chmod", "n", "-i", "file1", "file2"],
+        "\n",
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    matched files:
+    1: x file1
+    2: x file2
+    q: quit the prompt
+    enter the indices of the files to change (empty for all):
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz ab846918 base | base
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+
+    // Quitting the prompt doesn't change anything
+    let assert = test_env
+        .jj_cmd_stdin(&repo_path, &["file", "chmod", "x", "-i", "file1"], "q\n")
+        .assert()
+        .code(1);
+    let stdout = test_env.normalize_output(&get_stdout_string(&assert));
+    let stderr = test_env.normalize_output(&get_stderr_string(&assert));
+    insta::assert_snapshot!(stdout, @r###"
+    matched files:
+    1: n file1
+    q: quit the prompt
+    enter the indices of the files to change (empty for all):
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No files selected
+    "###);
+}
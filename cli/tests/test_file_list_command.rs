@@ -0,0 +1,62 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_file_list_count() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::create_dir_all(repo_path.join("dir").join("sub")).unwrap();
+    std::fs::write(repo_path.join("dir").join("file2"), "b\n").unwrap();
+    std::fs::write(repo_path.join("dir").join("file3.rs"), "c\n").unwrap();
+    std::fs::write(repo_path.join("dir").join("sub").join("file4.rs"), "d\n").unwrap();
+
+    // The count is the number of listed paths
+    for fileset in [
+        "all()",
+        "dir",
+        r#"glob:"**/*.rs""#,
+        "dir ~ dir/sub",
+        "none()",
+    ] {
+        let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", fileset]);
+        let count = test_env.jj_cmd_success(&repo_path, &["file", "list", "--count", fileset]);
+        assert_eq!(count, format!("{}\n", stdout.lines().count()), "{fileset}");
+    }
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", "--count"]);
+    insta::assert_snapshot!(stdout, @r###"
+    4
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", "--count", "dir"]);
+    insta::assert_snapshot!(stdout, @r###"
+    3
+    "###);
+
+    // Disjoint filesets match nothing
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", "--count", "dir & file1"]);
+    insta::assert_snapshot!(stdout, @r###"
+    0
+    "###);
+
+    // Counts files in the given revision
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", "--count", "-r", "root()"]);
+    insta::assert_snapshot!(stdout, @r###"
+    0
+    "###);
+}
@@ -389,6 +389,30 @@ fn test_git_colocated_branch_forget() {
     insta::assert_snapshot!(get_branch_output(&test_env, &workspace_root), @"");
 }
 
+#[test]
+fn test_git_colocated_branch_forget_git_tracking() {
+    let test_env = TestEnvironment::default();
+    let workspace_root = test_env.env_root().join("repo");
+    let _git_repo = git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["git", "init", "--git-repo", "."]);
+    test_env.jj_cmd_ok(&workspace_root, &["new"]);
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "foo"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &workspace_root), @r###"
+    foo: rlvkpnrz 65b6b74e (empty) (no description set)
+      @git: rlvkpnrz 65b6b74e (empty) (no description set)
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["branch", "forget", "foo@git"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Hint: Git-tracking branches were exported again from the local branches to the colocated Git repo, so forgetting them had no effect: foo@git
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &workspace_root), @r###"
+    foo: rlvkpnrz 65b6b74e (empty) (no description set)
+      @git: rlvkpnrz 65b6b74e (empty) (no description set)
+    "###);
+}
+
 #[test]
 fn test_git_colocated_conflicting_git_refs() {
     let test_env = TestEnvironment::default();
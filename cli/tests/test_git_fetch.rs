@@ -119,6 +119,120 @@ fn test_git_fetch_single_remote() {
     "###);
 }
 
+#[test]
+fn test_git_fetch_track_default_branch() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "rem1");
+    add_git_remote(&test_env, &repo_path, "rem2");
+    // rem1's HEAD points to its branch, rem2's HEAD is unborn
+    let rem1_git_repo = git2::Repository::open(test_env.env_root().join("rem1")).unwrap();
+    rem1_git_repo.set_head("refs/heads/rem1").unwrap();
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "fetch", "--all-remotes", "--track-default-branch"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    branch: rem1@rem1 [new] untracked
+    Started tracking the default branch of remote rem1: rem1
+    branch: rem2@rem2 [new] untracked
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    rem1: qxosxrvv 6a211027 message
+      @rem1: qxosxrvv 6a211027 message
+    rem2@rem2: yszkquru 2497a8a0 message
+    "###);
+
+    // The default branch is only tracked on the first fetch from a remote
+    test_env.jj_cmd_ok(&repo_path, &["branch", "untrack", "rem1@rem1"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "delete", "rem1"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "fetch", "--remote=rem1", "--track-default-branch"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    rem1@rem1: qxosxrvv 6a211027 message
+    rem2@rem2: yszkquru 2497a8a0 message
+    "###);
+}
+
+#[test]
+fn test_git_fetch_track() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "rem1");
+    add_git_remote(&test_env, &repo_path, "rem2");
+
+    // Only the matching branches are tracked
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "fetch", "--all-remotes", "--track=rem1"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    branch: rem1@rem1 [new] untracked
+    Started tracking remote branch rem1@rem1
+    branch: rem2@rem2 [new] untracked
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    rem1: qxosxrvv 6a211027 message
+      @rem1: qxosxrvv 6a211027 message
+    rem2@rem2: yszkquru 2497a8a0 message
+    "###);
+
+    // Branches that are already tracked are left alone
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "fetch", "--all-remotes", "--track=glob:rem*"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Started tracking remote branch rem2@rem2
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    rem1: qxosxrvv 6a211027 message
+      @rem1: qxosxrvv 6a211027 message
+    rem2: yszkquru 2497a8a0 message
+      @rem2: yszkquru 2497a8a0 message
+    "###);
+}
+
+#[test]
+fn test_git_fetch_no_tags() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "rem1");
+    let git_repo = git2::Repository::open(test_env.env_root().join("rem1")).unwrap();
+    let commit_id = git_repo.refname_to_id("refs/heads/rem1").unwrap();
+    git_repo
+        .reference("refs/tags/v1.0", commit_id, false, "")
+        .unwrap();
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--no-tags"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Hint: Fetching from the only existing remote: rem1
+    branch: rem1@rem1 [new] untracked
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["tag", "list"]);
+    insta::assert_snapshot!(stdout, @"");
+
+    // Tags are fetched by default
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Hint: Fetching from the only existing remote: rem1
+    tag: v1.0 [new]
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["tag", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    v1.0: qxosxrvv 6a211027 message
+    "###);
+}
+
 #[test]
 fn test_git_fetch_single_remote_all_remotes_flag() {
     let test_env = TestEnvironment::default();
@@ -226,6 +340,32 @@ fn test_git_fetch_multiple_remotes_from_config() {
     "###);
 }
 
+#[test]
+fn test_git_fetch_multiple_remotes_one_unreachable() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("git.auto-local-branch = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "rem1");
+    // The remote exists but its repo doesn't
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "remote", "add", "rem2", "../nonexistent"],
+    );
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(stderr, @r###"
+    branch: rem1@rem1 [new] tracked
+    Warning: Failed to fetch from remote rem2: unsupported URL protocol; class=Net (12)
+    Error: Failed to fetch from remote(s) rem2
+    "###);
+    // The branches from the reachable remote should have been fetched
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    rem1: qxosxrvv 6a211027 message
+      @rem1: qxosxrvv 6a211027 message
+    "###);
+}
+
 #[test]
 fn test_git_fetch_nonexistent_remote() {
     let test_env = TestEnvironment::default();
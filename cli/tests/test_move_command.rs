@@ -89,6 +89,9 @@ fn test_move() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Warning: Moving branches c from abandoned commit mzvwutvl 59597b34 c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1
     Working copy now at: kmkuslsw a45950b1 f | (no description set)
     Parent commit      : znkkpsqq c2f9de87 e | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -120,6 +123,9 @@ fn test_move() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Warning: Moving branches d from abandoned commit vruxwmqv e0dac715 d | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file3
     Working copy now at: kmkuslsw 5e5727af f | (no description set)
     Parent commit      : znkkpsqq ed9c4164 e | (no description set)
     "###);
@@ -148,6 +154,9 @@ fn test_move() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Warning: Moving branches e from abandoned commit znkkpsqq c2f9de87 e | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file2
     Rebased 1 descendant commits
     Working copy now at: kmkuslsw e21f6bb0 f | (no description set)
     Parent commit      : vruxwmqv 3cf0fa77 d e | (no description set)
@@ -215,6 +224,9 @@ fn test_move_partial() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Warning: Moving branches c from abandoned commit mzvwutvl 087591be c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1, file2
     Working copy now at: vruxwmqv 987bcfb2 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 2 files, removed 0 files
@@ -249,6 +261,7 @@ fn test_move_partial() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Moved changes to: file1
     Working copy now at: vruxwmqv 576244e8 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -286,6 +299,7 @@ fn test_move_partial() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Moved changes to: file1
     Working copy now at: vruxwmqv 5b407c24 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -324,6 +338,7 @@ fn test_move_partial() {
     insta::assert_snapshot!(stderr, @r###"
     Warning: `jj move` is deprecated; use `jj squash` instead, which is equivalent
     Warning: `jj move` will be removed in a future version, and this will be a hard error
+    Moved changes to: file1
     Rebased 1 descendant commits
     "###);
     insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
@@ -334,6 +334,51 @@ fn test_next_choose_branching_child() {
     "###);
 }
 
+#[test]
+fn test_next_prev_list_branching() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "@--"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "third"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "@--"]);
+
+    // List the candidates without prompting or moving the working copy
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "--list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    1: zsuskuln 5f24490d (empty) third
+    2: rlvkpnrz 9ed53a4a (empty) second
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-r", "@-", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  first
+    │
+    ~
+    "###);
+
+    // A merge commit has multiple candidate parents
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["new", "description(second)", "description(third)"],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "--list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    1: zsuskuln 5f24490d (empty) third
+    2: rlvkpnrz 9ed53a4a (empty) second
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+
+    // No candidates is still an error
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["next", "--list"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No descendant found 1 commit forward
+    "###);
+}
+
 #[test]
 fn test_prev_on_merge_commit() {
     let test_env = TestEnvironment::default();
@@ -706,6 +751,149 @@ fn test_next_conflict_editing() {
     "###);
 }
 
+#[test]
+fn test_next_prev_conflict_marker_style() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let file_path = repo_path.join("content.txt");
+    std::fs::write(&file_path, "first\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    // Create a conflict in the third commit.
+    std::fs::write(&file_path, "third\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "third"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(first)"]);
+    std::fs::write(&file_path, "first v2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["squash", "--into", "description(third)"]);
+
+    // The conflict is written with Git-style markers
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["next", "--conflict", "--conflict-marker-style", "git"],
+    );
+    insta::assert_snapshot!(std::fs::read_to_string(&file_path).unwrap(), @r###"
+    <<<<<<< Side #1 (Conflict 1 of 1)
+    third
+    ||||||| Base
+    first
+    =======
+    first v2
+    >>>>>>> Side #2 (Conflict 1 of 1 ends)
+    "###);
+
+    // Resolve the conflict in a child commit, and move back to the conflict
+    std::fs::write(&file_path, "resolved\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "fourth"]);
+    test_env.jj_cmd_ok(&repo_path, &["prev", "--conflict-marker-style", "snapshot"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  yostqsxwqrlt conflict
+    │ ◉  yqosqzytrlsw fourth
+    ├─╯
+    ◉  kkmpptxzrspx conflict third
+    ◉  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+    insta::assert_snapshot!(std::fs::read_to_string(&file_path).unwrap(), @r###"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1
+    third
+    ------- Contents of base
+    first
+    +++++++ Contents of side #2
+    first v2
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+
+    // The conflict markers can be parsed back when snapshotting
+    let content = std::fs::read_to_string(&file_path).unwrap();
+    std::fs::write(&file_path, content.replace("first v2", "first v3")).unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    content.txt    2-sided conflict
+    "###);
+}
+
+#[test]
+fn test_next_prev_conflict_in() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("a.txt"), "first").unwrap();
+    std::fs::write(repo_path.join("b.txt"), "first").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("b.txt"), "second").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    std::fs::write(repo_path.join("a.txt"), "third").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "third"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "fourth"]);
+    // Create a conflict in b.txt in the second commit, and in a.txt in the
+    // third commit. The conflict in b.txt is resolved in the third commit.
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(first)"]);
+    std::fs::write(repo_path.join("a.txt"), "first v2").unwrap();
+    std::fs::write(repo_path.join("b.txt"), "first v2").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(third)"]);
+    std::fs::write(repo_path.join("b.txt"), "resolved").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(fourth)"]);
+    // Test the setup
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  vruxwmqvtpmx conflict
+    ◉  zsuskulnrvyr conflict fourth
+    ◉  kkmpptxzrspx conflict third
+    ◉  rlvkpnrzqnoo conflict second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // Skips the commits that only have conflicts in other paths
+    test_env.jj_cmd_ok(&repo_path, &["prev", "--conflict-in", "b.txt"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  znkkpsqqskkl conflict
+    │ ◉  zsuskulnrvyr conflict fourth
+    │ ◉  kkmpptxzrspx conflict third
+    ├─╯
+    ◉  rlvkpnrzqnoo conflict second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(first)"]);
+    test_env.jj_cmd_ok(&repo_path, &["next", "--conflict-in", "a.txt"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  wqnwkozpkust conflict
+    │ ◉  zsuskulnrvyr conflict fourth
+    ├─╯
+    ◉  kkmpptxzrspx conflict third
+    ◉  rlvkpnrzqnoo conflict second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // No matching conflicts
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["next", "--conflict-in", "b.txt"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No descendant found with conflicts in the given paths
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["prev", "--conflict-in", "c.txt"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No ancestor found with conflicts in the given paths
+    "###);
+
+    // Cannot be combined with --conflict
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["next", "--conflict", "--conflict-in", "a.txt"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--conflict' cannot be used with '--conflict-in <FILESETS>'
+
+    Usage: jj next --conflict [OFFSET]
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_next_conflict_head() {
     // When editing a head with conflicts, `jj next --conflict [--edit]` errors out.
@@ -732,7 +920,211 @@ fn test_next_conflict_head() {
     "###);
 }
 
+#[test]
+fn test_next_prev_zero_offset() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+
+    // Without --edit, a new working-copy commit is created on the same parent
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "0"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: zsuskuln c97da310 (empty) (no description set)
+    Parent commit      : rlvkpnrz 9ed53a4a (empty) second
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "0"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: mzvwutvl c3b1a952 (empty) (no description set)
+    Parent commit      : rlvkpnrz 9ed53a4a (empty) second
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  mzvwutvlkqwt
+    ◉  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // With --edit, the parent is edited directly
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "0", "--edit"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 9ed53a4a (empty) second
+    Parent commit      : qpvuntsm fa15625b (empty) first
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "0", "--edit"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 9ed53a4a (empty) second
+    Parent commit      : qpvuntsm fa15625b (empty) first
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // If --edit is implied, the working-copy commit stays as is
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(first)"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "0"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "0"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    ◉  rlvkpnrzqnoo second
+    @  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+}
+
+#[test]
+fn test_next_prev_edit_immutable() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config(r#"revset-aliases."immutable_heads()" = "description(second)""#);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "third"]);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["prev", "--edit", "2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot edit immutable commit 9ed53a4a1bec
+    Hint: Pass `--ignore-immutable` or configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    Hint: Run `jj prev` without `--edit` to create a new working-copy commit on top of it instead.
+    "###);
+
+    // With implied --edit, the hint to drop --edit isn't shown
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["edit", "--ignore-immutable", "description(first)"],
+    );
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["next"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot edit immutable commit 9ed53a4a1bec
+    Hint: Pass `--ignore-immutable` or configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    "###);
+}
+
 fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
     let template = r#"separate(" ", change_id.short(), local_branches, if(conflict, "conflict"), description)"#;
     test_env.jj_cmd_success(cwd, &["log", "-T", template])
 }
+
+#[test]
+fn test_prev_no_snapshot() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "first\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("file"), "second\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+
+    // The dirty file isn't snapshotted into the working-copy commit, so it's
+    // overwritten when moving
+    std::fs::write(repo_path.join("file"), "dirty\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "--no-snapshot"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: zsuskuln 4fa9cf44 (empty) (no description set)
+    Parent commit      : qpvuntsm e8249df7 first
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  zsuskulnrvyr
+    │ ◉  rlvkpnrzqnoo second
+    ├─╯
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+    insta::assert_snapshot!(std::fs::read_to_string(repo_path.join("file")).unwrap(), @r###"
+    first
+    "###);
+}
+
+#[test]
+fn test_next_prev_stop_at() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "third"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "-r@-", "stop"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "fourth"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "fifth"]);
+
+    // Moving backward stops at the first commit matching the guard
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["prev", "10", "--stop-at", "branches()"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Stopped at commit matching --stop-at: kkmpptxz 30056b0c stop | (empty) third
+    Working copy now at: vruxwmqv 0c7d7732 (empty) (no description set)
+    Parent commit      : kkmpptxz 30056b0c stop | (empty) third
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  vruxwmqvtpmx
+    │ ◉  royxmykxtrkr fifth
+    │ ◉  zsuskulnrvyr fourth
+    ├─╯
+    ◉  kkmpptxzrspx stop third
+    ◉  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // Moving forward stops at the first commit matching the guard
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(first)"]);
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["next", "10", "--stop-at", "branches()"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Stopped at commit matching --stop-at: kkmpptxz 30056b0c stop | (empty) third
+    Working copy now at: kkmpptxz 30056b0c stop | (empty) third
+    Parent commit      : rlvkpnrz 9ed53a4a (empty) second
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    ◉  royxmykxtrkr fifth
+    ◉  zsuskulnrvyr fourth
+    @  kkmpptxzrspx stop third
+    ◉  rlvkpnrzqnoo second
+    ◉  qpvuntsmwlqt first
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // If no commit within the offset matches, the full offset is moved
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["next", "2", "--stop-at", "description(first)"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: royxmykx 195758bd (empty) fifth
+    Parent commit      : zsuskuln 9150bf00 (empty) fourth
+    "###);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["next", "10", "--stop-at", "description(first)"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No descendant found 10 commits forward
+    "###);
+}
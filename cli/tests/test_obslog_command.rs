@@ -142,6 +142,76 @@ fn test_obslog_with_or_without_diff() {
     "###);
 }
 
+#[test]
+fn test_obslog_conflict_marker_style() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "my description"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["rebase", "-r", "@", "-d", "root()"]);
+    std::fs::write(repo_path.join("file1"), "resolved\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "obslog",
+            "--no-graph",
+            "--git",
+            "--limit=1",
+            "--conflict-marker-style=snapshot",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    rlvkpnrz test.user@example.com 2001-02-03 08:05:10 7ce3cb39
+    my description
+    diff --git a/file1 b/file1
+    index 0000000000...2ab19ae607 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,8 +1,1 @@
+    -<<<<<<< Conflict 1 of 1
+    -+++++++ Contents of side #1
+    -------- Contents of base
+    -foo
+    -+++++++ Contents of side #2
+    -foo
+    -bar
+    ->>>>>>> Conflict 1 of 1 ends
+    +resolved
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "obslog",
+            "--no-graph",
+            "--git",
+            "--limit=1",
+            "--conflict-marker-style=git",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    rlvkpnrz test.user@example.com 2001-02-03 08:05:10 7ce3cb39
+    my description
+    diff --git a/file1 b/file1
+    index 0000000000...2ab19ae607 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,7 +1,1 @@
+    -<<<<<<< Side #1 (Conflict 1 of 1)
+    -||||||| Base
+    -foo
+    -=======
+    -foo
+    -bar
+    ->>>>>>> Side #2 (Conflict 1 of 1 ends)
+    +resolved
+    "###);
+}
+
 #[test]
 fn test_obslog_with_custom_symbols() {
     let test_env = TestEnvironment::default();
@@ -263,6 +333,91 @@ fn test_obslog_squash() {
     "###);
 }
 
+#[test]
+fn test_obslog_squash_with_paths() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\nbaz\n").unwrap();
+
+    let edit_script = test_env.set_up_fake_editor();
+    std::fs::write(edit_script, "write\nsquashed").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["squash"]);
+
+    // Only the hunks of the matching paths are shown
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "--no-graph", "--git", "-r", "@-", "file1"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    qpvuntsm test.user@example.com 2001-02-03 08:05:10 82b9fe2f
+    squashed
+    diff --git a/file1 b/file1
+    index 257cc5642c...3bd1f0e297 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,1 +1,2 @@
+     foo
+    +bar
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:09 adea7201
+    first
+    diff --git a/file1 b/file1
+    new file mode 100644
+    index 0000000000..257cc5642c
+    --- /dev/null
+    +++ b/file1
+    @@ -1,0 +1,1 @@
+    +foo
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:08 fa15625b
+    (empty) first
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:07 230dd059
+    (empty) (no description set)
+    kkmpptxz hidden test.user@example.com 2001-02-03 08:05:10 5859b896
+    second
+    diff --git a/file1 b/file1
+    index 257cc5642c...3bd1f0e297 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,1 +1,2 @@
+     foo
+    +bar
+    kkmpptxz hidden test.user@example.com 2001-02-03 08:05:09 5f46d0cc
+    (empty) second
+    "###);
+
+    // Paths imply --patch if no diff format is specified
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["obslog", "--no-graph", "-r", "@-", "file2"]);
+    insta::assert_snapshot!(stdout, @r###"
+    qpvuntsm test.user@example.com 2001-02-03 08:05:10 82b9fe2f
+    squashed
+    Modified regular file file2:
+       1    1: foo
+            2: baz
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:09 adea7201
+    first
+    Added regular file file2:
+            1: foo
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:08 fa15625b
+    (empty) first
+    qpvuntsm hidden test.user@example.com 2001-02-03 08:05:07 230dd059
+    (empty) (no description set)
+    kkmpptxz hidden test.user@example.com 2001-02-03 08:05:10 5859b896
+    second
+    Modified regular file file2:
+       1    1: foo
+            2: baz
+    kkmpptxz hidden test.user@example.com 2001-02-03 08:05:09 5f46d0cc
+    (empty) second
+    "###);
+}
+
 #[test]
 fn test_obslog_with_no_template() {
     let test_env = TestEnvironment::default();
@@ -291,3 +446,397 @@ fn test_obslog_with_no_template() {
     - name_placeholder
     "###);
 }
+
+#[test]
+fn test_obslog_op_id() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+
+    let template = r#"commit_id.short() ++ " " ++ description"#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["obslog", "--op-id", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  f9dec4ae3d88 f325a3c0c6ef second
+    ◉  0c5b9b667f91 766420db930c first
+    ◉  550c7992bb0a fa15625b4a98 first
+    ◉  b51416386f26 230dd059e1b0
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "--op-id", "--no-graph", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    f9dec4ae3d88 f325a3c0c6ef second
+    0c5b9b667f91 766420db930c first
+    550c7992bb0a fa15625b4a98 first
+    b51416386f26 230dd059e1b0
+    "###);
+
+    // The op ids match the operations in the op log
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "-T",
+            r#"id.short() ++ " " ++ description ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    f9dec4ae3d88 describe commit 766420db930c4af683c9261908dfbd7deb3c6e12
+    0c5b9b667f91 snapshot working copy
+    550c7992bb0a describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    b51416386f26 add workspace 'default'
+    9a7d829846af initialize repo
+    000000000000
+    "###);
+}
+
+#[test]
+fn test_obslog_op_id_concurrent_operations() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "b1"]);
+    let op_id =
+        test_env.jj_cmd_success(&repo_path, &["op", "log", "--no-graph", "-T", "id", "-n1"]);
+
+    // Rewrite both changes in concurrent operations
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "--at-op",
+            &op_id,
+            "-r",
+            "description(a1)",
+            "-m",
+            "a2",
+        ],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "--at-op",
+            &op_id,
+            "-r",
+            "description(b1)",
+            "-m",
+            "b2",
+        ],
+    );
+
+    let template = r#"commit_id.short() ++ " " ++ description"#;
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "obslog",
+            "--op-id",
+            "--no-graph",
+            "-T",
+            template,
+            "-r",
+            "description(a2) | description(b2)",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    a3151321c693 b328ffbaf53b b2
+    86657280d192 e6d2192dfd68 b1
+    bb907d931449 88bb4d8fa16a a2
+    59ead4708493 60e5380caa71 a1
+    b51416386f26 230dd059e1b0
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Concurrent modification detected, resolving automatically.
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "-T",
+            r#"id.short() ++ " " ++ description ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    @    ad7d28da7615 resolve concurrent operations
+    ├─╮
+    ◉ │  bb907d931449 describe commit 60e5380caa717c7805d535522cf25e41157657cb
+    │ ◉  a3151321c693 describe commit e6d2192dfd68c9c219159e055b317dd5a1c20b2f
+    ├─╯
+    ◉  86657280d192 new empty commit
+    ◉  59ead4708493 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ◉  b51416386f26 add workspace 'default'
+    ◉  9a7d829846af initialize repo
+    ◉  000000000000
+    "###);
+}
+
+#[test]
+fn test_obslog_op_id_concurrently_abandoned() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "b1"]);
+    let commit_id = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            "commit_id",
+            "-r",
+            "description(a)",
+        ],
+    );
+    let op_id =
+        test_env.jj_cmd_success(&repo_path, &["op", "log", "--no-graph", "-T", "id", "-n1"]);
+
+    // Keep the commit in one operation and abandon it in a concurrent one. The
+    // commit was still created before both of them.
+    test_env.jj_cmd_ok(&repo_path, &["describe", "--at-op", &op_id, "-m", "b2"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["abandon", "--at-op", &op_id, "description(a)"],
+    );
+
+    let template = r#"commit_id.short() ++ " " ++ description"#;
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "obslog",
+            "--op-id",
+            "--no-graph",
+            "-T",
+            template,
+            "-r",
+            &commit_id,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    91e962704228 d8d5f980a897 a
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Concurrent modification detected, resolving automatically.
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "-T",
+            r#"id.short() ++ " " ++ description ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    @    e1849456107d resolve concurrent operations
+    ├─╮
+    ◉ │  d95484a0365a describe commit e6d2192dfd68c9c219159e055b317dd5a1c20b2f
+    │ ◉  fbe5dd7e46e9 abandon commit d8d5f980a897bec1a085986377897c00e531ebce
+    ├─╯
+    ◉  6039153ace3b new empty commit
+    ◉  91e962704228 new empty commit
+    ◉  b51416386f26 add workspace 'default'
+    ◉  9a7d829846af initialize repo
+    ◉  000000000000
+    "###);
+}
+
+#[test]
+fn test_obslog_multiple_revisions() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a1"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "b1"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "b2"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["describe", "-r", "description(a2)", "-m", "a3"],
+    );
+
+    let template = r#"
+    separate(" ", change_id.short(), commit_id.short(), description.first_line()) ++ "\n"
+    "#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "-T", template, "-r", "description(a3) | @"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  qpvuntsmwlqt c4a48aa3cb57 a3
+    ◉  qpvuntsmwlqt 5dc5bb8d72a1 a2
+    ◉  qpvuntsmwlqt 60e5380caa71 a1
+    ◉  qpvuntsmwlqt 230dd059e1b0
+    │
+    ~
+
+    @  zsuskulnrvyr 7f68501f8d7c b2
+    ◉  zsuskulnrvyr ac35c8b8c792 b1
+    │
+    ~
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "obslog",
+            "-T",
+            template,
+            "--no-graph",
+            "-r",
+            "description(a3)",
+            "-r",
+            "@",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    qpvuntsmwlqt c4a48aa3cb57 a3
+    qpvuntsmwlqt 5dc5bb8d72a1 a2
+    qpvuntsmwlqt 60e5380caa71 a1
+    qpvuntsmwlqt 230dd059e1b0
+    zsuskulnrvyr 7f68501f8d7c b2
+    zsuskulnrvyr ac35c8b8c792 b1
+    "###);
+}
+
+#[test]
+fn test_obslog_hide_noop() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "my description"]);
+    // Rewrite the commit without changing its tree or description
+    test_env.jj_cmd_ok(&repo_path, &["describe", "--reset-author", "--no-edit"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "--reset-author", "--no-edit"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "--reset-author", "--no-edit"]);
+    std::fs::write(repo_path.join("file1"), "bar\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "--reset-author", "--no-edit"]);
+
+    let template = r#"separate(" ", commit_id.short(), description.first_line()) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["obslog", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  7a7b42f9cada my description
+    ◉  bcf04ea80790 my description
+    ◉  fc7907760ec4 my description
+    ◉  18dbe6ed4155 my description
+    ◉  cbc702e9913e my description
+    ◉  fffcc4bb53d1 my description
+    ◉  0e15949eed93
+    ◉  230dd059e1b0
+    "###);
+
+    // The metadata-only rewrites are collapsed into the newest of them
+    let stdout = test_env.jj_cmd_success(&repo_path, &["obslog", "-T", template, "--hide-noop"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  7a7b42f9cada my description
+    │  (1 metadata-only rewrites)
+    ◉  fc7907760ec4 my description
+    │  (3 metadata-only rewrites)
+    ◉  0e15949eed93
+    ◉  230dd059e1b0
+    "###);
+
+    // The patch is shown against the version before the collapsed rewrites
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "-T", template, "--hide-noop", "--no-graph", "-p"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    7a7b42f9cada my description
+    (1 metadata-only rewrites)
+    Modified regular file file1:
+       1    1: foobar
+    fc7907760ec4 my description
+    (3 metadata-only rewrites)
+    0e15949eed93
+    Added regular file file1:
+            1: foo
+    230dd059e1b0
+    "###);
+}
+
+#[test]
+fn test_obslog_diff_tool() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "my description"]);
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+
+    let edit_script = test_env.set_up_fake_diff_editor();
+    std::fs::write(
+        &edit_script,
+        "print-files-before\0print --\0print-files-after",
+    )
+    .unwrap();
+
+    // The tool is run once per version with a previous version
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(
+        &repo_path,
+        &["obslog", "-r=@-", "--diff-tool=fake-diff-editor"],
+        "",
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    --
+    file2
+    --
+    --
+    file1
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Opening changes in qpvuntsm e8b594ac my description
+    Opening changes in qpvuntsm hidden fffcc4bb my description
+    Opening changes in qpvuntsm hidden 0e15949e (no description set)
+    "###);
+
+    // Paths restrict the diff shown in the tool
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        &repo_path,
+        &["obslog", "-r=@-", "--diff-tool=fake-diff-editor", "file2"],
+        "",
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    --
+    file2
+    --
+    --
+    "###);
+
+    // The tool needs a terminal
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["obslog", "-r=@-", "--diff-tool=fake-diff-editor"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot open an external diff tool without a terminal
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["obslog", "--diff-tool=fake-diff-editor", "-p"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--diff-tool <NAME>' cannot be used with '--patch'
+
+    Usage: jj obslog --diff-tool <NAME> [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
@@ -96,6 +96,7 @@ fn test_report_conflicts() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file
     Existing conflicts were resolved or abandoned from these commits:
       rlvkpnrz hidden eb93a73d (conflict) B
     Working copy now at: yostqsxw f5a0cf8c (empty) (no description set)
@@ -284,6 +284,45 @@ fn test_resolution() {
     file    2-sided conflict
     "###);
 
+    // Check that malformed conflict markers left by the merge tool are reported
+    // and treated as resolved content
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(
+        &editor_script,
+        indoc! {"
+            write
+            <<<<<<<
+            %%%%%%%
+            -some
+            fake
+            +++++++
+            conflict
+            >>>>>>>
+        "},
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "resolve",
+            "--config-toml",
+            "merge-tools.fake-editor.merge-tool-edits-conflict-markers=true",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Resolving conflicts in: file
+    Warning: Treating conflict markers at line 1 of the output file as resolved content: Unexpected line in diff section: "fake"
+    Working copy now at: vruxwmqv 5ec824d4 conflict | conflict
+    Parent commit      : zsuskuln aa493daf a | a
+    Parent commit      : royxmykx db6a4daf b | b
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(test_env.jj_cmd_cli_error(&repo_path, &["resolve", "--list"]),
+    @r###"
+    Error: No conflicts found at this revision
+    "###);
+
     // Check that if merge tool leaves conflict markers in output file but
     // `merge-tool-edits-conflict-markers=false` or is not specified,
     // `jj` considers the conflict resolved.
@@ -312,7 +351,7 @@ fn test_resolution() {
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
     Resolving conflicts in: file
-    Working copy now at: vruxwmqv 3166dfd2 conflict | conflict
+    Working copy now at: vruxwmqv e32488a2 conflict | conflict
     Parent commit      : zsuskuln aa493daf a | a
     Parent commit      : royxmykx db6a4daf b | b
     Added 0 files, modified 1 files, removed 0 files
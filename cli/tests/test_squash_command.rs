@@ -42,6 +42,9 @@ fn test_squash() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches c from abandoned commit mzvwutvl 382c9bad c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1
     Working copy now at: vruxwmqv f7bb78d8 (empty) (no description set)
     Parent commit      : kkmpptxz 59f44460 b c | (no description set)
     "###);
@@ -61,6 +64,9 @@ fn test_squash() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches b from abandoned commit kkmpptxz d5d59175 b | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1
     Rebased 1 descendant commits
     Working copy now at: mzvwutvl 1d70f50a c | (no description set)
     Parent commit      : qpvuntsm 9146bcc8 a b | (no description set)
@@ -101,6 +107,7 @@ fn test_squash() {
     let stderr = test_env.jj_cmd_failure(&repo_path, &["squash"]);
     insta::assert_snapshot!(stderr, @r###"
     Error: Cannot squash merge commits
+    Hint: Use `--into` to specify which parent to squash into.
     "###);
 
     // Can squash into a merge commit
@@ -109,6 +116,7 @@ fn test_squash() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Working copy now at: xlzxqlsl b50b843d (empty) (no description set)
     Parent commit      : nmzmmopx 338cbc05 e | (no description set)
     "###);
@@ -160,6 +168,9 @@ fn test_squash_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b", "-i"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches b from abandoned commit kkmpptxz d117da27 b | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1, file2
     Rebased 1 descendant commits
     Working copy now at: mzvwutvl 3c633226 c | (no description set)
     Parent commit      : qpvuntsm 38ffd8b9 a b | (no description set)
@@ -180,6 +191,7 @@ fn test_squash_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b", "-i"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file2
     Rebased 2 descendant commits
     Working copy now at: mzvwutvl 57c3cf20 c | (no description set)
     Parent commit      : kkmpptxz c4925e01 b | (no description set)
@@ -214,6 +226,7 @@ fn test_squash_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b", "file2"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file2
     Rebased 2 descendant commits
     Working copy now at: mzvwutvl 64d7ad7c c | (no description set)
     Parent commit      : kkmpptxz 60a26452 b | (no description set)
@@ -241,6 +254,38 @@ fn test_squash_partial() {
     b
     "###);
 
+    // Paths restrict the changes shown in the diff editor in interactive mode
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(
+        &edit_script,
+        "files-before file1\0files-after JJ-INSTRUCTIONS file1",
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b", "-i", "file1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
+    Rebased 2 descendant commits
+    Working copy now at: mzvwutvl bbbbeb14 c | (no description set)
+    Parent commit      : kkmpptxz 037d5665 b | (no description set)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file2", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    "###);
+
+    // The interactive selection is intersected with the paths
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(&edit_script, "reset file1").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash", "-r", "b", "-i", "file1"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No changes selected
+    "###);
+
     // If we specify only a non-existent file, then nothing changes.
     test_env.jj_cmd_ok(&repo_path, &["undo"]);
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "b", "nonexistent"]);
@@ -318,6 +363,9 @@ fn test_squash_from_to() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--from", "c"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches c from abandoned commit mzvwutvl 59597b34 c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1
     Working copy now at: kmkuslsw b902d1dd f | (no description set)
     Parent commit      : znkkpsqq c2f9de87 e | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -347,6 +395,9 @@ fn test_squash_from_to() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--from", "@--"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches d from abandoned commit vruxwmqv e0dac715 d | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file3
     Working copy now at: kmkuslsw cfc5eb87 f | (no description set)
     Parent commit      : znkkpsqq 4dc7c279 e | (no description set)
     "###);
@@ -374,6 +425,9 @@ fn test_squash_from_to() {
         test_env.jj_cmd_ok(&repo_path, &["squash", "--from", "e", "--into", "d"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches e from abandoned commit znkkpsqq c2f9de87 e | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file2
     Rebased 1 descendant commits
     Working copy now at: kmkuslsw 6de62c22 f | (no description set)
     Parent commit      : vruxwmqv 32196a11 d e | (no description set)
@@ -396,6 +450,233 @@ fn test_squash_from_to() {
     "###);
 }
 
+#[test]
+fn test_squash_from_descendant_into_ancestor() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Create history like this:
+    // D
+    // |
+    // C
+    // |
+    // B
+    // |
+    // A
+    //
+    // When moving changes from C into A, the changes should end up in A, be
+    // removed from C, and B and D should keep their own changes.
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "a"]);
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    std::fs::write(repo_path.join("file3"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "c"]);
+    std::fs::write(repo_path.join("file1"), "c\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "c\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "d"]);
+    std::fs::write(repo_path.join("file4"), "d\n").unwrap();
+    // Test the setup
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  ab396b085911 d
+    ◉  cee4a7776464 c
+    ◉  5a1f256a8075 b
+    ◉  54d3c1c0e9fd a
+    ◉  000000000000
+    "###);
+
+    // Move part of the changes from the descendant
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["squash", "--from", "c", "--into", "a", "file1"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
+    Rebased 3 descendant commits
+    Working copy now at: yqosqzyt 4339e5e4 d | (no description set)
+    Parent commit      : mzvwutvl ea003dea c | (no description set)
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  4339e5e4dae1 d
+    ◉  ea003dea41fe c
+    ◉  2bd2213783f2 b
+    ◉  d820192118cb a
+    ◉  000000000000
+    "###);
+    // The selected change has been applied to the ancestor
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file1
+    A file2
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+    // The commit in between is unchanged, but sees the moved change
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file3
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+    // The selected change has been removed from the source, but the rest is kept
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "c"]);
+    insta::assert_snapshot!(stdout, @r###"
+    M file2
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "c"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file2", "-r", "c"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+    // The descendant of the source keeps its own change
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "d"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file4
+    "###);
+
+    // Move all changes from the descendant, keeping it
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["squash", "--from", "c", "--into", "a", "--keep-emptied"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1, file2
+    Rebased 3 descendant commits
+    Working copy now at: yqosqzyt e268bd67 d | (no description set)
+    Parent commit      : mzvwutvl 70c7cc5b c | (empty) (no description set)
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  e268bd6714e5 d
+    ◉  70c7cc5bcc41 c
+    ◉  e0a0bdb5316e b
+    ◉  8df74dd0edec a
+    ◉  000000000000
+    "###);
+    // All changes have been applied to the ancestor
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file1
+    A file2
+    "###);
+    // The source is now empty
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "c"]);
+    insta::assert_snapshot!(stdout, @"");
+    // The working copy still has all the files
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file2"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+}
+
+#[test]
+fn test_squash_resolve() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Create history like this:
+    // B C
+    // |/
+    // A
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "a"]);
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    std::fs::write(repo_path.join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "c"]);
+    std::fs::write(repo_path.join("file"), "c\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "c\n").unwrap();
+    // Test the setup
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  0e76be76bcab c
+    │ ◉  35e764e4357c b
+    ├─╯
+    ◉  3b1673b6370c a
+    ◉  000000000000
+    "###);
+
+    // The merge tool fails if it's invoked
+    let edit_script = test_env.set_up_fake_editor();
+    std::fs::write(&edit_script, "fail").unwrap();
+
+    // The merge tool isn't invoked if there are no conflicts
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["squash", "--into", "b", "--resolve", "file2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file2
+    Working copy now at: mzvwutvl 2e963508 c | (no description set)
+    Parent commit      : qpvuntsm 3b1673b6 a | (no description set)
+    Added 0 files, modified 0 files, removed 1 files
+    "###);
+
+    // The merge tool can be specified, which implies --resolve. An invalid
+    // merge tool is reported before squashing.
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["squash", "--into", "b", "--resolve-tool", "nonexistent"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The tool `nonexistent` cannot be used as a merge tool with `jj resolve`
+    Hint: To use `nonexistent` as a merge tool, the config `merge-tools.nonexistent.merge-args` must be defined (see docs for details)
+    "###);
+
+    // Moving the change to a sibling results in a conflict, which is resolved
+    // with the merge tool
+    std::fs::write(&edit_script, "write\nresolved\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--into", "b", "--resolve"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches c from abandoned commit mzvwutvl 0e76be76 c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file, file2
+    New conflicts appeared in these commits:
+      kkmpptxz bdecbdd6 b | (conflict) (no description set)
+    To resolve the conflicts, start by updating to it:
+      jj new kkmpptxzrspx
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you may want inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    Working copy now at: kpqxywon eb96ca25 (empty) (no description set)
+    Parent commit      : qpvuntsm 3b1673b6 a c | (no description set)
+    Added 0 files, modified 1 files, removed 1 files
+    Resolving conflicts in: file
+    Existing conflicts were resolved or abandoned from these commits:
+      kkmpptxz hidden bdecbdd6 (conflict) (no description set)
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    ◉  14ff9b95599e b
+    │ @  eb96ca25c47c
+    ├─╯
+    ◉  3b1673b6370c a c
+    ◉  000000000000
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file", "-r", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    resolved
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file2", "-r", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+}
+
 #[test]
 fn test_squash_from_to_partial() {
     let mut test_env = TestEnvironment::default();
@@ -439,6 +720,9 @@ fn test_squash_from_to_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-i", "--from", "c"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches c from abandoned commit mzvwutvl 087591be c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1, file2
     Working copy now at: vruxwmqv 987bcfb2 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 2 files, removed 0 files
@@ -471,6 +755,7 @@ fn test_squash_from_to_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-i", "--from", "c"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Working copy now at: vruxwmqv 576244e8 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -506,6 +791,7 @@ fn test_squash_from_to_partial() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--from", "c", "file1"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Working copy now at: vruxwmqv 5b407c24 d | (no description set)
     Parent commit      : qpvuntsm b7b76717 a | (no description set)
     Added 0 files, modified 1 files, removed 0 files
@@ -544,6 +830,7 @@ fn test_squash_from_to_partial() {
     );
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Rebased 1 descendant commits
     "###);
     insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
@@ -626,6 +913,11 @@ fn test_squash_from_multiple() {
         test_env.jj_cmd_ok(&repo_path, &["squash", "--from=b", "--from=c", "--into=d"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches b from abandoned commit kkmpptxz 35e764e4 b | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Warning: Moving branches c from abandoned commit mzvwutvl 02a128cd c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file
     Rebased 2 descendant commits
     New conflicts appeared in these commits:
       yqosqzyt 98759deb d | (conflict) (no description set)
@@ -666,6 +958,13 @@ fn test_squash_from_multiple() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--from=b|c|f", "--into=e"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches b from abandoned commit kkmpptxz 35e764e4 b | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Warning: Moving branches c from abandoned commit mzvwutvl 02a128cd c | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Warning: Moving branches f from abandoned commit kpqxywon 94e57ecb f | (no description set) to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file
     Rebased 1 descendant commits
     Working copy now at: xznxytkn 6a670d1a (empty) (no description set)
     Parent commit      : yostqsxw c1293ff7 e f | (no description set)
@@ -751,6 +1050,7 @@ fn test_squash_from_multiple_partial() {
         test_env.jj_cmd_ok(&repo_path, &["squash", "--from=b|c", "--into=d", "file1"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Rebased 2 descendant commits
     New conflicts appeared in these commits:
       yqosqzyt b91b1157 d | (conflict) (no description set)
@@ -810,6 +1110,7 @@ fn test_squash_from_multiple_partial() {
         test_env.jj_cmd_ok(&repo_path, &["squash", "--from=b|c|f", "--into=e", "file1"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
     Rebased 1 descendant commits
     Working copy now at: kpqxywon 3b7559b8 f | (no description set)
     Parent commit      : yostqsxw a3b1714c e | (no description set)
@@ -891,6 +1192,7 @@ fn test_squash_from_multiple_partial_no_op() {
     );
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: b
     Working copy now at: mzvwutvl e178068a d
     Parent commit      : qpvuntsm 2443ea76 a
     Added 1 files, modified 0 files, removed 0 files
@@ -940,6 +1242,274 @@ fn test_squash_from_multiple_partial_no_op() {
     "###);
 }
 
+#[test]
+fn test_squash_from_paths() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Create history like this:
+    // B C D
+    //  \|/
+    //   A
+    let file_x = repo_path.join("x");
+    let file_y = repo_path.join("y");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=a"]);
+    std::fs::write(&file_x, "a\n").unwrap();
+    std::fs::write(&file_y, "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=b"]);
+    std::fs::write(&file_x, "b\n").unwrap();
+    std::fs::write(&file_y, "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "@-", "-m=c"]);
+    std::fs::write(&file_x, "c\n").unwrap();
+    std::fs::write(&file_y, "c\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "@-", "-m=d"]);
+    // Test the setup
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  64e6e5c0788a d
+    │ ◉  2847bbb9fe26 c
+    ├─╯
+    │ ◉  e0585849dce2 b
+    ├─╯
+    ◉  38e84baa140c a
+    ◉  000000000000
+    "###);
+
+    // Take x from b and y from c
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "squash",
+            "--from=description(b)",
+            "--from=description(c)",
+            "--into=@",
+            "-m=d",
+            "--from-paths=description(b)=x",
+            "--from-paths=description(c)=y",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: x, y
+    Working copy now at: mzvwutvl ac480188 d
+    Parent commit      : qpvuntsm 38e84baa a
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  ac480188ec11 d
+    │ ◉  dc28a0c41d2b c
+    ├─╯
+    │ ◉  645d3a4a7095 b
+    ├─╯
+    ◉  38e84baa140c a
+    ◉  000000000000
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "x", "y"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    c
+    "###);
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["file", "show", "-r=description(b)", "x", "y"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    b
+    "###);
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["file", "show", "-r=description(c)", "x", "y"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    a
+    "###);
+
+    // Sources without --from-paths use the positional paths
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "squash",
+            "--from=description(b)",
+            "--from=description(c)",
+            "--into=@",
+            "-m=d",
+            "--from-paths=description(b)=x",
+            "y",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: x, y
+    Working copy now at: mzvwutvl e56cc72d d
+    Parent commit      : qpvuntsm 38e84baa a
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "x", "y"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    c
+    "###);
+
+    // The revision must be one of the sources
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &[
+            "squash",
+            "--from=description(b)",
+            "--into=@",
+            "--from-paths=description(c)=y",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 2847bbb9fe26 is not a source of the squash
+    "###);
+
+    // The source and the fileset must be separated by `=`
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["squash", "--from-paths=x"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'x' for '--from-paths <SOURCE=FILESET>': missing `=` between the source revision and the fileset
+
+    For more information, try '--help'.
+    "###);
+}
+
+#[test]
+fn test_squash_no_source_predecessors() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=a"]);
+    std::fs::write(repo_path.join("a"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=b"]);
+    std::fs::write(repo_path.join("b"), "b\n").unwrap();
+    let obslog_args = [
+        "obslog",
+        "-r=description(a)",
+        "-T",
+        r#"separate(" ", commit_id.short(), description)"#,
+    ];
+
+    // By default, the source is recorded as a predecessor
+    test_env.jj_cmd_ok(&repo_path, &["squash", "-m=a"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &obslog_args);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉    bc0b2245d491 a
+    ├─╮
+    ◉ │  2443ea76b0b1 a
+    ◉ │  d8d5f980a897 a
+    ◉ │  230dd059e1b0
+      ◉  b73077b08c59 b
+      ◉  a786561e909f b
+    "###);
+
+    // With --no-source-predecessors, only the old destination is recorded
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    test_env.jj_cmd_ok(&repo_path, &["squash", "-m=a", "--no-source-predecessors"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &obslog_args);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  d1890f47d923 a
+    ◉  2443ea76b0b1 a
+    ◉  d8d5f980a897 a
+    ◉  230dd059e1b0
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "-r=@-", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    "###);
+}
+
+#[test]
+fn test_squash_workspace() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let secondary_path = test_env.env_root().join("secondary");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "a"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["workspace", "add", "--name", "second", "../secondary"],
+    );
+    test_env.jj_cmd_ok(&secondary_path, &["describe", "-m", "b"]);
+    std::fs::write(secondary_path.join("file1"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&secondary_path, &["new", "-m", "c"]);
+    std::fs::write(secondary_path.join("file2"), "c\n").unwrap();
+    test_env.jj_cmd_ok(&secondary_path, &["status"]);
+    let template = r#"separate(" ", commit_id.short(), working_copies, description)"#;
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["log", "-T", template]), @r###"
+    ◉  8d38738dd27a second@ c
+    ◉  6b6069c8256e b
+    │ @  3ca983229d2a default@
+    ├─╯
+    ◉  2a137714dac4 a
+    ◉  000000000000
+    "###);
+
+    // Squash the working-copy commit of the second workspace into its parent
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["squash", "--workspace", "second", "-m", "b+c"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file2
+    "###);
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["log", "-T", template]), @r###"
+    ◉  b1cc78514b92 second@
+    ◉  b92d8de6391b b+c
+    │ @  3ca983229d2a default@
+    ├─╯
+    ◉  2a137714dac4 a
+    ◉  000000000000
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "-r", "second@-", "file2"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+
+    // `@` in the revisions refers to the working-copy commit of that workspace
+    std::fs::write(repo_path.join("file3"), "d\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "squash",
+            "--workspace",
+            "second",
+            "--from",
+            "default@",
+            "--into",
+            "@-",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file3
+    Rebased 1 descendant commits
+    Working copy now at: kpqxywon 6c964075 (empty) (no description set)
+    Parent commit      : qpvuntsm 2a137714 a
+    Added 0 files, modified 0 files, removed 1 files
+    "###);
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["log", "-T", template]), @r###"
+    @  6c9640751ee8 default@
+    │ ◉  b887df48c603 second@
+    │ ◉  9d5c66804555 b+c
+    ├─╯
+    ◉  2a137714dac4 a
+    ◉  000000000000
+    "###);
+
+    // Unknown workspace
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash", "--workspace", "unknown"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No such workspace: unknown
+    "###);
+}
+
 fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
     let template = r#"separate(" ", commit_id.short(), branches, description)"#;
     test_env.jj_cmd_success(repo_path, &["log", "-T", template])
@@ -1058,6 +1628,71 @@ fn test_squash_empty() {
     "###);
 }
 
+#[test]
+fn test_squash_into_root() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash", "--into", "root()"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot squash into the root commit
+    "###);
+
+    // Squashing a child of the root commit into its parent is also rejected
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot squash into the root commit
+    "###);
+}
+
+#[test]
+fn test_squash_abandon_source_with_branches() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "a"]);
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    std::fs::write(repo_path.join("file1"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "b"]);
+
+    // The emptied source is abandoned, and its branch moves to the destination
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-u"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: Moving branches b from abandoned commit kkmpptxz f02b2099 b | b to its parent
+    Hint: Use `jj squash --keep-emptied` to keep the emptied commit.
+    Moved changes to: file1
+    Working copy now at: royxmykx b30f852e (empty) (no description set)
+    Parent commit      : qpvuntsm 9ed105ec a b | a
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  b30f852e4127
+    ◉  9ed105ecf614 a b a
+    ◉  000000000000
+    "###);
+
+    // With --keep-emptied, the source is kept along with its branch
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-u", "--keep-emptied"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
+    Rebased 1 descendant commits
+    Working copy now at: kkmpptxz ab617671 b | (empty) b
+    Parent commit      : qpvuntsm d040aba1 a | a
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  ab6176713b8a b b
+    ◉  d040aba1fc1b a a
+    ◉  000000000000
+    "###);
+}
+
 #[test]
 fn test_squash_use_destination_message() {
     let test_env = TestEnvironment::default();
@@ -1128,6 +1763,123 @@ fn test_squash_use_destination_message_and_message_mutual_exclusion() {
     "###);
 }
 
+#[test]
+fn test_squash_merge_into_parent() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "a"]);
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "c"]);
+    std::fs::write(repo_path.join("file3"), "c\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "b", "c"]);
+    std::fs::write(repo_path.join("file1"), "d\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  8c32f97f3330
+    ◉    9b64826ae196
+    ├─╮
+    │ ◉  79b408311a90 c
+    ◉ │  a969ea26d1ea b
+    ├─╯
+    ◉  184ddbcce5a9 a
+    ◉  000000000000
+    "###);
+
+    // The parent must be specified for a merge commit
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash", "-r", "@-"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot squash merge commits
+    Hint: Use `--into` to specify which parent to squash into.
+    "###);
+
+    // The destination must be one of the parents
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["squash", "-r", "@-", "--into", "a"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 184ddbcce5a9 is not a parent of 9b64826ae196
+    "###);
+
+    // The changes in the merge commit are moved into the specified parent
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "-r", "@-", "--into", "c"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file1
+    Rebased 1 descendant commits
+    Working copy now at: vruxwmqv 8aa9d6ea (empty) (no description set)
+    Parent commit      : kkmpptxz a969ea26 b | (no description set)
+    Parent commit      : mzvwutvl c8a11a18 c | (no description set)
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @    8aa9d6eaeea0
+    ├─╮
+    │ ◉  c8a11a184da6 c
+    ◉ │  a969ea26d1ea b
+    ├─╯
+    ◉  184ddbcce5a9 a
+    ◉  000000000000
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "c"]);
+    insta::assert_snapshot!(stdout, @r###"
+    d
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file1", "-r", "b"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    "###);
+}
+
+#[test]
+fn test_squash_stat() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "a"]);
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    std::fs::write(repo_path.join("file1"), "a\nb\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+    let log_before = get_log_output(&test_env, &repo_path);
+    insta::assert_snapshot!(log_before, @r###"
+    @  25d374316ffd b
+    ◉  184ddbcce5a9 a
+    ◉  000000000000
+    "###);
+
+    // The changes to be moved are shown, but nothing is moved
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--stat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Changes to move from kkmpptxz 25d37431 b | (no description set):
+    file1 | 1 +
+    file2 | 1 +
+    2 files changed, 2 insertions(+), 0 deletions(-)
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+    assert_eq!(get_log_output(&test_env, &repo_path), log_before);
+
+    // Only the changes matching the paths are shown
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--stat", "file2"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Changes to move from kkmpptxz 25d37431 b | (no description set):
+    file2 | 1 +
+    1 file changed, 1 insertion(+), 0 deletions(-)
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["squash", "--stat", "nonexistent"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    No changes to move.
+    "###);
+    assert_eq!(get_log_output(&test_env, &repo_path), log_before);
+}
+
 fn get_description(test_env: &TestEnvironment, repo_path: &Path, rev: &str) -> String {
     test_env.jj_cmd_success(
         repo_path,
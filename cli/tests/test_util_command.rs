@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use indoc::indoc;
 use insta::assert_snapshot;
 
 use crate::common::TestEnvironment;
@@ -112,3 +113,224 @@ fn test_shell_completions() {
     test("nushell");
     test("zsh");
 }
+
+#[test]
+fn test_util_parse_fileset() {
+    let test_env = TestEnvironment::default();
+    // No repository is needed
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &["util", "parse-fileset", "root:foo | ~bar"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    UnionAll(
+        [
+            Pattern(
+                PrefixPath(
+                    "foo",
+                ),
+            ),
+            Difference(
+                All,
+                Pattern(
+                    PrefixPath(
+                        "bar",
+                    ),
+                ),
+            ),
+        ],
+    )
+    "###);
+
+    // Paths are resolved relative to --cwd within --root
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &["util", "parse-fileset", "--root=ws", "--cwd=ws/sub", "foo"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    Pattern(
+        PrefixPath(
+            "sub/foo",
+        ),
+    )
+    "###);
+
+    // Path outside of --root
+    let stderr = test_env.jj_cmd_failure(
+        test_env.env_root(),
+        &[
+            "util",
+            "parse-fileset",
+            "--root=ws",
+            "--cwd=ws/sub",
+            "../../foo",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset: Invalid file pattern
+    Caused by:
+    1:  --> 1:1
+      |
+    1 | ../../foo
+      | ^-------^
+      |
+      = Invalid file pattern
+    2: Path "../../foo" is not in the repo "../"
+    3: Invalid component ".." in repo-relative path "../foo"
+    "###);
+
+    // Redundant patterns are reported as warnings
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["util", "parse-fileset", "src | src/a.rs"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    UnionAll(
+        [
+            Pattern(
+                PrefixPath(
+                    "src",
+                ),
+            ),
+            Pattern(
+                PrefixPath(
+                    "src/a.rs",
+                ),
+            ),
+        ],
+    )
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: The pattern `src/a.rs` is redundant given `src`
+    "###);
+
+    // Syntax error
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["util", "parse-fileset", "foo &"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset: Syntax error
+    Caused by:  --> 1:6
+      |
+    1 | foo &
+      |      ^---
+      |
+      = expected `~` or <primary>
+    "###);
+
+    // Unknown function
+    let stderr = test_env.jj_cmd_failure(
+        test_env.env_root(),
+        &["util", "parse-fileset", "nonexistent()"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset: Function "nonexistent" doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | nonexistent()
+      | ^---------^
+      |
+      = Function "nonexistent" doesn't exist
+    Hint: Did you mean "none"?
+    "###);
+}
+
+#[test]
+fn test_util_parse_conflict() {
+    let test_env = TestEnvironment::default();
+    // No repository is needed
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        test_env.env_root(),
+        &["util", "parse-conflict"],
+        indoc! {"
+            line 1
+            <<<<<<<
+            %%%%%%%
+            -line 2
+            +left
+            +++++++
+            right
+            >>>>>>>
+            line 3
+        "},
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    resolved: "line 1\n"
+    conflict:
+      add: "left\n"
+      remove: "line 2\n"
+      add: "right\n"
+    resolved: "line 3\n"
+    "###);
+
+    // The number of sides must match
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        test_env.env_root(),
+        &["util", "parse-conflict", "--sides=3"],
+        indoc! {"
+            <<<<<<<
+            %%%%%%%
+            -base
+            +left
+            +++++++
+            right
+            >>>>>>>
+        "},
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    no valid conflicts
+    "###);
+
+    // Shorter marker-like lines are parsed as content
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        test_env.env_root(),
+        &["util", "parse-conflict", "--marker-len=8"],
+        indoc! {"
+            <<<<<<<< Conflict 1 of 1
+            %%%%%%%% Changes from base to side #1
+            -base
+            +=======
+            ++++++++ Contents of side #2
+            <<<<<<<
+            >>>>>>>> Conflict 1 of 1 ends
+        "},
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    conflict:
+      add: "=======\n"
+      remove: "base\n"
+      add: "<<<<<<<\n"
+    "###);
+
+    // The markers must be at least as long as --marker-len
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        test_env.env_root(),
+        &["util", "parse-conflict", "--marker-len=8"],
+        indoc! {"
+            <<<<<<<
+            %%%%%%%
+            -base
+            +left
+            +++++++
+            right
+            >>>>>>>
+        "},
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    no valid conflicts
+    "###);
+
+    // Git-style conflict markers aren't understood
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(
+        test_env.env_root(),
+        &["util", "parse-conflict"],
+        indoc! {"
+            <<<<<<< left
+            left
+            =======
+            right
+            >>>>>>> right
+        "},
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    no valid conflicts
+    "###);
+}
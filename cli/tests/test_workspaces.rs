@@ -282,6 +282,7 @@ fn test_workspaces_conflicting_edits() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&main_path, &["squash"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file
     Rebased 1 descendant commits
     Working copy now at: mzvwutvl a58c9a9b (empty) (no description set)
     Parent commit      : qpvuntsm d4124476 (no description set)
@@ -369,6 +370,7 @@ fn test_workspaces_updated_by_other() {
     let (stdout, stderr) = test_env.jj_cmd_ok(&main_path, &["squash"]);
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
+    Moved changes to: file
     Rebased 1 descendant commits
     Working copy now at: mzvwutvl a58c9a9b (empty) (no description set)
     Parent commit      : qpvuntsm d4124476 (no description set)
@@ -14,12 +14,15 @@
 
 #![allow(missing_docs)]
 
+use std::borrow::Cow;
 use std::io::{Read, Write};
 use std::iter::zip;
 
+use futures::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use regex::bytes::Regex;
+use thiserror::Error;
 
 use crate::backend::{BackendError, BackendResult, CommitId, FileId, SymlinkId, TreeId, TreeValue};
 use crate::diff::{find_line_ranges, Diff, DiffHunk};
@@ -29,47 +32,101 @@ use crate::merge::{Merge, MergeBuilder, MergedTreeValue};
 use crate::repo_path::RepoPath;
 use crate::store::Store;
 
-const CONFLICT_START_LINE: &[u8] = b"<<<<<<<";
-const CONFLICT_END_LINE: &[u8] = b">>>>>>>";
-const CONFLICT_DIFF_LINE: &[u8] = b"%%%%%%%";
-const CONFLICT_MINUS_LINE: &[u8] = b"-------";
-const CONFLICT_PLUS_LINE: &[u8] = b"+++++++";
-const CONFLICT_START_LINE_CHAR: u8 = CONFLICT_START_LINE[0];
-const CONFLICT_END_LINE_CHAR: u8 = CONFLICT_END_LINE[0];
-const CONFLICT_DIFF_LINE_CHAR: u8 = CONFLICT_DIFF_LINE[0];
-const CONFLICT_MINUS_LINE_CHAR: u8 = CONFLICT_MINUS_LINE[0];
-const CONFLICT_PLUS_LINE_CHAR: u8 = CONFLICT_PLUS_LINE[0];
+/// Minimum length of conflict markers.
+pub const MIN_CONFLICT_MARKER_LEN: usize = 7;
+
+const CONFLICT_START_LINE_CHAR: u8 = b'<';
+const CONFLICT_END_LINE_CHAR: u8 = b'>';
+const CONFLICT_DIFF_LINE_CHAR: u8 = b'%';
+const CONFLICT_MINUS_LINE_CHAR: u8 = b'-';
+const CONFLICT_PLUS_LINE_CHAR: u8 = b'+';
+const CONFLICT_GIT_ANCESTOR_LINE_CHAR: u8 = b'|';
+const CONFLICT_GIT_SEPARATOR_LINE_CHAR: u8 = b'=';
 
 /// A conflict marker is one of the separators, optionally followed by a space
-/// and some text.
-// TODO: All the `{7}` could be replaced with `{7,}` to allow longer
-// separators. This could be useful to make it possible to allow conflict
-// markers inside the text of the conflicts.
+/// and some text. The separator must be at the start of the line, and is only
+/// treated as a marker if it's at least as long as the expected marker length
+/// (see `is_conflict_marker()`).
+///
+/// The separator is a run of a single marker character. It must be followed
+/// by either a space or the end of the line, so lines mixing marker characters
+/// (e.g. `<<<<<<<>>>>>>>` or `+++++++-------`) or followed by other whitespace
+/// are not conflict markers. The line may end with `\r\n`, since files edited
+/// on Windows may have their line endings converted.
 static CONFLICT_MARKER_REGEX: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
     Regex::new(
-        r"(<{7}|>{7}|%{7}|\-{7}|\+{7})( .*)?
+        r"^(<+|>+|%+|\-+|\++|\|+|=+)( .*)?\r?
 ",
     )
     .unwrap()
 });
 
-fn write_diff_hunks(hunks: &[DiffHunk], file: &mut dyn Write) -> std::io::Result<()> {
+/// Comment line written before the first conflict in a file when
+/// `ConflictMaterializeOptions::conflict_hint` is set. The line is removed
+/// again by `update_from_content_with_errors()` if it's passed the same
+/// options, unless the line was edited.
+static CONFLICT_HINT_REGEX: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+    Regex::new(r"^# jj: [0-9]+ conflicts? below; edit the markers or run `jj resolve`\r?\n$")
+        .unwrap()
+});
+
+fn conflict_hint_line(num_conflicts: usize) -> String {
+    let noun = if num_conflicts == 1 {
+        "conflict"
+    } else {
+        "conflicts"
+    };
+    format!("# jj: {num_conflicts} {noun} below; edit the markers or run `jj resolve`\n")
+}
+
+/// Removes the conflict hint from `content`, if it's there. The hint is
+/// looked for before the first conflict start marker, or anywhere if all the
+/// conflicts were resolved.
+fn strip_conflict_hint(content: &[u8], marker_len: usize) -> Cow<'_, [u8]> {
+    let mut pos = 0;
+    for line in content.split_inclusive(|b| *b == b'\n') {
+        if is_conflict_marker(line, marker_len) && line[0] == CONFLICT_START_LINE_CHAR {
+            break;
+        }
+        if CONFLICT_HINT_REGEX.is_match(line) {
+            let mut stripped = content[..pos].to_vec();
+            stripped.extend_from_slice(&content[pos + line.len()..]);
+            return Cow::Owned(stripped);
+        }
+        pos += line.len();
+    }
+    Cow::Borrowed(content)
+}
+
+/// Writes `content`, adding the line ending `eol` if it doesn't end with a
+/// newline so the next line or conflict marker starts on its own line.
+// TODO: The missing newline at the end of a term isn't recorded, so it will be
+// added when the conflict is parsed back.
+fn write_lines(content: &[u8], eol: &[u8], file: &mut dyn Write) -> std::io::Result<()> {
+    file.write_all(content)?;
+    if !content.is_empty() && !content.ends_with(b"\n") {
+        file.write_all(eol)?;
+    }
+    Ok(())
+}
+
+fn write_diff_hunks(hunks: &[DiffHunk], eol: &[u8], file: &mut dyn Write) -> std::io::Result<()> {
     for hunk in hunks {
         match hunk {
             DiffHunk::Matching(content) => {
                 for line in content.split_inclusive(|b| *b == b'\n') {
                     file.write_all(b" ")?;
-                    file.write_all(line)?;
+                    write_lines(line, eol, file)?;
                 }
             }
             DiffHunk::Different(content) => {
                 for line in content[0].split_inclusive(|b| *b == b'\n') {
                     file.write_all(b"-")?;
-                    file.write_all(line)?;
+                    write_lines(line, eol, file)?;
                 }
                 for line in content[1].split_inclusive(|b| *b == b'\n') {
                     file.write_all(b"+")?;
-                    file.write_all(line)?;
+                    write_lines(line, eol, file)?;
                 }
             }
         }
@@ -77,6 +134,19 @@ fn write_diff_hunks(hunks: &[DiffHunk], file: &mut dyn Write) -> std::io::Result
     Ok(())
 }
 
+/// Writes a conflict marker line of `len` repetitions of `marker_char`,
+/// followed by `text`.
+fn write_conflict_marker(
+    output: &mut dyn Write,
+    marker_char: u8,
+    len: usize,
+    text: &str,
+) -> std::io::Result<()> {
+    output.write_all(&vec![marker_char; len])?;
+    output.write_all(text.as_bytes())?;
+    output.write_all(b"\n")
+}
+
 async fn get_file_contents(
     store: &Store,
     path: &RepoPath,
@@ -154,7 +224,19 @@ pub async fn materialize_tree_value(
     path: &RepoPath,
     value: MergedTreeValue,
 ) -> BackendResult<MaterializedTreeValue> {
-    match materialize_tree_value_no_access_denied(store, path, value).await {
+    materialize_tree_value_with_options(store, path, value, &ConflictMaterializeOptions::default())
+        .await
+}
+
+/// Like `materialize_tree_value()`, but materializes conflicts with the given
+/// `options`.
+pub async fn materialize_tree_value_with_options(
+    store: &Store,
+    path: &RepoPath,
+    value: MergedTreeValue,
+    options: &ConflictMaterializeOptions,
+) -> BackendResult<MaterializedTreeValue> {
+    match materialize_tree_value_no_access_denied(store, path, value, options).await {
         Err(BackendError::ReadAccessDenied { source, .. }) => {
             Ok(MaterializedTreeValue::AccessDenied(source))
         }
@@ -166,6 +248,7 @@ async fn materialize_tree_value_no_access_denied(
     store: &Store,
     path: &RepoPath,
     value: MergedTreeValue,
+    options: &ConflictMaterializeOptions,
 ) -> BackendResult<MaterializedTreeValue> {
     match value.into_resolved() {
         Ok(None) => Ok(MaterializedTreeValue::Absent),
@@ -191,7 +274,7 @@ async fn materialize_tree_value_no_access_denied(
             if let Some(file_merge) = conflict.to_file_merge() {
                 let file_merge = file_merge.simplify();
                 let content = extract_as_single_hunk(&file_merge, store, path).await?;
-                materialize_merge_result(&content, &mut contents)
+                materialize_merge_result_with_options(&content, options, &mut contents)
                     .expect("Failed to materialize conflict to in-memory buffer");
             } else {
                 // Unless all terms are regular files, we can't do much better than to try to
@@ -214,110 +297,509 @@ async fn materialize_tree_value_no_access_denied(
     }
 }
 
+/// Options for materializing conflicts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ConflictMaterializeOptions {
+    /// Append machine-readable annotations to the conflict markers of each
+    /// term, e.g. `[jj:side=1]`, `[jj:base=1]`, or `[jj:diff from=1 to=2]`.
+    /// Sides and bases are always identified by their 1-based index, even if
+    /// there's only one base. The annotations come after the human-readable
+    /// text, and are ignored when parsing the conflict markers.
+    pub annotate_terms: bool,
+    /// Line ending to add after conflicted terms that don't end with a
+    /// newline, so the next conflict marker starts on its own line.
+    pub added_line_ending: ConflictLineEnding,
+    /// How to lay out the terms of each conflict.
+    pub marker_style: ConflictMarkerStyle,
+    /// Length of the conflict markers. If not set, the length is chosen by
+    /// `choose_materialized_conflict_marker_len()`.
+    pub marker_len: Option<usize>,
+    /// Write `Side #2` after the separator of `Git` style conflicts, for tools
+    /// that expect text there. It's omitted by default because VS Code
+    /// doesn't support it.
+    pub git_separator_comment: bool,
+    /// Write a comment line before the first conflict saying how many
+    /// conflicts there are and how to resolve them. The comment is only
+    /// written if there's at least one conflict. It's removed by
+    /// `update_from_content_with_errors()` when passed the same options,
+    /// unless it was edited.
+    pub conflict_hint: bool,
+}
+
+/// Style of the conflict markers to materialize conflicts with.
+///
+/// Conflicts in any of the styles can be parsed back. The `Git` style is meant
+/// for tools that only understand Git's `diff3` markers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictMarkerStyle {
+    /// Show each side as a diff from the base it's most similar to.
+    #[default]
+    Diff,
+    /// Show the full contents of each side and base.
+    Snapshot,
+    /// Use Git's `diff3` style markers. Conflicts with more than two sides
+    /// fall back to the `Snapshot` style.
+    Git,
+    /// Like `Diff`, but always show the base as a diff to the first side
+    /// ("ours"), followed by the contents of the second side ("theirs"), so
+    /// tools can find the base and ours in the same section. Conflicts with
+    /// more than two sides pair each base with the side before it.
+    BaseOurs,
+}
+
+/// Line ending to add after conflicted terms that don't end with a newline.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictLineEnding {
+    /// Add `\n`.
+    #[default]
+    Lf,
+    /// Add `\r\n`.
+    Crlf,
+    /// Add `\r\n` if most lines of the terms end with `\r\n`, and `\n`
+    /// otherwise.
+    Detect,
+}
+
+impl ConflictLineEnding {
+    fn resolve(self, single_hunk: &Merge<&[u8]>) -> &'static [u8] {
+        match self {
+            ConflictLineEnding::Lf => b"\n",
+            ConflictLineEnding::Crlf => b"\r\n",
+            ConflictLineEnding::Detect => {
+                let (crlf_count, lf_count) = single_hunk
+                    .iter()
+                    .flat_map(|content| content.split_inclusive(|b| *b == b'\n'))
+                    .fold((0, 0), |(crlf_count, lf_count), line| {
+                        if line.ends_with(b"\r\n") {
+                            (crlf_count + 1, lf_count)
+                        } else if line.ends_with(b"\n") {
+                            (crlf_count, lf_count + 1)
+                        } else {
+                            (crlf_count, lf_count)
+                        }
+                    });
+                if crlf_count > lf_count {
+                    b"\r\n"
+                } else {
+                    b"\n"
+                }
+            }
+        }
+    }
+}
+
+/// Returns the length of the run of marker characters if `line` looks like a
+/// conflict marker of any length. Lines lacking a newline are included since
+/// one is added when they are materialized.
+fn marker_like_len(line: &[u8]) -> Option<usize> {
+    let first = *line.first()?;
+    if !b"<>%-+|=".contains(&first) {
+        return None;
+    }
+    let len = line.iter().take_while(|b| **b == first).count();
+    matches!(line.get(len), None | Some(b' ' | b'\r' | b'\n')).then_some(len)
+}
+
+/// Chooses the length of the conflict markers to materialize `single_hunk`
+/// with.
+///
+/// The markers are made longer than any line in the terms that looks like a
+/// conflict marker, so that such lines are parsed back as content. Lines
+/// starting with `-` or `+` count as one longer since they may be prefixed
+/// with another `-` or `+` in a diff.
+pub fn choose_materialized_conflict_marker_len<T: AsRef<[u8]>>(single_hunk: &Merge<T>) -> usize {
+    let max_existing_marker_len = single_hunk
+        .iter()
+        .flat_map(|term| term.as_ref().split_inclusive(|b| *b == b'\n'))
+        .filter_map(|line| {
+            let len = marker_like_len(line)?;
+            match line[0] {
+                CONFLICT_MINUS_LINE_CHAR | CONFLICT_PLUS_LINE_CHAR => Some(len + 1),
+                _ => Some(len),
+            }
+        })
+        .max()
+        .unwrap_or(0);
+    (max_existing_marker_len + 1).max(MIN_CONFLICT_MARKER_LEN)
+}
+
 pub fn materialize_merge_result(
     single_hunk: &Merge<ContentHunk>,
     output: &mut dyn Write,
 ) -> std::io::Result<()> {
+    materialize_merge_result_with_options(
+        single_hunk,
+        &ConflictMaterializeOptions::default(),
+        output,
+    )
+}
+
+/// Like `materialize_merge_result()`, but with the given `options`.
+pub fn materialize_merge_result_with_options(
+    single_hunk: &Merge<ContentHunk>,
+    options: &ConflictMaterializeOptions,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    for chunk in materialize_merge_result_chunks(single_hunk, options) {
+        output.write_all(&chunk?)?;
+    }
+    Ok(())
+}
+
+/// Materializes `single_hunk` one merge hunk at a time. Resolved hunks are
+/// returned as is, and each conflict hunk is rendered into its own buffer.
+fn materialize_merge_result_chunks(
+    single_hunk: &Merge<ContentHunk>,
+    options: &ConflictMaterializeOptions,
+) -> impl Iterator<Item = std::io::Result<Vec<u8>>> {
+    let options = *options;
     let slices = single_hunk.map(|content| content.0.as_slice());
-    let merge_result = files::merge(&slices);
-    match merge_result {
-        MergeResult::Resolved(content) => {
-            output.write_all(&content.0)?;
+    let eol = options.added_line_ending.resolve(&slices);
+    let marker_len = options
+        .marker_len
+        .unwrap_or_else(|| choose_materialized_conflict_marker_len(&slices));
+    let hunks = match files::merge(&slices) {
+        MergeResult::Resolved(content) => vec![Merge::resolved(content)],
+        MergeResult::Conflict(hunks) => hunks,
+    };
+    let num_conflicts = hunks
+        .iter()
+        .filter(|hunk| hunk.as_resolved().is_none())
+        .count();
+    let mut conflict_index = 0;
+    hunks
+        .into_iter()
+        .map(move |hunk| match hunk.into_resolved() {
+            Ok(content) => Ok(content.0),
+            Err(hunk) => {
+                let mut buf = vec![];
+                if conflict_index == 0 && options.conflict_hint {
+                    buf.extend_from_slice(conflict_hint_line(num_conflicts).as_bytes());
+                }
+                conflict_index += 1;
+                materialize_conflict_hunk(
+                    &hunk,
+                    conflict_index,
+                    num_conflicts,
+                    &options,
+                    eol,
+                    marker_len,
+                    &mut buf,
+                )?;
+                Ok(buf)
+            }
+        })
+}
+
+/// Like `materialize_merge_result()`, but fails once the output would exceed
+/// `max_bytes`, so a pathological conflict can't produce a huge file.
+///
+/// The error is of kind `std::io::ErrorKind::Other`. The output written before
+/// the limit was hit is left in `output`.
+pub fn materialize_merge_result_bounded(
+    single_hunk: &Merge<ContentHunk>,
+    max_bytes: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let mut output = BoundedWriter {
+        inner: output,
+        max_bytes,
+        written: 0,
+    };
+    materialize_merge_result(single_hunk, &mut output)
+}
+
+/// Writer that counts the bytes written to `inner`, and fails instead of
+/// writing more than `max_bytes` in total.
+struct BoundedWriter<'a> {
+    inner: &'a mut dyn Write,
+    max_bytes: usize,
+    written: usize,
+}
+
+impl Write for BoundedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written + buf.len() > self.max_bytes {
+            return Err(std::io::Error::other(format!(
+                "Materialized conflict exceeds the limit of {} bytes",
+                self.max_bytes
+            )));
         }
-        MergeResult::Conflict(hunks) => {
-            let num_conflicts = hunks
-                .iter()
-                .filter(|hunk| hunk.as_resolved().is_none())
-                .count();
-            let mut conflict_index = 0;
-            for hunk in hunks {
-                if let Some(content) = hunk.as_resolved() {
-                    output.write_all(&content.0)?;
-                } else {
-                    conflict_index += 1;
-                    output.write_all(CONFLICT_START_LINE)?;
-                    output.write_all(
-                        format!(" Conflict {conflict_index} of {num_conflicts}\n").as_bytes(),
-                    )?;
-                    let mut add_index = 0;
-                    for (base_index, left) in hunk.removes().enumerate() {
-                        // The vast majority of conflicts one actually tries to
-                        // resolve manually have 1 base.
-                        let base_str = if hunk.removes().len() == 1 {
-                            "base".to_string()
-                        } else {
-                            format!("base #{}", base_index + 1)
-                        };
+        let len = self.inner.write(buf)?;
+        self.written += len;
+        Ok(len)
+    }
 
-                        let right1 = if let Some(right1) = hunk.get_add(add_index) {
-                            right1
-                        } else {
-                            // If we have no more positive terms, emit the remaining negative
-                            // terms as snapshots.
-                            output.write_all(CONFLICT_MINUS_LINE)?;
-                            output.write_all(format!(" Contents of {base_str}\n").as_bytes())?;
-                            output.write_all(&left.0)?;
-                            continue;
-                        };
-                        let diff1 = Diff::for_tokenizer(&[&left.0, &right1.0], find_line_ranges)
-                            .hunks()
-                            .collect_vec();
-                        // Check if the diff against the next positive term is better. Since
-                        // we want to preserve the order of the terms, we don't match against
-                        // any later positive terms.
-                        if let Some(right2) = hunk.get_add(add_index + 1) {
-                            let diff2 =
-                                Diff::for_tokenizer(&[&left.0, &right2.0], find_line_ranges)
-                                    .hunks()
-                                    .collect_vec();
-                            if diff_size(&diff2) < diff_size(&diff1) {
-                                // If the next positive term is a better match, emit
-                                // the current positive term as a snapshot and the next
-                                // positive term as a diff.
-                                output.write_all(CONFLICT_PLUS_LINE)?;
-                                output.write_all(
-                                    format!(" Contents of side #{}\n", add_index + 1).as_bytes(),
-                                )?;
-                                output.write_all(&right1.0)?;
-                                output.write_all(CONFLICT_DIFF_LINE)?;
-                                output.write_all(
-                                    format!(
-                                        " Changes from {base_str} to side #{}\n",
-                                        add_index + 2
-                                    )
-                                    .as_bytes(),
-                                )?;
-                                write_diff_hunks(&diff2, output)?;
-                                add_index += 2;
-                                continue;
-                            }
-                        }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
 
-                        output.write_all(CONFLICT_DIFF_LINE)?;
-                        output.write_all(
-                            format!(" Changes from {base_str} to side #{}\n", add_index + 1)
-                                .as_bytes(),
-                        )?;
-                        write_diff_hunks(&diff1, output)?;
-                        add_index += 1;
-                    }
+/// Like `materialize_merge_result_with_options()`, but writes to an
+/// `AsyncWrite`.
+///
+/// Only one conflict hunk at a time is buffered in memory. The writer is a
+/// `futures::io::AsyncWrite` rather than a Tokio one so that the library
+/// doesn't depend on a particular async runtime, like
+/// `update_from_content_reader()`.
+pub async fn materialize_merge_result_async(
+    single_hunk: &Merge<ContentHunk>,
+    options: &ConflictMaterializeOptions,
+    output: &mut (dyn AsyncWrite + Unpin),
+) -> std::io::Result<()> {
+    for chunk in materialize_merge_result_chunks(single_hunk, options) {
+        output.write_all(&chunk?).await?;
+    }
+    Ok(())
+}
 
-                    //  Emit the remaining positive terms as snapshots.
-                    for (add_index, slice) in hunk.adds().enumerate().skip(add_index) {
-                        output.write_all(CONFLICT_PLUS_LINE)?;
-                        output.write_all(
-                            format!(" Contents of side #{}\n", add_index + 1).as_bytes(),
-                        )?;
-                        output.write_all(&slice.0)?;
-                    }
-                    output.write_all(CONFLICT_END_LINE)?;
-                    output.write_all(
-                        format!(" Conflict {conflict_index} of {num_conflicts} ends\n").as_bytes(),
-                    )?;
-                }
+fn materialize_conflict_hunk(
+    hunk: &Merge<ContentHunk>,
+    conflict_index: usize,
+    num_conflicts: usize,
+    options: &ConflictMaterializeOptions,
+    eol: &[u8],
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    match options.marker_style {
+        ConflictMarkerStyle::Diff | ConflictMarkerStyle::BaseOurs => {}
+        ConflictMarkerStyle::Git if hunk.num_sides() == 2 => {
+            return materialize_git_style_conflict(
+                hunk,
+                conflict_index,
+                num_conflicts,
+                options,
+                eol,
+                marker_len,
+                output,
+            );
+        }
+        ConflictMarkerStyle::Snapshot | ConflictMarkerStyle::Git => {
+            return materialize_snapshot_style_conflict(
+                hunk,
+                conflict_index,
+                num_conflicts,
+                options,
+                eol,
+                marker_len,
+                output,
+            );
+        }
+    }
+    let annotation = |text: String| term_annotation(options, text);
+    write_conflict_marker(
+        output,
+        CONFLICT_START_LINE_CHAR,
+        marker_len,
+        &format!(" Conflict {conflict_index} of {num_conflicts}"),
+    )?;
+    let mut add_index = 0;
+    for (base_index, left) in hunk.removes().enumerate() {
+        // The vast majority of conflicts one actually tries to
+        // resolve manually have 1 base.
+        let base_str = if hunk.removes().len() == 1 {
+            "base".to_string()
+        } else {
+            format!("base #{}", base_index + 1)
+        };
+
+        let right1 = if let Some(right1) = hunk.get_add(add_index) {
+            right1
+        } else {
+            // If we have no more positive terms, emit the remaining negative
+            // terms as snapshots.
+            let annotation = annotation(format!("base={}", base_index + 1));
+            write_conflict_marker(
+                output,
+                CONFLICT_MINUS_LINE_CHAR,
+                marker_len,
+                &format!(" Contents of {base_str}{annotation}"),
+            )?;
+            write_lines(&left.0, eol, output)?;
+            continue;
+        };
+        let diff1 = Diff::for_tokenizer(&[&left.0, &right1.0], find_line_ranges)
+            .hunks()
+            .collect_vec();
+        // Check if the diff against the next positive term is better. Since
+        // we want to preserve the order of the terms, we don't match against
+        // any later positive terms.
+        let right2 = match options.marker_style {
+            ConflictMarkerStyle::BaseOurs => None,
+            _ => hunk.get_add(add_index + 1),
+        };
+        if let Some(right2) = right2 {
+            let diff2 = Diff::for_tokenizer(&[&left.0, &right2.0], find_line_ranges)
+                .hunks()
+                .collect_vec();
+            if diff_size(&diff2) < diff_size(&diff1) {
+                // If the next positive term is a better match, emit
+                // the current positive term as a snapshot and the next
+                // positive term as a diff.
+                let annotation1 = annotation(format!("side={}", add_index + 1));
+                write_conflict_marker(
+                    output,
+                    CONFLICT_PLUS_LINE_CHAR,
+                    marker_len,
+                    &format!(" Contents of side #{}{annotation1}", add_index + 1),
+                )?;
+                write_lines(&right1.0, eol, output)?;
+                let annotation2 =
+                    annotation(format!("diff from={} to={}", base_index + 1, add_index + 2));
+                write_conflict_marker(
+                    output,
+                    CONFLICT_DIFF_LINE_CHAR,
+                    marker_len,
+                    &format!(
+                        " Changes from {base_str} to side #{}{annotation2}",
+                        add_index + 2
+                    ),
+                )?;
+                write_diff_hunks(&diff2, eol, output)?;
+                add_index += 2;
+                continue;
             }
         }
+
+        let annotation = annotation(format!("diff from={} to={}", base_index + 1, add_index + 1));
+        write_conflict_marker(
+            output,
+            CONFLICT_DIFF_LINE_CHAR,
+            marker_len,
+            &format!(
+                " Changes from {base_str} to side #{}{annotation}",
+                add_index + 1
+            ),
+        )?;
+        write_diff_hunks(&diff1, eol, output)?;
+        add_index += 1;
     }
+
+    //  Emit the remaining positive terms as snapshots.
+    for (add_index, slice) in hunk.adds().enumerate().skip(add_index) {
+        let annotation = annotation(format!("side={}", add_index + 1));
+        write_conflict_marker(
+            output,
+            CONFLICT_PLUS_LINE_CHAR,
+            marker_len,
+            &format!(" Contents of side #{}{annotation}", add_index + 1),
+        )?;
+        write_lines(&slice.0, eol, output)?;
+    }
+    write_conflict_marker(
+        output,
+        CONFLICT_END_LINE_CHAR,
+        marker_len,
+        &format!(" Conflict {conflict_index} of {num_conflicts} ends"),
+    )?;
+    Ok(())
+}
+
+fn term_annotation(options: &ConflictMaterializeOptions, text: String) -> String {
+    if options.annotate_terms {
+        format!(" [jj:{text}]")
+    } else {
+        String::new()
+    }
+}
+
+fn materialize_snapshot_style_conflict(
+    hunk: &Merge<ContentHunk>,
+    conflict_index: usize,
+    num_conflicts: usize,
+    options: &ConflictMaterializeOptions,
+    eol: &[u8],
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    write_conflict_marker(
+        output,
+        CONFLICT_START_LINE_CHAR,
+        marker_len,
+        &format!(" Conflict {conflict_index} of {num_conflicts}"),
+    )?;
+    // Terms alternate between sides and bases, starting with the first side.
+    for (index, term) in hunk.iter().enumerate() {
+        if index % 2 == 0 {
+            let add_index = index / 2;
+            let annotation = term_annotation(options, format!("side={}", add_index + 1));
+            write_conflict_marker(
+                output,
+                CONFLICT_PLUS_LINE_CHAR,
+                marker_len,
+                &format!(" Contents of side #{}{annotation}", add_index + 1),
+            )?;
+        } else {
+            let base_index = index / 2;
+            let base_str = if hunk.removes().len() == 1 {
+                "base".to_string()
+            } else {
+                format!("base #{}", base_index + 1)
+            };
+            let annotation = term_annotation(options, format!("base={}", base_index + 1));
+            write_conflict_marker(
+                output,
+                CONFLICT_MINUS_LINE_CHAR,
+                marker_len,
+                &format!(" Contents of {base_str}{annotation}"),
+            )?;
+        }
+        write_lines(&term.0, eol, output)?;
+    }
+    write_conflict_marker(
+        output,
+        CONFLICT_END_LINE_CHAR,
+        marker_len,
+        &format!(" Conflict {conflict_index} of {num_conflicts} ends"),
+    )?;
+    Ok(())
+}
+
+/// Materializes a 2-sided conflict with Git's `diff3` style markers.
+fn materialize_git_style_conflict(
+    hunk: &Merge<ContentHunk>,
+    conflict_index: usize,
+    num_conflicts: usize,
+    options: &ConflictMaterializeOptions,
+    eol: &[u8],
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let (Some(left), Some(base), Some(right)) =
+        (hunk.get_add(0), hunk.get_remove(0), hunk.get_add(1))
+    else {
+        panic!("Git style conflicts must have exactly 2 sides");
+    };
+    write_conflict_marker(
+        output,
+        CONFLICT_START_LINE_CHAR,
+        marker_len,
+        &format!(" Side #1 (Conflict {conflict_index} of {num_conflicts})"),
+    )?;
+    write_lines(&left.0, eol, output)?;
+    write_conflict_marker(output, CONFLICT_GIT_ANCESTOR_LINE_CHAR, marker_len, " Base")?;
+    write_lines(&base.0, eol, output)?;
+    // The separator has no trailing text by default since VS Code doesn't
+    // support it.
+    let separator_text = if options.git_separator_comment {
+        " Side #2"
+    } else {
+        ""
+    };
+    write_conflict_marker(
+        output,
+        CONFLICT_GIT_SEPARATOR_LINE_CHAR,
+        marker_len,
+        separator_text,
+    )?;
+    write_lines(&right.0, eol, output)?;
+    write_conflict_marker(
+        output,
+        CONFLICT_END_LINE_CHAR,
+        marker_len,
+        &format!(" Side #2 (Conflict {conflict_index} of {num_conflicts} ends)"),
+    )?;
     Ok(())
 }
 
@@ -331,6 +813,32 @@ fn diff_size(hunks: &[DiffHunk]) -> usize {
         .sum()
 }
 
+/// Hunk of a file parsed from conflict markers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConflictHunk {
+    /// Content outside conflict markers.
+    Resolved(ContentHunk),
+    /// Content between conflict markers.
+    Conflict(Merge<ContentHunk>),
+}
+
+impl ConflictHunk {
+    /// Converts this hunk into a `Merge`, which is resolved for context hunks.
+    pub fn into_merge(self) -> Merge<ContentHunk> {
+        match self {
+            ConflictHunk::Resolved(content) => Merge::resolved(content),
+            ConflictHunk::Conflict(merge) => merge,
+        }
+    }
+}
+
+/// Conflict parsed from materialized content, with resolved context and
+/// conflicted regions kept apart.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsedConflict {
+    pub hunks: Vec<ConflictHunk>,
+}
+
 /// Parses conflict markers from a slice. Returns None if there were no valid
 /// conflict markers. The caller has to provide the expected number of merge
 /// sides (adds). Conflict markers that are otherwise valid will be considered
@@ -338,6 +846,82 @@ fn diff_size(hunks: &[DiffHunk]) -> usize {
 // TODO: "parse" is not usually the opposite of "materialize", so maybe we
 // should rename them to "serialize" and "deserialize"?
 pub fn parse_conflict(input: &[u8], num_sides: usize) -> Option<Vec<Merge<ContentHunk>>> {
+    parse_conflict_with_marker_len(input, num_sides, MIN_CONFLICT_MARKER_LEN)
+}
+
+/// Like [`parse_conflict()`], but only lines with conflict markers at least
+/// `marker_len` characters long are treated as markers. Shorter marker-like
+/// lines are parsed as content.
+pub fn parse_conflict_with_marker_len(
+    input: &[u8],
+    num_sides: usize,
+    marker_len: usize,
+) -> Option<Vec<Merge<ContentHunk>>> {
+    let parsed = parse_conflict_impl(input, num_sides, marker_len, &mut vec![])?;
+    Some(
+        parsed
+            .hunks
+            .into_iter()
+            .map(ConflictHunk::into_merge)
+            .collect(),
+    )
+}
+
+/// Like [`parse_conflict_with_marker_len()`], but tells resolved context apart
+/// from conflicted regions instead of returning both as `Merge`s.
+pub fn parse_conflict_explicit(
+    input: &[u8],
+    num_sides: usize,
+    marker_len: usize,
+) -> Option<ParsedConflict> {
+    parse_conflict_impl(input, num_sides, marker_len, &mut vec![])
+}
+
+/// Reason why a region between conflict markers wasn't parsed as a conflict.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ConflictParseError {
+    #[error("Content before the first section of the conflict")]
+    ContentBeforeSection,
+    #[error("Unexpected line in diff section: {line:?}")]
+    UnexpectedDiffLine { line: String },
+    #[error(
+        "Expected one more added side than removed sides, but found {adds} added and {removes} \
+         removed"
+    )]
+    AddRemoveCountMismatch { adds: usize, removes: usize },
+    #[error("Expected {expected} sides, but found {actual}")]
+    WrongNumberOfSides { expected: usize, actual: usize },
+    #[error("Expected one \"|||||||\" marker followed by one \"=======\" marker")]
+    MalformedGitConflict,
+}
+
+/// Malformed conflict marker regions along with the 1-based line numbers of
+/// their start markers.
+pub type ConflictParseErrors = Vec<(usize, ConflictParseError)>;
+
+/// Finds regions between conflict markers in `input` which don't parse as a
+/// conflict with `num_sides` sides. Such regions are treated as resolved
+/// content by [`parse_conflict()`], so this can be used to explain why a
+/// hand-edited conflict was considered resolved.
+///
+/// Returns the 1-based line number of the start marker along with the reason
+/// for each malformed region.
+pub fn check_conflict_markers(
+    input: &[u8],
+    num_sides: usize,
+    marker_len: usize,
+) -> ConflictParseErrors {
+    let mut errors = vec![];
+    parse_conflict_impl(input, num_sides, marker_len, &mut errors);
+    errors
+}
+
+fn parse_conflict_impl(
+    input: &[u8],
+    num_sides: usize,
+    marker_len: usize,
+    errors: &mut ConflictParseErrors,
+) -> Option<ParsedConflict> {
     if input.is_empty() {
         return None;
     }
@@ -346,21 +930,36 @@ pub fn parse_conflict(input: &[u8], num_sides: usize) -> Option<Vec<Merge<Conten
     let mut resolved_start = 0;
     let mut conflict_start = None;
     let mut conflict_start_len = 0;
-    for line in input.split_inclusive(|b| *b == b'\n') {
-        if CONFLICT_MARKER_REGEX.is_match_at(line, 0) {
+    let mut conflict_start_line_number = 0;
+    for (line_index, line) in input.split_inclusive(|b| *b == b'\n').enumerate() {
+        if is_conflict_marker(line, marker_len) {
             if line[0] == CONFLICT_START_LINE_CHAR {
                 conflict_start = Some(pos);
                 conflict_start_len = line.len();
+                conflict_start_line_number = line_index + 1;
             } else if conflict_start.is_some() && line[0] == CONFLICT_END_LINE_CHAR {
                 let conflict_body = &input[conflict_start.unwrap() + conflict_start_len..pos];
-                let hunk = parse_conflict_hunk(conflict_body);
-                if hunk.num_sides() == num_sides {
-                    let resolved_slice = &input[resolved_start..conflict_start.unwrap()];
-                    if !resolved_slice.is_empty() {
-                        hunks.push(Merge::resolved(ContentHunk(resolved_slice.to_vec())));
+                let hunk = parse_conflict_hunk(conflict_body, marker_len).and_then(|hunk| {
+                    if hunk.num_sides() == num_sides {
+                        Ok(hunk)
+                    } else {
+                        Err(ConflictParseError::WrongNumberOfSides {
+                            expected: num_sides,
+                            actual: hunk.num_sides(),
+                        })
                     }
-                    hunks.push(hunk);
-                    resolved_start = pos + line.len();
+                });
+                match hunk {
+                    Ok(hunk) => {
+                        let resolved_slice = &input[resolved_start..conflict_start.unwrap()];
+                        if !resolved_slice.is_empty() {
+                            hunks
+                                .push(ConflictHunk::Resolved(ContentHunk(resolved_slice.to_vec())));
+                        }
+                        hunks.push(ConflictHunk::Conflict(hunk));
+                        resolved_start = pos + line.len();
+                    }
+                    Err(err) => errors.push((conflict_start_line_number, err)),
                 }
                 conflict_start = None;
             }
@@ -372,15 +971,98 @@ pub fn parse_conflict(input: &[u8], num_sides: usize) -> Option<Vec<Merge<Conten
         None
     } else {
         if resolved_start < input.len() {
-            hunks.push(Merge::resolved(ContentHunk(
+            hunks.push(ConflictHunk::Resolved(ContentHunk(
                 input[resolved_start..].to_vec(),
             )));
         }
-        Some(hunks)
+        Some(ParsedConflict { hunks })
+    }
+}
+
+/// Parses the body of a conflict between the start and end markers. Returns
+/// an error if the body isn't a valid conflict.
+fn parse_conflict_hunk(
+    input: &[u8],
+    marker_len: usize,
+) -> Result<Merge<ContentHunk>, ConflictParseError> {
+    // Git style conflicts start with the contents of the first side (or the
+    // ancestor marker if it's empty) instead of a section marker.
+    let first_line = input.split_inclusive(|b| *b == b'\n').next();
+    let starts_with_section = first_line.is_some_and(|line| {
+        is_conflict_marker(line, marker_len)
+            && matches!(
+                line[0],
+                CONFLICT_DIFF_LINE_CHAR | CONFLICT_MINUS_LINE_CHAR | CONFLICT_PLUS_LINE_CHAR
+            )
+    });
+    let has_git_ancestor = input.split_inclusive(|b| *b == b'\n').any(|line| {
+        is_conflict_marker(line, marker_len) && line[0] == CONFLICT_GIT_ANCESTOR_LINE_CHAR
+    });
+    let is_git_style = !starts_with_section && has_git_ancestor;
+    if is_git_style {
+        parse_git_style_conflict_hunk(input, marker_len)
+    } else {
+        parse_jj_style_conflict_hunk(input, marker_len)
+    }
+}
+
+/// Returns true if `line` is a conflict marker at least `marker_len`
+/// characters long.
+fn is_conflict_marker(line: &[u8], marker_len: usize) -> bool {
+    CONFLICT_MARKER_REGEX.is_match_at(line, 0)
+        && line.iter().take_while(|b| **b == line[0]).count() >= marker_len
+}
+
+/// Parses the body of a conflict with Git's `diff3` style markers. Any text
+/// after the markers is ignored.
+fn parse_git_style_conflict_hunk(
+    input: &[u8],
+    marker_len: usize,
+) -> Result<Merge<ContentHunk>, ConflictParseError> {
+    #[derive(PartialEq)]
+    enum State {
+        Left,
+        Base,
+        Right,
+    }
+    let mut state = State::Left;
+    let mut left = ContentHunk(vec![]);
+    let mut base = ContentHunk(vec![]);
+    let mut right = ContentHunk(vec![]);
+    for line in input.split_inclusive(|b| *b == b'\n') {
+        if is_conflict_marker(line, marker_len) {
+            match line[0] {
+                CONFLICT_GIT_ANCESTOR_LINE_CHAR if state == State::Left => {
+                    state = State::Base;
+                    continue;
+                }
+                CONFLICT_GIT_SEPARATOR_LINE_CHAR if state == State::Base => {
+                    state = State::Right;
+                    continue;
+                }
+                CONFLICT_GIT_ANCESTOR_LINE_CHAR | CONFLICT_GIT_SEPARATOR_LINE_CHAR => {
+                    return Err(ConflictParseError::MalformedGitConflict);
+                }
+                _ => {}
+            }
+        }
+        match state {
+            State::Left => left.0.extend_from_slice(line),
+            State::Base => base.0.extend_from_slice(line),
+            State::Right => right.0.extend_from_slice(line),
+        }
+    }
+    if state != State::Right {
+        return Err(ConflictParseError::MalformedGitConflict);
     }
+    Ok(Merge::from_removes_adds(vec![base], vec![left, right]))
 }
 
-fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
+/// Parses the body of a conflict with jj's section markers.
+fn parse_jj_style_conflict_hunk(
+    input: &[u8],
+    marker_len: usize,
+) -> Result<Merge<ContentHunk>, ConflictParseError> {
     enum State {
         Diff,
         Minus,
@@ -391,7 +1073,7 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
     let mut removes = vec![];
     let mut adds = vec![];
     for line in input.split_inclusive(|b| *b == b'\n') {
-        if CONFLICT_MARKER_REGEX.is_match_at(line, 0) {
+        if is_conflict_marker(line, marker_len) {
             match line[0] {
                 CONFLICT_DIFF_LINE_CHAR => {
                     state = State::Diff;
@@ -423,7 +1105,9 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
                     adds.last_mut().unwrap().0.extend_from_slice(rest);
                 } else {
                     // Doesn't look like a conflict
-                    return Merge::resolved(ContentHunk(vec![]));
+                    return Err(ConflictParseError::UnexpectedDiffLine {
+                        line: String::from_utf8_lossy(line).trim_end().to_owned(),
+                    });
                 }
             }
             State::Minus => {
@@ -434,23 +1118,63 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
             }
             State::Unknown => {
                 // Doesn't look like a conflict
-                return Merge::resolved(ContentHunk(vec![]));
+                return Err(ConflictParseError::ContentBeforeSection);
             }
         }
     }
 
-    Merge::from_removes_adds(removes, adds)
+    if adds.len() != removes.len() + 1 {
+        // Doesn't look like a conflict. This can happen if the file contains
+        // lines that look like conflict markers.
+        return Err(ConflictParseError::AddRemoveCountMismatch {
+            adds: adds.len(),
+            removes: removes.len(),
+        });
+    }
+    Ok(Merge::from_removes_adds(removes, adds))
 }
 
 /// Parses conflict markers in `content` and returns an updated version of
 /// `file_ids` with the new contents. If no (valid) conflict markers remain, a
 /// single resolves `FileId` will be returned.
+///
+/// The conflict markers are expected to be as long as the ones the old
+/// conflict is materialized with, as chosen by
+/// `choose_materialized_conflict_marker_len()`. Shorter marker-like lines,
+/// e.g. ones the terms already contained, are parsed as content. If the new
+/// terms contain lines looking like markers of that length, longer markers
+/// are chosen the next time the conflict is materialized.
 pub async fn update_from_content(
     file_ids: &Merge<Option<FileId>>,
     store: &Store,
     path: &RepoPath,
     content: &[u8],
 ) -> BackendResult<Merge<Option<FileId>>> {
+    let (new_file_ids, _errors) = update_from_content_with_errors(
+        file_ids,
+        store,
+        path,
+        content,
+        &ConflictMaterializeOptions::default(),
+    )
+    .await?;
+    Ok(new_file_ids)
+}
+
+/// Like `update_from_content()`, but also returns the regions between conflict
+/// markers which were treated as resolved content because they couldn't be
+/// parsed, as reported by [`check_conflict_markers()`].
+///
+/// `options` should be the ones the old conflict was materialized with. If
+/// `options.conflict_hint` is set, the hint line is removed from the new
+/// content unless it was edited.
+pub async fn update_from_content_with_errors(
+    file_ids: &Merge<Option<FileId>>,
+    store: &Store,
+    path: &RepoPath,
+    content: &[u8],
+    options: &ConflictMaterializeOptions,
+) -> BackendResult<(Merge<Option<FileId>>, ConflictParseErrors)> {
     let simplified_file_ids = file_ids.clone().simplify();
     let simplified_file_ids = &simplified_file_ids;
 
@@ -461,38 +1185,137 @@ pub async fn update_from_content(
     // copy.
     let mut old_content = Vec::with_capacity(content.len());
     let merge_hunk = extract_as_single_hunk(simplified_file_ids, store, path).await?;
-    materialize_merge_result(&merge_hunk, &mut old_content).unwrap();
+    let marker_len = options
+        .marker_len
+        .unwrap_or_else(|| choose_materialized_conflict_marker_len(&merge_hunk));
+    materialize_merge_result_with_options(&merge_hunk, options, &mut old_content).unwrap();
     if content == old_content {
-        return Ok(file_ids.clone());
+        return Ok((file_ids.clone(), vec![]));
     }
+    let content = if options.conflict_hint {
+        strip_conflict_hint(content, marker_len)
+    } else {
+        Cow::Borrowed(content)
+    };
+    update_from_changed_content(
+        file_ids,
+        simplified_file_ids,
+        store,
+        path,
+        &content,
+        marker_len,
+    )
+}
 
+/// Like `update_from_content()`, but reads the new content from `reader`.
+///
+/// The new content is compared against the materialized old content while
+/// reading, and is only buffered once a difference is found. If the content is
+/// unchanged, `file_ids` will be returned as is.
+pub async fn update_from_content_reader(
+    file_ids: &Merge<Option<FileId>>,
+    store: &Store,
+    path: &RepoPath,
+    reader: &mut (dyn AsyncRead + Unpin),
+) -> BackendResult<Merge<Option<FileId>>> {
+    let simplified_file_ids = file_ids.clone().simplify();
+    let simplified_file_ids = &simplified_file_ids;
+
+    let mut old_content = vec![];
+    let merge_hunk = extract_as_single_hunk(simplified_file_ids, store, path).await?;
+    let marker_len = choose_materialized_conflict_marker_len(&merge_hunk);
+    materialize_merge_result(&merge_hunk, &mut old_content).unwrap();
+
+    let map_read_err = |err: std::io::Error| BackendError::Other(err.into());
+    let mut buf = [0; 8192];
+    let mut matched_len = 0;
+    let mut content = loop {
+        let n = reader.read(&mut buf).await.map_err(map_read_err)?;
+        if n == 0 {
+            if matched_len == old_content.len() {
+                return Ok(file_ids.clone());
+            }
+            break old_content[..matched_len].to_vec();
+        }
+        let chunk = &buf[..n];
+        if old_content[matched_len..].starts_with(chunk) {
+            matched_len += n;
+        } else {
+            let mut content = old_content[..matched_len].to_vec();
+            content.extend_from_slice(chunk);
+            break content;
+        }
+    };
+    reader
+        .read_to_end(&mut content)
+        .await
+        .map_err(map_read_err)?;
+    let (new_file_ids, _errors) = update_from_changed_content(
+        file_ids,
+        simplified_file_ids,
+        store,
+        path,
+        &content,
+        marker_len,
+    )?;
+    Ok(new_file_ids)
+}
+
+/// Updates `file_ids` with the `content` which is known to differ from the
+/// materialized old content. Only conflict markers at least `marker_len`
+/// characters long are parsed. Also returns the regions between conflict
+/// markers which couldn't be parsed.
+fn update_from_changed_content(
+    file_ids: &Merge<Option<FileId>>,
+    simplified_file_ids: &Merge<Option<FileId>>,
+    store: &Store,
+    path: &RepoPath,
+    content: &[u8],
+    marker_len: usize,
+) -> BackendResult<(Merge<Option<FileId>>, ConflictParseErrors)> {
     // Parse conflicts from the new content using the arity of the simplified
     // conflicts initially. If unsuccessful, attempt to parse conflicts from with
     // the arity of the unsimplified conflicts since such a conflict may be
     // present in the working copy if written by an earlier version of jj.
-    let (used_file_ids, hunks) = 'hunks: {
-        if let Some(hunks) = parse_conflict(content, simplified_file_ids.num_sides()) {
-            break 'hunks (simplified_file_ids, hunks);
+    let mut errors = vec![];
+    let (used_file_ids, parsed) = 'hunks: {
+        if let Some(parsed) = parse_conflict_impl(
+            content,
+            simplified_file_ids.num_sides(),
+            marker_len,
+            &mut errors,
+        ) {
+            break 'hunks (simplified_file_ids, parsed);
         };
         if simplified_file_ids.num_sides() != file_ids.num_sides() {
-            if let Some(hunks) = parse_conflict(content, file_ids.num_sides()) {
-                break 'hunks (file_ids, hunks);
+            let mut unsimplified_errors = vec![];
+            if let Some(parsed) = parse_conflict_impl(
+                content,
+                file_ids.num_sides(),
+                marker_len,
+                &mut unsimplified_errors,
+            ) {
+                errors = unsimplified_errors;
+                break 'hunks (file_ids, parsed);
             };
         };
         // Either there are no markers or they don't have the expected arity
         let file_id = store.write_file(path, &mut &content[..])?;
-        return Ok(Merge::normal(file_id));
+        return Ok((Merge::normal(file_id), errors));
     };
 
     let mut contents = used_file_ids.map(|_| vec![]);
-    for hunk in hunks {
-        if let Some(slice) = hunk.as_resolved() {
-            for content in contents.iter_mut() {
-                content.extend_from_slice(&slice.0);
+    for hunk in parsed.hunks {
+        match hunk {
+            ConflictHunk::Resolved(slice) => {
+                for content in contents.iter_mut() {
+                    content.extend_from_slice(&slice.0);
+                }
             }
-        } else {
-            for (content, slice) in zip(contents.iter_mut(), hunk.into_iter()) {
-                content.extend(slice.0);
+            ConflictHunk::Conflict(hunk) => {
+                for (content, slice) in zip(contents.iter_mut(), hunk) {
+                    content.extend(slice.0);
+                }
             }
         }
     }
@@ -503,7 +1326,7 @@ pub async fn update_from_content(
         .any(|(content, file_id)| file_id.is_none() && !content.is_empty())
     {
         let file_id = store.write_file(path, &mut &content[..])?;
-        return Ok(Merge::normal(file_id));
+        return Ok((Merge::normal(file_id), errors));
     }
 
     // Now write the new files contents we found by parsing the file with conflict
@@ -533,5 +1356,5 @@ pub async fn update_from_content(
     } else {
         Merge::from_vec(new_file_ids)
     };
-    Ok(new_file_ids)
+    Ok((new_file_ids, errors))
 }
@@ -146,6 +146,12 @@ impl<'a> Iterator for DiffLineIterator<'a> {
 #[derive(PartialEq, Eq, Clone)]
 pub struct ContentHunk(pub Vec<u8>);
 
+impl AsRef<[u8]> for ContentHunk {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl Debug for ContentHunk {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         String::from_utf8_lossy(&self.0).fmt(f)
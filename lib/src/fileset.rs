@@ -17,6 +17,7 @@
 use std::collections::HashMap;
 use std::{iter, path, slice};
 
+use itertools::Itertools as _;
 use once_cell::sync::Lazy;
 use thiserror::Error;
 
@@ -26,8 +27,8 @@ use crate::fileset_parser::{
 };
 pub use crate::fileset_parser::{FilesetParseError, FilesetParseErrorKind, FilesetParseResult};
 use crate::matchers::{
-    DifferenceMatcher, EverythingMatcher, FileGlobsMatcher, FilesMatcher, IntersectionMatcher,
-    Matcher, NothingMatcher, PrefixMatcher, UnionMatcher,
+    union_all_matchers, DifferenceMatcher, EverythingMatcher, FileGlobsMatcher, FilesMatcher,
+    IntersectionMatcher, Matcher, NothingMatcher, PrefixMatcher, Visit,
 };
 use crate::repo_path::{
     RelativePathParseError, RepoPath, RepoPathBuf, RepoPathUiConverter, UiPathParseError,
@@ -48,22 +49,49 @@ pub enum FilePatternParseError {
     /// Failed to parse glob pattern.
     #[error(transparent)]
     GlobPattern(#[from] glob::PatternError),
+    /// The `.gitignore` line has no pattern.
+    #[error("Empty gitignore pattern")]
+    EmptyGitIgnoreLine,
 }
 
 /// Basic pattern to match `RepoPath`.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum FilePattern {
     /// Matches file (or exact) path.
     FilePath(RepoPathBuf),
     /// Matches path prefix.
+    ///
+    /// The empty (root) path matches all paths in the workspace. Unlike
+    /// `FilesetExpression::All`, it is reported by `explicit_paths()`.
     PrefixPath(RepoPathBuf),
     /// Matches file (or exact) path with glob pattern.
     FileGlob {
         /// Prefix directory path where the `pattern` will be evaluated.
         dir: RepoPathBuf,
         /// Glob pattern relative to `dir`.
+        #[serde(with = "glob_pattern_serde")]
+        pattern: glob::Pattern,
+    },
+    /// Matches paths like a line of a `.gitignore` file placed in `dir`.
+    ///
+    /// A file matches if its path or any of its ancestor directories matches
+    /// the `pattern`.
+    GitIgnore {
+        /// Directory path where the `.gitignore` line would be placed.
+        dir: RepoPathBuf,
+        /// Glob pattern relative to `dir`. Lines which aren't anchored to the
+        /// directory are prefixed with `**/`.
+        #[serde(with = "glob_pattern_serde")]
         pattern: glob::Pattern,
+        /// Whether the line ends with `/`, so only files under a matching
+        /// directory match.
+        dir_only: bool,
     },
+    /// Excludes paths matching the inner pattern from the union this pattern
+    /// belongs to. Parsed from a glob pattern prefixed with `!`.
+    ///
+    /// On its own, this pattern matches nothing.
+    Excluded(Box<FilePattern>),
     // TODO: add more patterns:
     // - FilesInPath: files in directory, non-recursively?
     // - NameGlob or SuffixGlob: file name with glob?
@@ -89,6 +117,7 @@ impl FilePattern {
         // * string pattern syntax (+ case sensitivity?)
         //   * path: literal path (default) (default anchor: prefix)
         //   * glob: glob pattern (default anchor: file)
+        //   * gitignore: .gitignore line (default anchor: name)
         //   * regex?
         match kind {
             "cwd" => Self::cwd_prefix_path(path_converter, input),
@@ -97,6 +126,8 @@ impl FilePattern {
             "root" => Self::root_prefix_path(input),
             "root-file" => Self::root_file_path(input),
             "root-glob" => Self::root_file_glob(input),
+            "cwd-gitignore" | "gitignore" => Self::cwd_gitignore(path_converter, input),
+            "root-gitignore" => Self::root_gitignore(input),
             _ => Err(FilePatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -124,9 +155,10 @@ impl FilePattern {
         path_converter: &RepoPathUiConverter,
         input: impl AsRef<str>,
     ) -> Result<Self, FilePatternParseError> {
-        let (dir, pattern) = split_glob_path(input.as_ref());
+        let (excluded, input) = split_excluded_glob(input.as_ref());
+        let (dir, pattern) = split_glob_path(input);
         let dir = path_converter.parse_file_path(dir)?;
-        Self::file_glob_at(dir, pattern)
+        Self::file_glob_at(dir, pattern).map(|pattern| pattern.excluded_if(excluded))
     }
 
     /// Pattern that matches workspace-relative file (or exact) path.
@@ -144,9 +176,56 @@ impl FilePattern {
 
     /// Pattern that matches workspace-relative file path glob.
     pub fn root_file_glob(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
-        let (dir, pattern) = split_glob_path(input.as_ref());
+        let (excluded, input) = split_excluded_glob(input.as_ref());
+        let (dir, pattern) = split_glob_path(input);
         let dir = RepoPathBuf::from_relative_path(dir)?;
-        Self::file_glob_at(dir, pattern)
+        Self::file_glob_at(dir, pattern).map(|pattern| pattern.excluded_if(excluded))
+    }
+
+    /// Pattern that matches paths like a `.gitignore` line placed in the
+    /// current working directory.
+    pub fn cwd_gitignore(
+        path_converter: &RepoPathUiConverter,
+        input: impl AsRef<str>,
+    ) -> Result<Self, FilePatternParseError> {
+        let dir = path_converter.parse_file_path("")?;
+        Self::gitignore_at(dir, input.as_ref())
+    }
+
+    /// Pattern that matches paths like a `.gitignore` line placed in the
+    /// workspace root.
+    pub fn root_gitignore(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
+        Self::gitignore_at(RepoPathBuf::root(), input.as_ref())
+    }
+
+    fn gitignore_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
+        let (excluded, input) = split_excluded_glob(input);
+        let (dir_only, input) = match input.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+        // A leading or middle separator anchors the line to the directory.
+        let (anchored, input) = match input.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (input.contains('/'), input),
+        };
+        if input.is_empty() {
+            return Err(FilePatternParseError::EmptyGitIgnoreLine);
+        }
+        // Normalize separator to '/', reject ".." which will never match
+        let normalized = RepoPathBuf::from_relative_path(input)?;
+        let normalized = normalized.as_internal_file_string();
+        let pattern = if anchored || normalized.starts_with("**/") {
+            glob::Pattern::new(normalized)?
+        } else {
+            glob::Pattern::new(&format!("**/{normalized}"))?
+        };
+        let pattern = FilePattern::GitIgnore {
+            dir,
+            pattern,
+            dir_only,
+        };
+        Ok(pattern.excluded_if(excluded))
     }
 
     fn file_glob_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
@@ -159,6 +238,14 @@ impl FilePattern {
         Ok(FilePattern::FileGlob { dir, pattern })
     }
 
+    fn excluded_if(self, excluded: bool) -> Self {
+        if excluded {
+            FilePattern::Excluded(Box::new(self))
+        } else {
+            self
+        }
+    }
+
     /// Returns path if this pattern represents a literal path in a workspace.
     /// Returns `None` if this is a glob pattern for example.
     pub fn as_path(&self) -> Option<&RepoPath> {
@@ -166,10 +253,49 @@ impl FilePattern {
             FilePattern::FilePath(path) => Some(path),
             FilePattern::PrefixPath(path) => Some(path),
             FilePattern::FileGlob { .. } => None,
+            FilePattern::GitIgnore { .. } => None,
+            FilePattern::Excluded(_) => None,
+        }
+    }
+
+    /// Returns directory path under which paths matching this pattern would
+    /// be found.
+    ///
+    /// Unlike `as_path()`, this returns the literal `dir` of a glob pattern,
+    /// and the parent directory of a file path.
+    pub fn as_prefix_dir(&self) -> Option<&RepoPath> {
+        match self {
+            FilePattern::FilePath(path) => path.parent(),
+            FilePattern::PrefixPath(path) => Some(path),
+            FilePattern::FileGlob { dir, .. } => Some(dir),
+            FilePattern::GitIgnore { dir, .. } => Some(dir),
+            FilePattern::Excluded(pattern) => pattern.as_prefix_dir(),
         }
     }
 }
 
+/// Breakdown of the patterns in a `FilesetExpression`, which may be used to
+/// choose a strategy before building a matcher.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FilesetCost {
+    /// Number of file (or exact) path patterns.
+    pub file_paths: usize,
+    /// Number of path prefix patterns.
+    pub prefix_paths: usize,
+    /// Number of glob patterns.
+    pub file_globs: usize,
+    /// Whether the expression is known to match everything.
+    pub is_universal: bool,
+}
+
+/// Strips the `!` prefix of an excluded glob pattern.
+fn split_excluded_glob(input: &str) -> (bool, &str) {
+    match input.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    }
+}
+
 /// Splits `input` path into literal directory path and glob pattern.
 fn split_glob_path(input: &str) -> (&str, &str) {
     const GLOB_CHARS: &[char] = &['?', '*', '[', ']']; // see glob::Pattern::escape()
@@ -181,8 +307,28 @@ fn split_glob_path(input: &str) -> (&str, &str) {
     input.split_at(prefix_len)
 }
 
+/// Serializes glob pattern as its source string, which is compiled again on
+/// deserialization.
+mod glob_pattern_serde {
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        pattern: &glob::Pattern,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(pattern.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<glob::Pattern, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        glob::Pattern::new(&source).map_err(serde::de::Error::custom)
+    }
+}
+
 /// AST-level representation of the fileset expression.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum FilesetExpression {
     /// Matches nothing.
     None,
@@ -240,7 +386,10 @@ impl FilesetExpression {
     }
 
     /// Expression that matches any of the given `expressions`.
+    ///
+    /// Duplicate expressions are removed, keeping the first occurrence.
     pub fn union_all(expressions: Vec<FilesetExpression>) -> Self {
+        let expressions = expressions.into_iter().unique().collect_vec();
         match expressions.len() {
             0 => FilesetExpression::none(),
             1 => expressions.into_iter().next().unwrap(),
@@ -258,6 +407,32 @@ impl FilesetExpression {
         FilesetExpression::Difference(Box::new(self), Box::new(other))
     }
 
+    /// Transforms each pattern in this expression by `f`, preserving the
+    /// structure of the expression.
+    pub fn map_patterns(self, mut f: impl FnMut(FilePattern) -> FilePattern) -> Self {
+        fn map(
+            expr: FilesetExpression,
+            f: &mut dyn FnMut(FilePattern) -> FilePattern,
+        ) -> FilesetExpression {
+            match expr {
+                FilesetExpression::None | FilesetExpression::All => expr,
+                FilesetExpression::Pattern(pattern) => FilesetExpression::Pattern(f(pattern)),
+                FilesetExpression::UnionAll(exprs) => FilesetExpression::UnionAll(
+                    exprs.into_iter().map(|expr| map(expr, f)).collect(),
+                ),
+                FilesetExpression::Intersection(expr1, expr2) => FilesetExpression::Intersection(
+                    Box::new(map(*expr1, f)),
+                    Box::new(map(*expr2, f)),
+                ),
+                FilesetExpression::Difference(expr1, expr2) => FilesetExpression::Difference(
+                    Box::new(map(*expr1, f)),
+                    Box::new(map(*expr2, f)),
+                ),
+            }
+        }
+        map(self, &mut f)
+    }
+
     /// Flattens union expression at most one level.
     fn as_union_all(&self) -> &[Self] {
         match self {
@@ -299,21 +474,204 @@ impl FilesetExpression {
         })
     }
 
+    /// Collects directory paths referenced by this expression, which are
+    /// suitable for suggesting path completions under.
+    ///
+    /// For example, `["a", "b/c", ""]` will be returned for expression
+    /// `"a" | glob:"b/c/*.rs" & ~file:"d"`. Duplicates are removed, preserving the
+    /// order of the first occurrence.
+    pub fn collect_prefix_dirs(&self) -> Vec<&RepoPath> {
+        self.dfs_pre()
+            .filter_map(|expr| match expr {
+                FilesetExpression::Pattern(pattern) => pattern.as_prefix_dir(),
+                _ => None,
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns directory prefixes under which all paths matched by this
+    /// expression are found, or `None` if it may match paths anywhere in the
+    /// workspace.
+    ///
+    /// For example, `["a", "b/c.rs"]` will be returned for expression
+    /// `"a" | file:"b/c.rs" | "a/d"`, and `None` for `glob:"**"` evaluated at
+    /// the workspace root. Prefixes covered by another prefix are omitted.
+    pub fn covering_prefixes(&self) -> Option<Vec<RepoPathBuf>> {
+        let prefixes = match self {
+            FilesetExpression::None => vec![],
+            FilesetExpression::All => return None,
+            FilesetExpression::Pattern(pattern) => match pattern {
+                FilePattern::FilePath(path) | FilePattern::PrefixPath(path) => {
+                    vec![path.clone()]
+                }
+                FilePattern::FileGlob { dir, .. } | FilePattern::GitIgnore { dir, .. } => {
+                    vec![dir.clone()]
+                }
+                // Excluded patterns only subtract from the union.
+                FilePattern::Excluded(_) => vec![],
+            },
+            FilesetExpression::UnionAll(exprs) => {
+                let mut prefixes = vec![];
+                for expr in exprs {
+                    prefixes.extend(expr.covering_prefixes()?);
+                }
+                prefixes
+            }
+            FilesetExpression::Intersection(expr1, expr2) => {
+                match (expr1.covering_prefixes(), expr2.covering_prefixes()) {
+                    (None, None) => return None,
+                    (Some(prefixes), None) | (None, Some(prefixes)) => prefixes,
+                    (Some(prefixes1), Some(prefixes2)) => prefixes1
+                        .iter()
+                        .cartesian_product(&prefixes2)
+                        .filter_map(|(prefix1, prefix2)| {
+                            if prefix1.starts_with(prefix2) {
+                                Some(prefix1.clone())
+                            } else if prefix2.starts_with(prefix1) {
+                                Some(prefix2.clone())
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                }
+            }
+            FilesetExpression::Difference(expr1, _) => expr1.covering_prefixes()?,
+        };
+        if prefixes.iter().any(|prefix| prefix.is_root()) {
+            return None;
+        }
+        let minimal = prefixes
+            .iter()
+            .filter(|prefix| {
+                !prefixes
+                    .iter()
+                    .any(|other| other != *prefix && prefix.starts_with(other))
+            })
+            .unique()
+            .cloned()
+            .collect();
+        Some(minimal)
+    }
+
+    /// Estimates the cost of matching this expression by counting the patterns
+    /// in it.
+    pub fn estimate_cost(&self) -> FilesetCost {
+        let mut cost = FilesetCost {
+            is_universal: self.is_universal(),
+            ..Default::default()
+        };
+        for expr in self.dfs_pre() {
+            match expr {
+                FilesetExpression::Pattern(FilePattern::FilePath(_)) => cost.file_paths += 1,
+                FilesetExpression::Pattern(FilePattern::PrefixPath(_)) => cost.prefix_paths += 1,
+                FilesetExpression::Pattern(
+                    FilePattern::FileGlob { .. }
+                    | FilePattern::GitIgnore { .. }
+                    | FilePattern::Excluded(_),
+                ) => cost.file_globs += 1,
+                _ => {}
+            }
+        }
+        cost
+    }
+
+    /// Returns true if this expression is known to match everything. This is
+    /// conservative, so `false` doesn't mean the expression doesn't match
+    /// everything.
+    fn is_universal(&self) -> bool {
+        match self {
+            FilesetExpression::None => false,
+            FilesetExpression::All => true,
+            FilesetExpression::Pattern(FilePattern::PrefixPath(path)) => path.is_root(),
+            FilesetExpression::Pattern(_) => false,
+            FilesetExpression::UnionAll(exprs) => {
+                exprs.iter().any(|expr| expr.is_universal())
+                    && !exprs.iter().any(|expr| {
+                        matches!(expr, FilesetExpression::Pattern(FilePattern::Excluded(_)))
+                    })
+            }
+            FilesetExpression::Intersection(expr1, expr2) => {
+                expr1.is_universal() && expr2.is_universal()
+            }
+            FilesetExpression::Difference(expr1, expr2) => {
+                expr1.is_universal() && matches!(**expr2, FilesetExpression::None)
+            }
+        }
+    }
+
     /// Transforms the expression tree to `Matcher` object.
     pub fn to_matcher(&self) -> Box<dyn Matcher> {
         build_union_matcher(self.as_union_all())
     }
+
+    /// Filters the given `paths` by this expression, preserving their order.
+    ///
+    /// The matcher is built only once, so this is cheaper than calling
+    /// `to_matcher()` per path.
+    pub fn matches_paths<'p>(
+        &self,
+        paths: impl IntoIterator<Item = &'p RepoPath>,
+    ) -> Vec<&'p RepoPath> {
+        let matcher = self.to_matcher();
+        paths
+            .into_iter()
+            .filter(|path| matcher.matches(path))
+            .collect()
+    }
+
+    /// Builds the `Matcher` once and wraps it with this expression, so the
+    /// fileset can be evaluated repeatedly without rebuilding the matcher.
+    pub fn compile(self) -> CompiledFileset {
+        let matcher = self.to_matcher();
+        CompiledFileset {
+            expression: self,
+            matcher,
+        }
+    }
+}
+
+/// Fileset expression paired with its prebuilt `Matcher`.
+#[derive(Debug)]
+pub struct CompiledFileset {
+    expression: FilesetExpression,
+    matcher: Box<dyn Matcher>,
+}
+
+impl CompiledFileset {
+    /// Expression this fileset was compiled from.
+    pub fn expression(&self) -> &FilesetExpression {
+        &self.expression
+    }
+
+    /// Unwraps the expression, dropping the compiled matcher.
+    pub fn into_expression(self) -> FilesetExpression {
+        self.expression
+    }
+}
+
+impl Matcher for CompiledFileset {
+    fn matches(&self, file: &RepoPath) -> bool {
+        self.matcher.matches(file)
+    }
+
+    fn visit(&self, dir: &RepoPath) -> Visit {
+        self.matcher.visit(dir)
+    }
 }
 
 /// Transforms the union `expressions` to `Matcher` object.
 ///
 /// Since `Matcher` typically accepts a set of patterns to be OR-ed, this
-/// function takes a list of union `expressions` as input.
+/// function takes a list of union `expressions` as input. Excluded patterns are
+/// subtracted from the union of the other `expressions`.
 fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
     let mut file_paths = Vec::new();
     let mut prefix_paths = Vec::new();
     let mut file_globs = Vec::new();
-    let mut matchers: Vec<Option<Box<dyn Matcher>>> = Vec::new();
+    let mut excluded = Vec::new();
+    let mut matchers: Vec<Box<dyn Matcher>> = Vec::new();
     for expr in expressions {
         let matcher: Box<dyn Matcher> = match expr {
             // None and All are supposed to be simplified by caller.
@@ -326,52 +684,115 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
                     FilePattern::FileGlob { dir, pattern } => {
                         file_globs.push((dir, pattern.clone()))
                     }
+                    FilePattern::GitIgnore {
+                        dir,
+                        pattern,
+                        dir_only,
+                    } => {
+                        if !dir_only {
+                            file_globs.push((dir, pattern.clone()));
+                        }
+                        // Files under a matching directory match too
+                        let contents = format!("{}/**", pattern.as_str());
+                        file_globs.push((dir, glob::Pattern::new(&contents).unwrap()));
+                    }
+                    FilePattern::Excluded(pattern) => {
+                        excluded.push(FilesetExpression::pattern(pattern.as_ref().clone()))
+                    }
                 }
                 continue;
             }
             // UnionAll is supposed to be flattened by caller.
             FilesetExpression::UnionAll(exprs) => build_union_matcher(exprs),
-            FilesetExpression::Intersection(expr1, expr2) => {
-                let m1 = build_union_matcher(expr1.as_union_all());
-                let m2 = build_union_matcher(expr2.as_union_all());
-                Box::new(IntersectionMatcher::new(m1, m2))
-            }
-            FilesetExpression::Difference(expr1, expr2) => {
-                let m1 = build_union_matcher(expr1.as_union_all());
-                let m2 = build_union_matcher(expr2.as_union_all());
-                Box::new(DifferenceMatcher::new(m1, m2))
+            FilesetExpression::Intersection(..) | FilesetExpression::Difference(..) => {
+                build_difference_matcher(expr)
             }
         };
-        matchers.push(Some(matcher));
+        matchers.push(matcher);
     }
 
+    // File paths and globs under a prefix path would be matched anyway.
+    if !prefix_paths.is_empty() {
+        let is_covered =
+            |path: &RepoPath| prefix_paths.iter().any(|prefix| path.starts_with(prefix));
+        file_paths.retain(|path| !is_covered(path));
+        file_globs.retain(|(dir, _)| !is_covered(dir));
+    }
     if !file_paths.is_empty() {
-        matchers.push(Some(Box::new(FilesMatcher::new(file_paths))));
+        matchers.push(Box::new(FilesMatcher::new(file_paths)));
     }
     if !prefix_paths.is_empty() {
-        matchers.push(Some(Box::new(PrefixMatcher::new(prefix_paths))));
+        matchers.push(Box::new(PrefixMatcher::new(prefix_paths)));
     }
     if !file_globs.is_empty() {
-        matchers.push(Some(Box::new(FileGlobsMatcher::new(file_globs))));
+        matchers.push(Box::new(FileGlobsMatcher::new(file_globs)));
+    }
+    let matcher = union_all_matchers(matchers);
+    if excluded.is_empty() {
+        matcher
+    } else {
+        Box::new(DifferenceMatcher::new(
+            matcher,
+            build_union_matcher(&excluded),
+        ))
     }
-    union_all_matchers(&mut matchers)
 }
 
-/// Concatenates all `matchers` as union.
+/// Transforms the intersection or difference `expr` to `Matcher` object.
 ///
-/// Each matcher element must be wrapped in `Some` so the matchers can be moved
-/// in arbitrary order.
-fn union_all_matchers(matchers: &mut [Option<Box<dyn Matcher>>]) -> Box<dyn Matcher> {
-    match matchers {
-        [] => Box::new(NothingMatcher),
-        [matcher] => matcher.take().expect("matcher should still be available"),
-        _ => {
-            // Build balanced tree to minimize the recursion depth.
-            let (left, right) = matchers.split_at_mut(matchers.len() / 2);
-            let m1 = union_all_matchers(left);
-            let m2 = union_all_matchers(right);
-            Box::new(UnionMatcher::new(m1, m2))
+/// Nested intersections and differences are flattened to the intersection of
+/// the positive terms minus the union of the negated terms, so `~x & ~y` and
+/// `~(x | y)` build the same matcher with a single `DifferenceMatcher` at the
+/// top. Double negations cancel out.
+fn build_difference_matcher(expr: &FilesetExpression) -> Box<dyn Matcher> {
+    let mut positives = Vec::new();
+    let mut negatives = Vec::new();
+    collect_difference_terms(expr, &mut positives, &mut negatives);
+    let matcher = positives
+        .iter()
+        .map(|expr| build_union_matcher(expr.as_union_all()))
+        .reduce(|m1, m2| Box::new(IntersectionMatcher::new(m1, m2)))
+        .unwrap_or_else(|| Box::new(EverythingMatcher));
+    if negatives.is_empty() {
+        matcher
+    } else {
+        let negatives = negatives
+            .iter()
+            .flat_map(|expr| expr.as_union_all())
+            .cloned()
+            .collect_vec();
+        Box::new(DifferenceMatcher::new(
+            matcher,
+            build_union_matcher(&negatives),
+        ))
+    }
+}
+
+/// Splits `expr` into terms such that it matches the intersection of the
+/// `positives` minus the union of the `negatives`.
+fn collect_difference_terms<'a>(
+    expr: &'a FilesetExpression,
+    positives: &mut Vec<&'a FilesetExpression>,
+    negatives: &mut Vec<&'a FilesetExpression>,
+) {
+    match expr {
+        FilesetExpression::All => {}
+        FilesetExpression::Intersection(expr1, expr2) => {
+            collect_difference_terms(expr1, positives, negatives);
+            collect_difference_terms(expr2, positives, negatives);
+        }
+        FilesetExpression::Difference(expr1, expr2) => {
+            collect_difference_terms(expr1, positives, negatives);
+            let mut inner_positives = Vec::new();
+            let mut inner_negatives = Vec::new();
+            collect_difference_terms(expr2, &mut inner_positives, &mut inner_negatives);
+            match (&*inner_positives, &*inner_negatives) {
+                // x ~ (all() ~ y) = x & y
+                ([], [inner]) => positives.push(inner),
+                _ => negatives.push(expr2),
+            }
         }
+        _ => positives.push(expr),
     }
 }
 
@@ -390,9 +811,59 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, FilesetFunction>> = Lazy
         function.expect_no_arguments()?;
         Ok(FilesetExpression::all())
     });
+    map.insert("root", |_path_converter, function| {
+        function.expect_no_arguments()?;
+        Ok(FilesetExpression::prefix_path(RepoPathBuf::root()))
+    });
+    map.insert("union", |path_converter, function| {
+        let [lhs_node, rhs_node] = function.expect_exact_arguments()?;
+        let lhs = resolve_expression(path_converter, lhs_node)?;
+        let rhs = resolve_expression(path_converter, rhs_node)?;
+        Ok(lhs.union(rhs))
+    });
+    map.insert("intersection", |path_converter, function| {
+        let [lhs_node, rhs_node] = function.expect_exact_arguments()?;
+        let lhs = resolve_expression(path_converter, lhs_node)?;
+        let rhs = resolve_expression(path_converter, rhs_node)?;
+        Ok(lhs.intersection(rhs))
+    });
+    map.insert("subtract", |path_converter, function| {
+        let [lhs_node, rhs_node] = function.expect_exact_arguments()?;
+        let lhs = resolve_expression(path_converter, lhs_node)?;
+        let rhs = resolve_expression(path_converter, rhs_node)?;
+        Ok(lhs.difference(rhs))
+    });
+    map.insert("diff_paths", |path_converter, function| {
+        let [lhs_node, rhs_node] = function.expect_exact_arguments()?;
+        let lhs = resolve_file_path_arg(path_converter, lhs_node)?;
+        let rhs = resolve_file_path_arg(path_converter, rhs_node)?;
+        Ok(lhs.difference(rhs))
+    });
     map
 });
 
+/// Resolves a function argument which should be a string literal naming a
+/// cwd-relative file path.
+fn resolve_file_path_arg(
+    path_converter: &RepoPathUiConverter,
+    node: &ExpressionNode,
+) -> FilesetParseResult<FilesetExpression> {
+    let input = match &node.kind {
+        ExpressionKind::Identifier(name) => *name,
+        ExpressionKind::String(name) => name.as_str(),
+        _ => {
+            return Err(FilesetParseError::expression(
+                "Expected file path",
+                node.span,
+            ))
+        }
+    };
+    let pattern = FilePattern::cwd_file_path(path_converter, input).map_err(|err| {
+        FilesetParseError::expression("Invalid file pattern", node.span).with_source(err)
+    })?;
+    Ok(FilesetExpression::pattern(pattern))
+}
+
 fn resolve_function(
     path_converter: &RepoPathUiConverter,
     function: &FunctionCallNode,
@@ -442,15 +913,150 @@ fn resolve_expression(
             let lhs = resolve_expression(path_converter, lhs_node)?;
             let rhs = resolve_expression(path_converter, rhs_node)?;
             match op {
-                BinaryOp::Union => Ok(lhs.union(rhs)),
                 BinaryOp::Intersection => Ok(lhs.intersection(rhs)),
                 BinaryOp::Difference => Ok(lhs.difference(rhs)),
             }
         }
+        ExpressionKind::UnionAll(nodes) => {
+            let expressions: Vec<_> = nodes
+                .iter()
+                .map(|node| resolve_expression(path_converter, node))
+                .try_collect()?;
+            Ok(expressions
+                .into_iter()
+                .reduce(FilesetExpression::union)
+                .unwrap())
+        }
         ExpressionKind::FunctionCall(function) => resolve_function(path_converter, function),
     }
 }
 
+/// Warnings about a fileset expression that was parsed successfully.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FilesetDiagnostics {
+    warnings: Vec<String>,
+}
+
+impl FilesetDiagnostics {
+    /// Creates an empty collection of warnings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if there are no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Iterates over the warning messages in the order they were found.
+    pub fn warnings(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    fn add_warning(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+}
+
+/// Collects the operands of the union `node`, flattening nested unions.
+fn collect_union_operands<'a, 'i>(
+    node: &'a ExpressionNode<'i>,
+    operands: &mut Vec<&'a ExpressionNode<'i>>,
+) {
+    match &node.kind {
+        ExpressionKind::UnionAll(nodes) => {
+            for node in nodes {
+                collect_union_operands(node, operands);
+            }
+        }
+        ExpressionKind::FunctionCall(function)
+            if function.name == "union" && function.args.len() == 2 =>
+        {
+            for arg_node in &function.args {
+                collect_union_operands(arg_node, operands);
+            }
+        }
+        _ => operands.push(node),
+    }
+}
+
+/// Returns true if all paths matched by `pattern` are matched by `other`.
+fn is_pattern_subsumed(pattern: &FilePattern, other: &FilePattern) -> bool {
+    if pattern == other {
+        return true;
+    }
+    match (pattern, other) {
+        (
+            FilePattern::FilePath(path)
+            | FilePattern::PrefixPath(path)
+            | FilePattern::FileGlob { dir: path, .. }
+            | FilePattern::GitIgnore { dir: path, .. },
+            FilePattern::PrefixPath(prefix),
+        ) => path.starts_with(prefix),
+        _ => false,
+    }
+}
+
+/// Adds a warning for each pattern in a union that is redundant because
+/// another pattern of the same union already matches all of its paths.
+fn check_redundant_patterns(
+    diagnostics: &mut FilesetDiagnostics,
+    path_converter: &RepoPathUiConverter,
+    node: &ExpressionNode,
+) {
+    let mut operands = vec![];
+    collect_union_operands(node, &mut operands);
+    let mut patterns: Vec<(&ExpressionNode, FilePattern)> = vec![];
+    for operand in operands {
+        let pattern = match &operand.kind {
+            ExpressionKind::Identifier(name) => {
+                FilePattern::cwd_prefix_path(path_converter, name).ok()
+            }
+            ExpressionKind::String(name) => FilePattern::cwd_prefix_path(path_converter, name).ok(),
+            ExpressionKind::StringPattern { kind, value } => {
+                FilePattern::from_str_kind(path_converter, value, kind).ok()
+            }
+            ExpressionKind::Unary(_, arg_node) => {
+                check_redundant_patterns(diagnostics, path_converter, arg_node);
+                None
+            }
+            ExpressionKind::Binary(_, lhs_node, rhs_node) => {
+                check_redundant_patterns(diagnostics, path_converter, lhs_node);
+                check_redundant_patterns(diagnostics, path_converter, rhs_node);
+                None
+            }
+            ExpressionKind::UnionAll(nodes) => {
+                for node in nodes {
+                    check_redundant_patterns(diagnostics, path_converter, node);
+                }
+                None
+            }
+            ExpressionKind::FunctionCall(function) => {
+                for arg_node in &function.args {
+                    check_redundant_patterns(diagnostics, path_converter, arg_node);
+                }
+                None
+            }
+        };
+        if let Some(pattern) = pattern {
+            patterns.push((operand, pattern));
+        }
+    }
+    for (i, (node, pattern)) in patterns.iter().enumerate() {
+        // Of identical patterns, only the later ones are reported.
+        let subsuming = patterns.iter().enumerate().find(|(j, (_, other))| {
+            *j != i && is_pattern_subsumed(pattern, other) && (pattern != other || *j < i)
+        });
+        if let Some((_, (other_node, _))) = subsuming {
+            diagnostics.add_warning(format!(
+                "The pattern `{}` is redundant given `{}`",
+                node.span.as_str(),
+                other_node.span.as_str()
+            ));
+        }
+    }
+}
+
 /// Parses text into `FilesetExpression` with bare string fallback.
 ///
 /// If the text can't be parsed as a fileset expression, and if it doesn't
@@ -458,10 +1064,22 @@ fn resolve_expression(
 pub fn parse_maybe_bare(
     text: &str,
     path_converter: &RepoPathUiConverter,
+) -> FilesetParseResult<FilesetExpression> {
+    parse_maybe_bare_with_diagnostics(&mut FilesetDiagnostics::new(), text, path_converter)
+}
+
+/// Like `parse_maybe_bare()`, but also reports warnings about the expression
+/// to `diagnostics`, such as patterns that are redundant within a union.
+pub fn parse_maybe_bare_with_diagnostics(
+    diagnostics: &mut FilesetDiagnostics,
+    text: &str,
+    path_converter: &RepoPathUiConverter,
 ) -> FilesetParseResult<FilesetExpression> {
     let node = fileset_parser::parse_program_or_bare_string(text)?;
     // TODO: add basic tree substitution pass to eliminate redundant expressions
-    resolve_expression(path_converter, &node)
+    let expression = resolve_expression(path_converter, &node)?;
+    check_redundant_patterns(diagnostics, path_converter, &node);
+    Ok(expression)
 }
 
 #[cfg(test)]
@@ -470,6 +1088,10 @@ mod tests {
 
     use super::*;
 
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
     fn repo_path_buf(value: impl Into<String>) -> RepoPathBuf {
         RepoPathBuf::from_internal_string(value)
     }
@@ -623,6 +1245,101 @@ mod tests {
         );
         assert!(parse(r#"root-glob:"../*""#).is_err());
         assert!(parse(r#"root-glob:"/*""#).is_err());
+
+        // excluded, with and without meta characters
+        let excluded = |expr| match expr {
+            FilesetExpression::Pattern(pattern) => {
+                FilesetExpression::pattern(FilePattern::Excluded(Box::new(pattern)))
+            }
+            _ => panic!("unexpected expression: {expr:?}"),
+        };
+        assert_eq!(
+            parse(r#"glob:"!**/generated/*.rs""#).unwrap(),
+            excluded(glob_expr("cur*", "**/generated/*.rs"))
+        );
+        assert_eq!(
+            parse(r#"root-glob:"!foo/*.rs""#).unwrap(),
+            excluded(glob_expr("foo", "*.rs"))
+        );
+        assert_eq!(
+            parse(r#"glob:"!foo""#).unwrap(),
+            excluded(FilesetExpression::file_path(repo_path_buf("cur*/foo")))
+        );
+
+        // "./" escapes the leading "!"
+        assert_eq!(
+            parse(r#"glob:"./!foo*""#).unwrap(),
+            glob_expr("cur*", "!foo*")
+        );
+    }
+
+    #[test]
+    fn test_parse_gitignore_pattern() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(text, &path_converter);
+        let gitignore_expr = |dir: &str, pattern: &str, dir_only| {
+            FilesetExpression::pattern(FilePattern::GitIgnore {
+                dir: repo_path_buf(dir),
+                pattern: glob::Pattern::new(pattern).unwrap(),
+                dir_only,
+            })
+        };
+
+        // unanchored
+        assert_eq!(
+            parse(r#"gitignore:"foo""#).unwrap(),
+            gitignore_expr("cur", "**/foo", false)
+        );
+        assert_eq!(
+            parse(r#"gitignore:"*.o""#).unwrap(),
+            gitignore_expr("cur", "**/*.o", false)
+        );
+        assert_eq!(
+            parse(r#"gitignore:"**/foo""#).unwrap(),
+            gitignore_expr("cur", "**/foo", false)
+        );
+
+        // anchored
+        assert_eq!(
+            parse(r#"gitignore:"/foo""#).unwrap(),
+            gitignore_expr("cur", "foo", false)
+        );
+        assert_eq!(
+            parse(r#"cwd-gitignore:"foo/*.o""#).unwrap(),
+            gitignore_expr("cur", "foo/*.o", false)
+        );
+        assert_eq!(
+            parse(r#"root-gitignore:"/foo""#).unwrap(),
+            gitignore_expr("", "foo", false)
+        );
+
+        // directory
+        assert_eq!(
+            parse(r#"gitignore:"foo/""#).unwrap(),
+            gitignore_expr("cur", "**/foo", true)
+        );
+        assert_eq!(
+            parse(r#"root-gitignore:"/foo/bar/""#).unwrap(),
+            gitignore_expr("", "foo/bar", true)
+        );
+
+        // excluded
+        assert_eq!(
+            parse(r#"gitignore:"!foo""#).unwrap(),
+            FilesetExpression::pattern(FilePattern::Excluded(Box::new(FilePattern::GitIgnore {
+                dir: repo_path_buf("cur"),
+                pattern: glob::Pattern::new("**/foo").unwrap(),
+                dir_only: false,
+            })))
+        );
+
+        assert!(parse(r#"gitignore:"""#).is_err());
+        assert!(parse(r#"gitignore:"/""#).is_err());
+        assert!(parse(r#"gitignore:"../foo""#).is_err());
+        assert!(parse(r#"gitignore:"[""#).is_err());
     }
 
     #[test]
@@ -643,26 +1360,86 @@ mod tests {
             message: "Expected 0 arguments",
         }
         "###);
-        insta::assert_debug_snapshot!(parse("ale()").unwrap_err().kind(), @r###"
-        NoSuchFunction {
-            name: "ale",
-            candidates: [
-                "all",
-            ],
+        assert_eq!(
+            parse("root()").unwrap(),
+            FilesetExpression::prefix_path(RepoPathBuf::root())
+        );
+        assert_eq!(parse("root()").unwrap(), parse("root:''").unwrap());
+        assert_eq!(parse("union(x, y)").unwrap(), parse("x | y").unwrap());
+        assert_eq!(
+            parse("intersection(x, y)").unwrap(),
+            parse("x & y").unwrap()
+        );
+        assert_eq!(parse("subtract(x, y)").unwrap(), parse("x ~ y").unwrap());
+        assert_eq!(
+            parse("subtract(all(), union(x, glob:'*.rs'))").unwrap(),
+            parse("~(x | glob:'*.rs')").unwrap()
+        );
+        insta::assert_debug_snapshot!(parse("union(x)").unwrap_err().kind(), @r###"
+        InvalidArguments {
+            name: "union",
+            message: "Expected 2 arguments",
         }
         "###);
-    }
-
-    #[test]
-    fn test_parse_compound_expression() {
-        let settings = insta_settings();
-        let _guard = settings.bind_to_scope();
-        let path_converter = RepoPathUiConverter::Fs {
-            cwd: PathBuf::from("/ws/cur"),
-            base: PathBuf::from("/ws"),
-        };
-        let parse = |text| parse_maybe_bare(text, &path_converter);
-
+        insta::assert_debug_snapshot!(parse("intersection(x, y, z)").unwrap_err().kind(), @r###"
+        InvalidArguments {
+            name: "intersection",
+            message: "Expected 2 arguments",
+        }
+        "###);
+        insta::assert_debug_snapshot!(parse("subtract()").unwrap_err().kind(), @r###"
+        InvalidArguments {
+            name: "subtract",
+            message: "Expected 2 arguments",
+        }
+        "###);
+        assert_eq!(
+            parse("diff_paths('a b', \"c|d\")").unwrap(),
+            FilesetExpression::file_path(repo_path_buf("cur/a b"))
+                .difference(FilesetExpression::file_path(repo_path_buf("cur/c|d")))
+        );
+        assert_eq!(
+            parse("diff_paths(x, ../y)").unwrap(),
+            parse("file:x ~ file:../y").unwrap()
+        );
+        insta::assert_debug_snapshot!(parse("diff_paths(x)").unwrap_err().kind(), @r###"
+        InvalidArguments {
+            name: "diff_paths",
+            message: "Expected 2 arguments",
+        }
+        "###);
+        insta::assert_debug_snapshot!(parse("diff_paths(x, y, z)").unwrap_err().kind(), @r###"
+        InvalidArguments {
+            name: "diff_paths",
+            message: "Expected 2 arguments",
+        }
+        "###);
+        insta::assert_debug_snapshot!(
+            parse("diff_paths(x, glob:'*')").unwrap_err().kind(),
+            @r###"Expression("Expected file path")"###);
+        insta::assert_debug_snapshot!(
+            parse("diff_paths('../..', y)").unwrap_err().kind(),
+            @r###"Expression("Invalid file pattern")"###);
+        insta::assert_debug_snapshot!(parse("ale()").unwrap_err().kind(), @r###"
+        NoSuchFunction {
+            name: "ale",
+            candidates: [
+                "all",
+            ],
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_parse_compound_expression() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(text, &path_converter);
+
         insta::assert_debug_snapshot!(parse("~x").unwrap(), @r###"
         Difference(
             All,
@@ -691,6 +1468,258 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_union_all_dedup() {
+        let expr = FilesetExpression::union_all(vec![
+            FilesetExpression::file_path(repo_path_buf("b")),
+            FilesetExpression::prefix_path(repo_path_buf("a")),
+            FilesetExpression::file_path(repo_path_buf("b")),
+            FilesetExpression::prefix_path(repo_path_buf("b")),
+            FilesetExpression::prefix_path(repo_path_buf("a")),
+        ]);
+        assert_eq!(
+            expr,
+            FilesetExpression::UnionAll(vec![
+                FilesetExpression::file_path(repo_path_buf("b")),
+                FilesetExpression::prefix_path(repo_path_buf("a")),
+                FilesetExpression::prefix_path(repo_path_buf("b")),
+            ])
+        );
+        assert_eq!(
+            expr.explicit_paths().collect_vec(),
+            ["b", "a", "b"].map(RepoPath::from_internal_string)
+        );
+
+        // Duplicate compound expressions are removed, too
+        let compound = || {
+            FilesetExpression::prefix_path(repo_path_buf("a"))
+                .difference(FilesetExpression::file_path(repo_path_buf("a/b")))
+        };
+        let expr = FilesetExpression::union_all(vec![compound(), compound()]);
+        assert_eq!(expr, compound());
+
+        let expr =
+            FilesetExpression::union_all(vec![FilesetExpression::all(), FilesetExpression::all()]);
+        assert_eq!(expr, FilesetExpression::all());
+    }
+
+    #[test]
+    fn test_map_patterns() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(text, &path_converter).unwrap();
+
+        // Re-anchor patterns from "cur" to "other"
+        let reanchor =
+            |path: &RepoPath| match path.strip_prefix(RepoPath::from_internal_string("cur")) {
+                Some(rest) => rest
+                    .components()
+                    .fold(repo_path_buf("other"), |dir, name| dir.join(name)),
+                None => path.to_owned(),
+            };
+        let expr =
+            parse("a | (file:b & ~glob:'c/*') | root:d").map_patterns(|pattern| match pattern {
+                FilePattern::FilePath(path) => FilePattern::FilePath(reanchor(&path)),
+                FilePattern::PrefixPath(path) => FilePattern::PrefixPath(reanchor(&path)),
+                FilePattern::FileGlob { dir, pattern } => FilePattern::FileGlob {
+                    dir: reanchor(&dir),
+                    pattern,
+                },
+                FilePattern::GitIgnore { .. } | FilePattern::Excluded(_) => unreachable!(),
+            });
+        insta::assert_debug_snapshot!(expr, @r###"
+        UnionAll(
+            [
+                Pattern(PrefixPath("other/a")),
+                Intersection(
+                    Pattern(FilePath("other/b")),
+                    Difference(
+                        All,
+                        Pattern(
+                            FileGlob {
+                                dir: "other/c",
+                                pattern: Pattern {
+                                    original: "*",
+                                    tokens: [
+                                        AnySequence,
+                                    ],
+                                    is_recursive: false,
+                                },
+                            },
+                        ),
+                    ),
+                ),
+                Pattern(PrefixPath("d")),
+            ],
+        )
+        "###);
+
+        // Uppercase file paths
+        let expr = parse("file:a | b").map_patterns(|pattern| match pattern {
+            FilePattern::FilePath(path) => FilePattern::FilePath(
+                RepoPathBuf::from_internal_string(path.as_internal_file_string().to_uppercase()),
+            ),
+            pattern => pattern,
+        });
+        insta::assert_debug_snapshot!(expr, @r###"
+        UnionAll(
+            [
+                Pattern(FilePath("CUR/A")),
+                Pattern(PrefixPath("cur/b")),
+            ],
+        )
+        "###);
+
+        // Expressions without patterns are unchanged
+        assert_eq!(
+            parse("all() & ~none()").map_patterns(|_| unreachable!()),
+            parse("all() & ~none()")
+        );
+    }
+
+    #[test]
+    fn test_collect_prefix_dirs() {
+        let collect = |text: &str| -> Vec<RepoPathBuf> {
+            let path_converter = RepoPathUiConverter::Fs {
+                cwd: PathBuf::from("/ws/cur"),
+                base: PathBuf::from("/ws"),
+            };
+            let expr = parse_maybe_bare(text, &path_converter).unwrap();
+            expr.collect_prefix_dirs()
+                .into_iter()
+                .map(|path| path.to_owned())
+                .collect()
+        };
+        assert!(collect("none()").is_empty());
+        assert!(collect("all()").is_empty());
+        assert_eq!(collect("a"), ["cur/a"].map(repo_path_buf));
+        assert_eq!(collect("file:a"), ["cur"].map(repo_path_buf));
+        assert_eq!(collect("root-file:a"), [""].map(repo_path_buf));
+        assert_eq!(collect("glob:'b/c/*.rs'"), ["cur/b/c"].map(repo_path_buf));
+        assert_eq!(
+            collect("root:x | root:a & root-glob:'b/c/*.rs' | ~root-file:d/e"),
+            ["x", "a", "b/c", "d"].map(repo_path_buf)
+        );
+        // Duplicates are removed
+        assert_eq!(
+            collect("root:a | root-file:a/b | root-glob:'a/*' | root:c"),
+            ["a", "c"].map(repo_path_buf)
+        );
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let cost = |text: &str| {
+            parse_maybe_bare(text, &path_converter)
+                .unwrap()
+                .estimate_cost()
+        };
+        assert_eq!(cost("none()"), FilesetCost::default());
+        assert_eq!(
+            cost("all()"),
+            FilesetCost {
+                is_universal: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cost("."),
+            FilesetCost {
+                prefix_paths: 1,
+                is_universal: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cost("file:a | file:b | c"),
+            FilesetCost {
+                file_paths: 2,
+                prefix_paths: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cost("glob:'*.rs' | (d & glob:'e/*') | ~file:f"),
+            FilesetCost {
+                file_paths: 1,
+                prefix_paths: 1,
+                file_globs: 2,
+                is_universal: false,
+            }
+        );
+        assert_eq!(
+            cost("all() & ~a"),
+            FilesetCost {
+                prefix_paths: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cost("all() & (~none() | a)"),
+            FilesetCost {
+                prefix_paths: 1,
+                is_universal: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let round_trip = |text: &str| {
+            let expression = parse_maybe_bare(text, &path_converter).unwrap();
+            let json = serde_json::to_string(&expression).unwrap();
+            let deserialized: FilesetExpression = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, expression);
+            json
+        };
+
+        insta::assert_snapshot!(round_trip("none()"), @r###""None""###);
+        insta::assert_snapshot!(round_trip("all()"), @r###""All""###);
+        insta::assert_snapshot!(
+            round_trip("file:foo/bar"),
+            @r###"{"Pattern":{"FilePath":"foo/bar"}}"###);
+        insta::assert_snapshot!(
+            round_trip("foo"),
+            @r###"{"Pattern":{"PrefixPath":"foo"}}"###);
+        insta::assert_snapshot!(
+            round_trip("glob:'foo/*.[ch]'"),
+            @r###"{"Pattern":{"FileGlob":{"dir":"foo","pattern":"*.[ch]"}}}"###);
+        insta::assert_snapshot!(
+            round_trip("(a | glob:'*') & ~b"),
+            @r###"{"Intersection":[{"UnionAll":[{"Pattern":{"PrefixPath":"a"}},{"Pattern":{"FileGlob":{"dir":"","pattern":"*"}}}]},{"Difference":["All",{"Pattern":{"PrefixPath":"b"}}]}]}"###);
+
+        // The glob pattern is compiled again
+        let expression: FilesetExpression =
+            serde_json::from_str(r#"{"Pattern":{"FileGlob":{"dir":"foo","pattern":"*.rs"}}}"#)
+                .unwrap();
+        let matcher = expression.to_matcher();
+        assert!(matcher.matches(RepoPath::from_internal_string("foo/lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("foo/lib.c")));
+
+        // Invalid glob and path are rejected
+        assert!(serde_json::from_str::<FilesetExpression>(
+            r#"{"Pattern":{"FileGlob":{"dir":"","pattern":"[*"}}}"#,
+        )
+        .is_err());
+        assert!(
+            serde_json::from_str::<FilesetExpression>(r#"{"Pattern":{"FilePath":"/foo"}}"#)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_explicit_paths() {
         let collect = |expr: &FilesetExpression| -> Vec<RepoPathBuf> {
@@ -724,6 +1753,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_covering_prefixes() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let prefixes = |text: &str| {
+            let expr = parse_maybe_bare(text, &path_converter).unwrap();
+            expr.covering_prefixes().map(|prefixes| {
+                prefixes
+                    .iter()
+                    .map(|path| path.as_internal_file_string().to_owned())
+                    .collect_vec()
+            })
+        };
+        assert_eq!(prefixes("none()"), Some(vec![]));
+        assert_eq!(prefixes("all()"), None);
+        assert_eq!(prefixes("."), None);
+        assert_eq!(prefixes(r#"glob:"**""#), None);
+        assert_eq!(
+            prefixes("src/ | docs/api/x.rs").unwrap(),
+            ["src", "docs/api/x.rs"]
+        );
+        assert_eq!(
+            prefixes(r#"glob:"src/*.rs" | root-glob:"docs/**/*.md""#).unwrap(),
+            ["src", "docs"]
+        );
+        // gitignore lines are evaluated relative to their directory
+        assert_eq!(prefixes(r#"src & gitignore:"target/""#).unwrap(), ["src"]);
+        assert_eq!(prefixes(r#"root-gitignore:"/docs/target/""#), None);
+
+        // Covered and duplicated prefixes are omitted
+        assert_eq!(prefixes("a/b | a | c | a/b/c | c").unwrap(), ["a", "c"]);
+        // Excluded patterns don't add prefixes
+        assert_eq!(prefixes(r#"a | glob:"!b/*""#).unwrap(), ["a"]);
+        // Any prefix covering everything makes the whole union unbounded
+        assert_eq!(prefixes(r#"a | glob:"*.rs""#), None);
+
+        // Intersection keeps the narrower of overlapping prefixes
+        assert_eq!(prefixes("(a | b/c) & (a/d | b)").unwrap(), ["a/d", "b/c"]);
+        assert_eq!(prefixes("a & b").unwrap(), [] as [&str; 0]);
+        assert_eq!(prefixes("a & ~glob:'*.rs'").unwrap(), ["a"]);
+        assert_eq!(prefixes(r#"glob:"*.rs" & (a | b)"#).unwrap(), ["a", "b"]);
+        assert_eq!(prefixes(r#"glob:"*.rs" & all()"#), None);
+
+        // Difference is bounded by the left-hand side
+        assert_eq!(prefixes("a ~ a/b").unwrap(), ["a"]);
+        assert_eq!(prefixes("~a"), None);
+    }
+
+    #[test]
+    fn test_root_pattern() {
+        let expr = FilesetExpression::prefix_path(RepoPathBuf::root());
+        let matcher = expr.to_matcher();
+        assert!(matcher.matches(RepoPath::from_internal_string("file")));
+        assert!(matcher.matches(RepoPath::from_internal_string("dir/file")));
+        assert_eq!(matcher.visit(RepoPath::root()), Visit::AllRecursively);
+
+        // The root path is an explicit path, but all() has none
+        assert_eq!(expr.explicit_paths().collect_vec(), [RepoPath::root()]);
+        assert_eq!(FilesetExpression::all().explicit_paths().count(), 0);
+        let expr = FilesetExpression::union_all(vec![
+            FilesetExpression::prefix_path(RepoPathBuf::root()),
+            FilesetExpression::file_path(repo_path_buf("a")),
+        ]);
+        assert_eq!(
+            expr.explicit_paths().collect_vec(),
+            [RepoPath::root(), RepoPath::from_internal_string("a")]
+        );
+    }
+
+    #[test]
+    fn test_parse_redundant_patterns() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let warnings = |text| {
+            let mut diagnostics = FilesetDiagnostics::new();
+            parse_maybe_bare_with_diagnostics(&mut diagnostics, text, &path_converter).unwrap();
+            diagnostics.warnings().map(str::to_owned).collect_vec()
+        };
+
+        // Patterns under a prefix in the same union
+        assert_eq!(
+            warnings("src | src/a.rs"),
+            ["The pattern `src/a.rs` is redundant given `src`"]
+        );
+        assert_eq!(
+            warnings("src/a.rs | src"),
+            ["The pattern `src/a.rs` is redundant given `src`"]
+        );
+        assert_eq!(
+            warnings(r#"src/lib | glob:"src/*.rs" | "src""#),
+            [
+                "The pattern `src/lib` is redundant given `\"src\"`",
+                r#"The pattern `glob:"src/*.rs"` is redundant given `"src"`"#,
+            ]
+        );
+        assert_eq!(
+            warnings("union(src, src/a.rs)"),
+            ["The pattern `src/a.rs` is redundant given `src`"]
+        );
+        // Only the later one of identical patterns
+        assert_eq!(
+            warnings("src | root:src"),
+            ["The pattern `root:src` is redundant given `src`"]
+        );
+        // Unions nested in other expressions
+        assert_eq!(
+            warnings("~(src | src/a.rs) & foo"),
+            ["The pattern `src/a.rs` is redundant given `src`"]
+        );
+
+        // Patterns not subsumed by another pattern
+        assert!(warnings("src").is_empty());
+        assert!(warnings("src | src2/a.rs").is_empty());
+        assert!(warnings("file:src | src/a.rs").is_empty());
+        assert!(warnings(r#"glob:"*.rs" | src"#).is_empty());
+        assert!(warnings(r#"src | glob:"!src/*.rs""#).is_empty());
+        // Patterns in different unions
+        assert!(warnings("src & src/a.rs").is_empty());
+        assert!(warnings("src ~ src/a.rs").is_empty());
+        assert!(warnings("(src & foo) | src/a.rs").is_empty());
+    }
+
     #[test]
     fn test_build_matcher_simple() {
         let settings = insta_settings();
@@ -805,6 +1960,197 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_build_matcher_gitignore_pattern() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/dir"),
+            base: PathBuf::from("/ws"),
+        };
+        let to_matcher = |text| {
+            parse_maybe_bare(text, &path_converter)
+                .unwrap()
+                .to_matcher()
+        };
+
+        // Unanchored line matches at any depth, including files under a
+        // matching directory
+        let m = to_matcher(r#"gitignore:"foo""#);
+        assert!(!m.matches(repo_path("foo")));
+        assert!(m.matches(repo_path("dir/foo")));
+        assert!(m.matches(repo_path("dir/a/b/foo")));
+        assert!(m.matches(repo_path("dir/foo/bar")));
+        assert!(m.matches(repo_path("dir/a/foo/bar")));
+        assert!(!m.matches(repo_path("dir/foobar")));
+        assert!(!m.matches(repo_path("dir/bar")));
+
+        // Anchored line matches only relative to the directory
+        let m = to_matcher(r#"gitignore:"/foo""#);
+        assert!(m.matches(repo_path("dir/foo")));
+        assert!(m.matches(repo_path("dir/foo/bar")));
+        assert!(!m.matches(repo_path("dir/a/foo")));
+        assert!(!m.matches(repo_path("dir/a/foo/bar")));
+        let m = to_matcher(r#"gitignore:"a/*.o""#);
+        assert!(m.matches(repo_path("dir/a/b.o")));
+        assert!(m.matches(repo_path("dir/a/b.o/c")));
+        assert!(!m.matches(repo_path("dir/a/b/c.o")));
+        assert!(!m.matches(repo_path("dir/x/a/b.o")));
+
+        // Directory line matches only files under a matching directory
+        let m = to_matcher(r#"gitignore:"foo/""#);
+        assert!(!m.matches(repo_path("dir/foo")));
+        assert!(!m.matches(repo_path("dir/a/foo")));
+        assert!(m.matches(repo_path("dir/foo/bar")));
+        assert!(m.matches(repo_path("dir/a/foo/bar")));
+        assert!(m.matches(repo_path("dir/a/foo/b/c")));
+        let m = to_matcher(r#"root-gitignore:"/foo/""#);
+        assert!(!m.matches(repo_path("foo")));
+        assert!(m.matches(repo_path("foo/bar")));
+        assert!(!m.matches(repo_path("dir/foo/bar")));
+
+        // Excluded line subtracts from the other patterns
+        let m = to_matcher(r#"root:"" | gitignore:"!*.o""#);
+        assert!(m.matches(repo_path("foo")));
+        assert!(m.matches(repo_path("dir/foo")));
+        assert!(!m.matches(repo_path("dir/foo.o")));
+        assert!(!m.matches(repo_path("dir/a/foo.o")));
+        assert!(m.matches(repo_path("foo.o")));
+    }
+
+    #[test]
+    fn test_build_matcher_excluded_glob() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(text, &path_converter).unwrap();
+
+        // Excluded glob is subtracted from the other patterns of the union
+        let expr = parse(r#"glob:"**/*.rs" | glob:"!**/generated/*.rs""#);
+        let matcher = expr.to_matcher();
+        assert!(matcher.matches(RepoPath::from_internal_string("src/lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/generated/lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/README.md")));
+        assert!(!expr.estimate_cost().is_universal);
+
+        // The order of the patterns doesn't matter, and the exclusion applies
+        // to non-glob patterns as well
+        let matcher = parse(r#"glob:"!**/generated/*.rs" | src | glob:"**/*.rs""#).to_matcher();
+        assert!(matcher.matches(RepoPath::from_internal_string("src/README.md")));
+        assert!(matcher.matches(RepoPath::from_internal_string("lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/generated/lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("generated/lib.rs")));
+
+        // all() in the union is no longer universal
+        let expr = parse(r#"all() | glob:"!**/generated/*.rs""#);
+        assert!(!expr.estimate_cost().is_universal);
+        let matcher = expr.to_matcher();
+        assert!(matcher.matches(RepoPath::from_internal_string("src/README.md")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/generated/lib.rs")));
+
+        // Outside of a union, excluded glob matches nothing
+        let matcher = parse(r#"src & glob:"!**/generated/*.rs""#).to_matcher();
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/lib.rs")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/generated/lib.rs")));
+    }
+
+    #[test]
+    fn test_matches_paths() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(text, &path_converter).unwrap();
+        let paths = [
+            "README.md",
+            "lib/src/lib.rs",
+            "lib/src/fileset.rs",
+            "lib/tests/test_fileset.rs",
+            "cli/src/main.rs",
+        ]
+        .map(RepoPath::from_internal_string);
+        let matches_paths = |text| {
+            parse(text)
+                .matches_paths(paths)
+                .into_iter()
+                .map(|path| path.as_internal_file_string())
+                .collect_vec()
+        };
+
+        // Glob
+        assert_eq!(
+            matches_paths(r#"glob:"**/*.rs""#),
+            [
+                "lib/src/lib.rs",
+                "lib/src/fileset.rs",
+                "lib/tests/test_fileset.rs",
+                "cli/src/main.rs",
+            ]
+        );
+        assert_eq!(matches_paths(r#"glob:"*.md""#), ["README.md"]);
+
+        // Prefix
+        assert_eq!(
+            matches_paths("lib"),
+            [
+                "lib/src/lib.rs",
+                "lib/src/fileset.rs",
+                "lib/tests/test_fileset.rs",
+            ]
+        );
+        assert_eq!(matches_paths("lib/src/lib.rs"), ["lib/src/lib.rs"]);
+        assert_eq!(matches_paths("nonexistent"), [] as [&str; 0]);
+
+        // Compound
+        assert_eq!(
+            matches_paths(r#"lib ~ glob:"lib/tests/*""#),
+            ["lib/src/lib.rs", "lib/src/fileset.rs"]
+        );
+        assert_eq!(
+            matches_paths(r#"README.md | (glob:"**/*.rs" & cli)"#),
+            ["README.md", "cli/src/main.rs"]
+        );
+        assert_eq!(matches_paths(r#"~glob:"**/*.rs""#), ["README.md"]);
+        assert_eq!(matches_paths("all()").len(), paths.len());
+    }
+
+    #[test]
+    fn test_compiled_fileset() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let paths = [
+            "",
+            "README.md",
+            "lib",
+            "lib/src",
+            "lib/src/lib.rs",
+            "lib/tests/test_fileset.rs",
+            "cli/src/main.rs",
+        ]
+        .map(RepoPath::from_internal_string);
+        for text in [
+            "none()",
+            "all()",
+            "lib",
+            r#"glob:"**/*.rs""#,
+            r#"lib ~ glob:"lib/tests/*""#,
+            r#"README.md | (glob:"**/*.rs" & cli)"#,
+            r#"~glob:"**/*.rs""#,
+        ] {
+            let expression = parse_maybe_bare(text, &path_converter).unwrap();
+            let compiled = expression.clone().compile();
+            assert_eq!(compiled.expression(), &expression);
+            for _ in 0..2 {
+                for path in paths {
+                    let matcher = expression.to_matcher();
+                    assert_eq!(compiled.matches(path), matcher.matches(path), "{text}");
+                    assert_eq!(compiled.visit(path), matcher.visit(path), "{text}");
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_build_matcher_union_patterns_of_same_kind() {
         let settings = insta_settings();
@@ -864,6 +2210,84 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_build_matcher_union_patterns_covered_by_prefix() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let glob_expr = |dir: &str, pattern: &str| {
+            FilesetExpression::pattern(FilePattern::FileGlob {
+                dir: repo_path_buf(dir),
+                pattern: glob::Pattern::new(pattern).unwrap(),
+            })
+        };
+
+        // File and glob under the prefix are redundant
+        let expr = FilesetExpression::union_all(vec![
+            FilesetExpression::file_path(repo_path_buf("src/a.rs")),
+            FilesetExpression::prefix_path(repo_path_buf("src")),
+            glob_expr("src/b", "*.rs"),
+        ]);
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        PrefixMatcher {
+            tree: Dir {
+                "src": Prefix {},
+            },
+        }
+        "###);
+
+        // The prefix itself can be a file
+        let expr = FilesetExpression::union_all(vec![
+            FilesetExpression::file_path(repo_path_buf("src")),
+            FilesetExpression::prefix_path(repo_path_buf("src")),
+        ]);
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        PrefixMatcher {
+            tree: Dir {
+                "src": Prefix {},
+            },
+        }
+        "###);
+
+        // Sibling paths with a common string prefix aren't covered
+        let expr = FilesetExpression::union_all(vec![
+            FilesetExpression::file_path(repo_path_buf("src2/a.rs")),
+            FilesetExpression::prefix_path(repo_path_buf("src")),
+            glob_expr("", "*.rs"),
+        ]);
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        UnionMatcher {
+            input1: FilesMatcher {
+                tree: Dir {
+                    "src2": Dir {
+                        "a.rs": File {},
+                    },
+                },
+            },
+            input2: UnionMatcher {
+                input1: PrefixMatcher {
+                    tree: Dir {
+                        "src": Prefix {},
+                    },
+                },
+                input2: FileGlobsMatcher {
+                    tree: [
+                        Pattern {
+                            original: "*.rs",
+                            tokens: [
+                                AnySequence,
+                                Char('.'),
+                                Char('r'),
+                                Char('s'),
+                            ],
+                            is_recursive: false,
+                        },
+                    ] {},
+                },
+            },
+        }
+        "###);
+    }
+
     #[test]
     fn test_build_matcher_unnormalized_union() {
         let settings = insta_settings();
@@ -896,10 +2320,7 @@ mod tests {
         insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
         UnionMatcher {
             input1: UnionMatcher {
-                input1: IntersectionMatcher {
-                    input1: EverythingMatcher,
-                    input2: NothingMatcher,
-                },
+                input1: NothingMatcher,
                 input2: DifferenceMatcher {
                     wanted: NothingMatcher,
                     unwanted: EverythingMatcher,
@@ -920,4 +2341,91 @@ mod tests {
         }
         "###);
     }
+
+    #[test]
+    fn test_build_matcher_negated() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path = |name| FilesetExpression::file_path(repo_path_buf(name));
+        let negate = |expr| FilesetExpression::all().difference(expr);
+
+        // ~(a | b | c)
+        let expr = negate(FilesetExpression::union_all(vec![
+            path("a"),
+            path("b"),
+            path("c"),
+        ]));
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        DifferenceMatcher {
+            wanted: EverythingMatcher,
+            unwanted: FilesMatcher {
+                tree: Dir {
+                    "a": File {},
+                    "b": File {},
+                    "c": File {},
+                },
+            },
+        }
+        "###);
+
+        // ~a & ~b is the same as ~(a | b)
+        let expr = negate(path("a")).intersection(negate(path("b")));
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        DifferenceMatcher {
+            wanted: EverythingMatcher,
+            unwanted: FilesMatcher {
+                tree: Dir {
+                    "a": File {},
+                    "b": File {},
+                },
+            },
+        }
+        "###);
+
+        // (x ~ a) ~ b is the same as x ~ (a | b)
+        let expr = path("x").difference(path("a")).difference(path("b"));
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        DifferenceMatcher {
+            wanted: FilesMatcher {
+                tree: Dir {
+                    "x": File {},
+                },
+            },
+            unwanted: FilesMatcher {
+                tree: Dir {
+                    "a": File {},
+                    "b": File {},
+                },
+            },
+        }
+        "###);
+
+        // ~~a is the same as a
+        let expr = negate(negate(path("a")));
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        FilesMatcher {
+            tree: Dir {
+                "a": File {},
+            },
+        }
+        "###);
+
+        // x ~ ~(a | b) is the same as x & (a | b)
+        let expr = path("x").difference(negate(path("a").union(path("b"))));
+        insta::assert_debug_snapshot!(expr.to_matcher(), @r###"
+        IntersectionMatcher {
+            input1: FilesMatcher {
+                tree: Dir {
+                    "x": File {},
+                },
+            },
+            input2: FilesMatcher {
+                tree: Dir {
+                    "a": File {},
+                    "b": File {},
+                },
+            },
+        }
+        "###);
+    }
 }
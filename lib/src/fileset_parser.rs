@@ -18,7 +18,7 @@ use std::error;
 
 use itertools::Itertools as _;
 use once_cell::sync::Lazy;
-use pest::iterators::Pair;
+use pest::iterators::{Pair, Pairs};
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest::Parser;
 use pest_derive::Parser;
@@ -88,6 +88,8 @@ pub struct FilesetParseError {
 pub enum FilesetParseErrorKind {
     #[error("Syntax error")]
     SyntaxError,
+    #[error("Unterminated string literal")]
+    UnterminatedString,
     #[error(r#"Function "{name}" doesn't exist"#)]
     NoSuchFunction {
         name: String,
@@ -164,9 +166,14 @@ fn rename_rules_in_pest_error(err: pest::error::Error<Rule>) -> pest::error::Err
 pub enum ExpressionKind<'i> {
     Identifier(&'i str),
     String(String),
-    StringPattern { kind: &'i str, value: String },
+    StringPattern {
+        kind: &'i str,
+        value: String,
+    },
     Unary(UnaryOp, Box<ExpressionNode<'i>>),
     Binary(BinaryOp, Box<ExpressionNode<'i>>, Box<ExpressionNode<'i>>),
+    /// `x | y | ..`
+    UnionAll(Vec<ExpressionNode<'i>>),
     FunctionCall(Box<FunctionCallNode<'i>>),
 }
 
@@ -178,8 +185,6 @@ pub enum UnaryOp {
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum BinaryOp {
-    /// `|`
-    Union,
     /// `&`
     Intersection,
     /// `~`
@@ -273,7 +278,7 @@ fn parse_expression_node(pair: Pair<Rule>) -> FilesetParseResult<ExpressionNode>
         })
         .map_infix(|lhs, op, rhs| {
             let op_kind = match op.as_rule() {
-                Rule::union_op => BinaryOp::Union,
+                Rule::union_op => return Ok(union_nodes(lhs?, rhs?)),
                 Rule::intersection_op => BinaryOp::Intersection,
                 Rule::difference_op => BinaryOp::Difference,
                 r => panic!("unexpected infix operator rule {r:?}"),
@@ -287,11 +292,166 @@ fn parse_expression_node(pair: Pair<Rule>) -> FilesetParseResult<ExpressionNode>
         .parse(pair.into_inner())
 }
 
+fn union_nodes<'i>(lhs: ExpressionNode<'i>, rhs: ExpressionNode<'i>) -> ExpressionNode<'i> {
+    let span = lhs.span.start_pos().span(&rhs.span.end_pos());
+    let expr = match lhs.kind {
+        // Flatten "x | y | z" so long chains of unions don't nest the tree.
+        ExpressionKind::UnionAll(mut nodes) => {
+            nodes.push(rhs);
+            ExpressionKind::UnionAll(nodes)
+        }
+        _ => ExpressionKind::UnionAll(vec![lhs, rhs]),
+    };
+    ExpressionNode::new(expr, span)
+}
+
+/// Default limit of the depth of the expression tree, which is large enough
+/// for hand-written expressions, but small enough to not overflow the stack
+/// while the tree is processed recursively.
+pub const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 256;
+
+fn nested_too_deeply_error(max_depth: usize, span: pest::Span<'_>) -> FilesetParseError {
+    FilesetParseError::expression(
+        format!("Expression is nested too deeply (the limit is {max_depth})"),
+        span,
+    )
+}
+
+/// Calls `f` with each character of `text` outside of string literals.
+/// Returns the position and quote character of the string literal left
+/// unterminated at the end of `text`, if any.
+fn scan_outside_string_literals(
+    text: &str,
+    mut f: impl FnMut(char) -> FilesetParseResult<()>,
+) -> FilesetParseResult<Option<(usize, char)>> {
+    let mut quote = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some((_, '"')), '\\') => {
+                chars.next();
+            }
+            (Some((_, q)), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some((i, c)),
+            (None, _) => f(c)?,
+        }
+    }
+    Ok(quote)
+}
+
+/// Returns an error if parentheses in `text` are nested more deeply than
+/// `max_depth`. This has to be checked before parsing since the parser
+/// recurses on each level of parentheses.
+fn check_parentheses_depth(text: &str, max_depth: usize) -> FilesetParseResult<()> {
+    let mut depth: usize = 0;
+    scan_outside_string_literals(text, |c| {
+        match c {
+            '(' => {
+                depth += 1;
+                if depth > max_depth {
+                    let span = pest::Span::new(text, 0, text.len()).unwrap();
+                    return Err(nested_too_deeply_error(max_depth, span));
+                }
+            }
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Returns an error pointing at the opening quote if `text` ends inside a
+/// string literal. This is checked after a syntax error to report a more
+/// specific error than the one from the grammar.
+fn check_unterminated_string(text: &str) -> FilesetParseResult<()> {
+    if let Some((start, c)) = scan_outside_string_literals(text, |_| Ok(()))? {
+        let span = pest::Span::new(text, start, start + c.len_utf8()).unwrap();
+        return Err(FilesetParseError::new(
+            FilesetParseErrorKind::UnterminatedString,
+            span,
+        ));
+    }
+    Ok(())
+}
+
+/// Parses `text` as `rule`, reporting unterminated strings specifically.
+fn parse_rule(rule: Rule, text: &str) -> FilesetParseResult<Pairs<'_, Rule>> {
+    FilesetParser::parse(rule, text).map_err(|err| {
+        check_unterminated_string(text)
+            .err()
+            .unwrap_or_else(|| err.into())
+    })
+}
+
+/// Returns an error if the tree to be built from the `expression` pair would
+/// be deeper than `max_depth`.
+///
+/// The depth is estimated from the pairs without building the tree since
+/// `PrattParser` recurses on each prefix operator. Unions are flattened into a
+/// single node, so only the chains of the other infix operators between them
+/// add to the depth.
+fn check_expression_depth(pair: &Pair<Rule>, max_depth: usize) -> FilesetParseResult<()> {
+    fn expression_depth(pair: Pair<Rule>) -> usize {
+        assert_eq!(pair.as_rule(), Rule::expression);
+        let mut max_union_operand_depth = 0;
+        let mut max_operand_depth = 0;
+        let mut num_prefix_ops = 0;
+        let mut num_infix_ops = 0;
+        let mut has_union = false;
+        for child in pair.into_inner() {
+            match child.as_rule() {
+                Rule::negate_op => num_prefix_ops += 1,
+                Rule::union_op => {
+                    max_union_operand_depth =
+                        max_union_operand_depth.max(max_operand_depth + num_infix_ops);
+                    max_operand_depth = 0;
+                    num_infix_ops = 0;
+                    has_union = true;
+                }
+                Rule::intersection_op | Rule::difference_op => num_infix_ops += 1,
+                Rule::primary => {
+                    let depth = num_prefix_ops + primary_depth(child);
+                    max_operand_depth = max_operand_depth.max(depth);
+                    num_prefix_ops = 0;
+                }
+                r => panic!("unexpected expression rule {r:?}"),
+            }
+        }
+        max_union_operand_depth = max_union_operand_depth.max(max_operand_depth + num_infix_ops);
+        max_union_operand_depth + usize::from(has_union)
+    }
+
+    fn primary_depth(pair: Pair<Rule>) -> usize {
+        let first = pair.into_inner().next().unwrap();
+        match first.as_rule() {
+            Rule::expression => expression_depth(first),
+            Rule::function => {
+                let (_name_pair, args_pair) = first.into_inner().collect_tuple().unwrap();
+                1 + args_pair
+                    .into_inner()
+                    .map(expression_depth)
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 1,
+        }
+    }
+
+    if expression_depth(pair.clone()) > max_depth {
+        return Err(nested_too_deeply_error(max_depth, pair.as_span()));
+    }
+    Ok(())
+}
+
 /// Parses text into expression tree. No name resolution is made at this stage.
 #[cfg(test)] // TODO: alias will be parsed with no bare_string fallback
 pub fn parse_program(text: &str) -> FilesetParseResult<ExpressionNode> {
-    let mut pairs = FilesetParser::parse(Rule::program, text)?;
+    check_parentheses_depth(text, DEFAULT_MAX_EXPRESSION_DEPTH)?;
+    let mut pairs = parse_rule(Rule::program, text)?;
     let first = pairs.next().unwrap();
+    check_expression_depth(&first, DEFAULT_MAX_EXPRESSION_DEPTH)?;
     parse_expression_node(first)
 }
 
@@ -301,11 +461,24 @@ pub fn parse_program(text: &str) -> FilesetParseResult<ExpressionNode> {
 /// If the text can't be parsed as a fileset expression, and if it doesn't
 /// contain any operator-like characters, it will be parsed as a file path.
 pub fn parse_program_or_bare_string(text: &str) -> FilesetParseResult<ExpressionNode> {
-    let mut pairs = FilesetParser::parse(Rule::program_or_bare_string, text)?;
+    parse_program_or_bare_string_with_max_depth(text, DEFAULT_MAX_EXPRESSION_DEPTH)
+}
+
+/// Like [`parse_program_or_bare_string()`], but with the specified limit of
+/// the depth of the expression tree.
+pub fn parse_program_or_bare_string_with_max_depth(
+    text: &str,
+    max_depth: usize,
+) -> FilesetParseResult<ExpressionNode> {
+    check_parentheses_depth(text, max_depth)?;
+    let mut pairs = parse_rule(Rule::program_or_bare_string, text)?;
     let first = pairs.next().unwrap();
     let span = first.as_span();
     let expr = match first.as_rule() {
-        Rule::expression => return parse_expression_node(first),
+        Rule::expression => {
+            check_expression_depth(&first, max_depth)?;
+            return parse_expression_node(first);
+        }
         Rule::bare_string_pattern => {
             let (lhs, op, rhs) = first.into_inner().collect_tuple().unwrap();
             assert_eq!(lhs.as_rule(), Rule::strict_identifier);
@@ -389,6 +562,10 @@ mod tests {
                 let rhs = Box::new(normalize_tree(*rhs));
                 ExpressionKind::Binary(op, lhs, rhs)
             }
+            ExpressionKind::UnionAll(nodes) => {
+                let nodes = normalize_list(nodes);
+                ExpressionKind::UnionAll(nodes)
+            }
             ExpressionKind::FunctionCall(function) => {
                 let function = Box::new(normalize_function_call(*function));
                 ExpressionKind::FunctionCall(function)
@@ -525,7 +702,11 @@ mod tests {
         );
         assert_matches!(
             parse_into_kind("x|y"),
-            Ok(ExpressionKind::Binary(BinaryOp::Union, _, _))
+            Ok(ExpressionKind::UnionAll(nodes)) if nodes.len() == 2
+        );
+        assert_matches!(
+            parse_into_kind("x|y|z"),
+            Ok(ExpressionKind::UnionAll(nodes)) if nodes.len() == 3
         );
         assert_matches!(
             parse_into_kind("x&y"),
@@ -631,7 +812,7 @@ mod tests {
         );
         assert_eq!(
             parse_maybe_bare_into_kind(r#"foo:"unclosed quote"#),
-            Err(FilesetParseErrorKind::SyntaxError)
+            Err(FilesetParseErrorKind::UnterminatedString)
         );
 
         // Surrounding spaces are simply preserved. They could be trimmed, but
@@ -653,4 +834,102 @@ mod tests {
           = expected `~` or <primary>
         "###);
     }
+
+    #[test]
+    fn test_parse_unterminated_string() {
+        assert_eq!(
+            parse_into_kind(r#"glob:"*.rs"#),
+            Err(FilesetParseErrorKind::UnterminatedString)
+        );
+        assert_eq!(
+            parse_maybe_bare_into_kind(r#"glob:"*.rs"#),
+            Err(FilesetParseErrorKind::UnterminatedString)
+        );
+        assert_eq!(
+            parse_maybe_bare_into_kind(r#"foo | 'bar"#),
+            Err(FilesetParseErrorKind::UnterminatedString)
+        );
+        // Escaped quote doesn't terminate the string
+        assert_eq!(
+            parse_into_kind(r#" "foo\" "#),
+            Err(FilesetParseErrorKind::UnterminatedString)
+        );
+        // Quotes of the other kind don't terminate the string
+        assert_eq!(
+            parse_into_kind(r#" 'foo" "#),
+            Err(FilesetParseErrorKind::UnterminatedString)
+        );
+        // Terminated strings are reported as other errors
+        assert_eq!(
+            parse_into_kind(r#" "foo" | "#),
+            Err(FilesetParseErrorKind::SyntaxError)
+        );
+
+        // The error points at the opening quote
+        insta::assert_snapshot!(
+            parse_program_or_bare_string(r#"glob:"*.rs"#).unwrap_err().to_string(), @r###"
+         --> 1:6
+          |
+        1 | glob:"*.rs
+          |      ^
+          |
+          = Unterminated string literal
+        "###);
+        insta::assert_snapshot!(
+            parse_program_or_bare_string(r#"foo | 'bar"#).unwrap_err().to_string(), @r###"
+         --> 1:7
+          |
+        1 | foo | 'bar
+          |       ^
+          |
+          = Unterminated string literal
+        "###);
+    }
+
+    #[test]
+    fn test_parse_too_deep() {
+        let too_deep = |text: &str| {
+            matches!(
+                parse_program(text).unwrap_err().kind,
+                FilesetParseErrorKind::Expression(message) if message.contains("too deeply")
+            )
+        };
+        let max_depth = DEFAULT_MAX_EXPRESSION_DEPTH;
+
+        // Pathologically nested expressions shouldn't overflow the stack
+        assert!(too_deep(&format!("{}x", "~".repeat(100_000))));
+        assert!(too_deep(&"a&".repeat(100_000)[..199_999]));
+        assert!(too_deep(&format!(
+            "{}x{}",
+            "(".repeat(100_000),
+            ")".repeat(100_000)
+        )));
+        assert!(too_deep(&format!(
+            "{}x{}",
+            "(~~~".repeat(100),
+            ")".repeat(100)
+        )));
+        assert!(too_deep(&format!("union(x, {}x)", "~".repeat(max_depth))));
+
+        // Expressions within the limit are accepted
+        assert!(parse_program(&format!("{}x", "~".repeat(max_depth - 1))).is_ok());
+        assert!(parse_program(&"a|".repeat(max_depth)[..max_depth * 2 - 1]).is_ok());
+        assert!(parse_program(&"a&".repeat(max_depth)[..max_depth * 2 - 1]).is_ok());
+
+        // Unions are flattened, so long chains of them aren't nested
+        assert!(parse_program(&"a|".repeat(100_000)[..199_999]).is_ok());
+        assert!(parse_program(&"a&b|".repeat(100_000)[..399_999]).is_ok());
+        assert!(too_deep(&format!("{}a|a", "a&".repeat(max_depth))));
+        assert!(parse_maybe_bare_into_kind(&format!("{}x", "~".repeat(max_depth))).is_err());
+
+        // Parentheses in string literals aren't counted
+        let quoted = format!("'{0}' | \"{0}\\\"{0}\"", "(".repeat(max_depth + 1));
+        assert!(parse_program(&quoted).is_ok());
+
+        // The limit can be configured
+        assert!(parse_program_or_bare_string_with_max_depth("~~x", 3).is_ok());
+        assert!(parse_program_or_bare_string_with_max_depth("~~x", 2).is_err());
+        assert!(parse_program_or_bare_string_with_max_depth("a | b", 1).is_err());
+        assert!(parse_program_or_bare_string_with_max_depth("a | b | c", 2).is_ok());
+    }
 }
@@ -1220,12 +1220,32 @@ pub struct GitFetchStats {
     pub import_stats: GitImportStats,
 }
 
+/// How to fetch tags from the remote.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GitFetchTags {
+    /// Fetch tags pointing to the fetched commits, and import all tags.
+    #[default]
+    Auto,
+    /// Don't fetch nor import any tags.
+    Skip,
+}
+
+impl GitFetchTags {
+    fn to_git2(self) -> git2::AutotagOption {
+        match self {
+            GitFetchTags::Auto => git2::AutotagOption::Unspecified,
+            GitFetchTags::Skip => git2::AutotagOption::None,
+        }
+    }
+}
+
 #[tracing::instrument(skip(mut_repo, git_repo, callbacks))]
 pub fn fetch(
     mut_repo: &mut MutableRepo,
     git_repo: &git2::Repository,
     remote_name: &str,
     branch_names: &[StringPattern],
+    tags: GitFetchTags,
     callbacks: RemoteCallbacks<'_>,
     git_settings: &GitSettings,
 ) -> Result<GitFetchStats, GitFetchError> {
@@ -1244,6 +1264,7 @@ pub fn fetch(
     fetch_options.proxy_options(proxy_options);
     let callbacks = callbacks.into_git();
     fetch_options.remote_callbacks(callbacks);
+    fetch_options.download_tags(tags.to_git2());
     // At this point, we are only updating Git's remote tracking branches, not the
     // local branches.
     let refspecs: Vec<_> = branch_names
@@ -1266,7 +1287,7 @@ pub fn fetch(
     tracing::debug!("remote.prune");
     remote.prune(None)?;
     tracing::debug!("remote.update_tips");
-    remote.update_tips(None, false, git2::AutotagOption::Unspecified, None)?;
+    remote.update_tips(None, false, tags.to_git2(), None)?;
     // TODO: We could make it optional to get the default branch since we only care
     // about it on clone.
     let mut default_branch = None;
@@ -1285,12 +1306,12 @@ pub fn fetch(
 
     // Import the remote-tracking branches into the jj repo and update jj's
     // local branches. We also import local tags since remote tags should have
-    // been merged by Git.
+    // been merged by Git, unless tags are skipped.
     tracing::debug!("import_refs");
     let import_stats = import_some_refs(mut_repo, git_settings, |ref_name| {
         to_remote_branch(ref_name, remote_name)
             .map(|branch| branch_names.iter().any(|pattern| pattern.matches(branch)))
-            .unwrap_or_else(|| matches!(ref_name, RefName::Tag(_)))
+            .unwrap_or_else(|| tags == GitFetchTags::Auto && matches!(ref_name, RefName::Tag(_)))
     })?;
     let stats = GitFetchStats {
         default_branch,
@@ -1310,14 +1331,27 @@ pub enum GitPushError {
     RemoteReservedForLocalGitRepo,
     #[error("Refs in unexpected location: {0:?}")]
     RefInUnexpectedLocation(Vec<String>),
-    #[error("Remote rejected the update of some refs (do you have permission to push to {0:?}?)")]
-    RefUpdateRejected(Vec<String>),
+    #[error(
+        "Remote rejected the update of some refs (do you have permission to push to {:?}?)",
+        .0.iter().map(|rejection| &rejection.qualified_name).collect_vec()
+    )]
+    RefUpdateRejected(Vec<GitRefUpdateRejection>),
     // TODO: I'm sure there are other errors possible, such as transport-level errors,
     // and errors caused by the remote rejecting the push.
     #[error("Unexpected git error when pushing")]
     InternalGitError(#[from] git2::Error),
 }
 
+/// A ref update the remote refused to apply.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GitRefUpdateRejection {
+    /// The fully-qualified name of the ref on the remote.
+    pub qualified_name: String,
+    /// The reason reported by the remote, e.g. "pre-receive hook declined".
+    /// `None` if the remote didn't report a status for the ref at all.
+    pub reason: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub struct GitBranchPushTargets {
     pub branch_updates: Vec<(String, BranchPushUpdate)>,
@@ -1430,6 +1464,7 @@ fn push_refs(
         .copied()
         .collect();
     let mut failed_push_negotiations = vec![];
+    let mut rejected_reasons: HashMap<String, String> = HashMap::new();
     let push_result = {
         let mut push_options = git2::PushOptions::new();
         let mut proxy_options = git2::ProxyOptions::new();
@@ -1441,6 +1476,9 @@ fn push_refs(
                 let dst_refname = update
                     .dst_refname()
                     .expect("Expect reference name to be valid UTF-8");
+                // `None` means the ref is expected to be absent on the remote.
+                // This serves as the lease for a brand-new ref, so there's no
+                // case where the lease base is unknown.
                 let expected_remote_location = *qualified_remote_refs_expected_locations
                     .get(dst_refname)
                     .expect("Push is trying to move a ref it wasn't asked to move");
@@ -1497,8 +1535,11 @@ fn push_refs(
             }
         });
         callbacks.push_update_reference(|refname, status| {
-            // The status is Some if the ref update was rejected
-            if status.is_none() {
+            // The status is Some if the ref update was rejected, and contains
+            // the reason given by the remote
+            if let Some(reason) = status {
+                rejected_reasons.insert(refname.to_owned(), reason.to_owned());
+            } else {
                 remaining_remote_refs.remove(refname);
             }
             Ok(())
@@ -1527,7 +1568,10 @@ fn push_refs(
                 remaining_remote_refs
                     .iter()
                     .sorted()
-                    .map(|name| name.to_string())
+                    .map(|name| GitRefUpdateRejection {
+                        qualified_name: name.to_string(),
+                        reason: rejected_reasons.remove(*name),
+                    })
                     .collect(),
             ))
         }
@@ -29,6 +29,7 @@ use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 use std::{fs, iter, mem, slice};
 
+use futures::io::AllowStdIo;
 use futures::StreamExt;
 use itertools::{EitherOrBoth, Itertools};
 use once_cell::unsync::OnceCell;
@@ -45,7 +46,9 @@ use crate::backend::{
     TreeValue,
 };
 use crate::commit::Commit;
-use crate::conflicts::{self, materialize_tree_value, MaterializedTreeValue};
+use crate::conflicts::{
+    self, materialize_tree_value_with_options, ConflictMaterializeOptions, MaterializedTreeValue,
+};
 use crate::file_util::{check_symlink_support, try_symlink};
 use crate::fsmonitor::FsmonitorSettings;
 #[cfg(feature = "watchman")]
@@ -64,8 +67,8 @@ use crate::settings::HumanByteSize;
 use crate::store::Store;
 use crate::tree::Tree;
 use crate::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError, SnapshotOptions,
-    SnapshotProgress, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError,
+    SnapshotOptions, SnapshotProgress, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
 };
 
 #[cfg(unix)]
@@ -1163,15 +1166,15 @@ impl TreeState {
             };
             Ok(Merge::normal(TreeValue::File { id, executable }))
         } else if let Some(old_file_ids) = current_tree_values.to_file_merge() {
-            let content = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+            let file = File::open(disk_path).map_err(|err| SnapshotError::Other {
                 message: format!("Failed to open file {}", disk_path.display()),
                 err: err.into(),
             })?;
-            let new_file_ids = conflicts::update_from_content(
+            let new_file_ids = conflicts::update_from_content_reader(
                 &old_file_ids,
                 self.store.as_ref(),
                 repo_path,
-                &content,
+                &mut AllowStdIo::new(file),
             )
             .block_on()?;
             match new_file_ids.into_resolved() {
@@ -1287,7 +1290,11 @@ impl TreeState {
         Ok(())
     }
 
-    pub fn check_out(&mut self, new_tree: &MergedTree) -> Result<CheckoutStats, CheckoutError> {
+    pub fn check_out(
+        &mut self,
+        new_tree: &MergedTree,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         let old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
                 source: Box::new(err),
@@ -1295,7 +1302,7 @@ impl TreeState {
             other => CheckoutError::InternalBackendError(other),
         })?;
         let stats = self
-            .update(&old_tree, new_tree, self.sparse_matcher().as_ref())
+            .update(&old_tree, new_tree, self.sparse_matcher().as_ref(), options)
             .block_on()?;
         self.tree_id = new_tree.id();
         Ok(stats)
@@ -1316,9 +1323,12 @@ impl TreeState {
         let added_matcher = DifferenceMatcher::new(&new_matcher, &old_matcher);
         let removed_matcher = DifferenceMatcher::new(&old_matcher, &new_matcher);
         let empty_tree = MergedTree::resolved(Tree::null(self.store.clone(), RepoPathBuf::root()));
-        let added_stats = self.update(&empty_tree, &tree, &added_matcher).block_on()?;
+        let options = CheckoutOptions::default();
+        let added_stats = self
+            .update(&empty_tree, &tree, &added_matcher, &options)
+            .block_on()?;
         let removed_stats = self
-            .update(&tree, &empty_tree, &removed_matcher)
+            .update(&tree, &empty_tree, &removed_matcher, &options)
             .block_on()?;
         self.sparse_patterns = sparse_patterns;
         assert_eq!(added_stats.updated_files, 0);
@@ -1339,6 +1349,7 @@ impl TreeState {
         old_tree: &MergedTree,
         new_tree: &MergedTree,
         matcher: &dyn Matcher,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: maybe it's better not include the skipped counts in the "intended"
         // counts
@@ -1348,6 +1359,10 @@ impl TreeState {
             removed_files: 0,
             skipped_files: 0,
         };
+        let materialize_options = ConflictMaterializeOptions {
+            marker_style: options.conflict_marker_style,
+            ..Default::default()
+        };
         let mut changed_file_states = Vec::new();
         let mut deleted_files = HashSet::new();
         let mut diff_stream = Box::pin(
@@ -1356,7 +1371,13 @@ impl TreeState {
                 .map(|(path, diff)| async {
                     match diff {
                         Ok((before, after)) => {
-                            let result = materialize_tree_value(&self.store, &path, after).await;
+                            let result = materialize_tree_value_with_options(
+                                &self.store,
+                                &path,
+                                after,
+                                &materialize_options,
+                            )
+                            .await;
                             (path, result.map(|value| (before.is_present(), value)))
                         }
                         Err(err) => (path, Err(err)),
@@ -1801,7 +1822,11 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(tree_state.current_tree_id().clone())
     }
 
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError> {
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: Write a "pending_checkout" file with the new TreeId so we can
         // continue an interrupted update if we find such a file.
         let new_tree = commit.tree()?;
@@ -1812,7 +1837,7 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
                 message: "Failed to load the working copy state".to_string(),
                 err: err.into(),
             })?
-            .check_out(&new_tree)?;
+            .check_out(&new_tree, options)?;
         self.tree_state_dirty = true;
         Ok(stats)
     }
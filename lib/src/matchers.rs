@@ -173,6 +173,13 @@ fn files_tree_to_visit_sets(tree: &RepoPathTree<FilesNodeKind>) -> Visit {
     Visit::sets(dirs, files)
 }
 
+/// Matches file paths at or under the given prefixes.
+///
+/// A prefix matches both a file and a directory of that name. Tree walks can
+/// list directory entries by checking that `visit()` of the directory isn't
+/// `Visit::Nothing`: the prefix directory itself and all directories below it
+/// are visited with `Visit::AllRecursively`, and its ancestors are visited with
+/// a set that includes the next path component.
 #[derive(Debug)]
 pub struct PrefixMatcher {
     tree: RepoPathTree<PrefixNodeKind>,
@@ -449,6 +456,59 @@ impl<M1: Matcher, M2: Matcher> Matcher for IntersectionMatcher<M1, M2> {
     }
 }
 
+/// Combines all `matchers` as union.
+///
+/// The matchers are combined as a balanced tree to minimize the recursion
+/// depth. If `matchers` is empty, the result matches nothing.
+pub fn union_all_matchers(
+    matchers: impl IntoIterator<Item = Box<dyn Matcher>>,
+) -> Box<dyn Matcher> {
+    let mut matchers = matchers.into_iter().map(Some).collect_vec();
+    combine_all_matchers(&mut matchers, &|| Box::new(NothingMatcher), &|m1, m2| {
+        Box::new(UnionMatcher::new(m1, m2))
+    })
+}
+
+/// Combines all `matchers` as intersection.
+///
+/// The matchers are combined as a balanced tree to minimize the recursion
+/// depth. If `matchers` is empty, the result matches everything.
+pub fn intersection_all_matchers(
+    matchers: impl IntoIterator<Item = Box<dyn Matcher>>,
+) -> Box<dyn Matcher> {
+    let mut matchers = matchers.into_iter().map(Some).collect_vec();
+    combine_all_matchers(&mut matchers, &|| Box::new(EverythingMatcher), &|m1, m2| {
+        Box::new(IntersectionMatcher::new(m1, m2))
+    })
+}
+
+/// Matches paths that are matched by `wanted` but not by `unwanted`.
+pub fn difference_matcher(
+    wanted: Box<dyn Matcher>,
+    unwanted: Box<dyn Matcher>,
+) -> Box<dyn Matcher> {
+    Box::new(DifferenceMatcher::new(wanted, unwanted))
+}
+
+/// Each matcher element must be wrapped in `Some` so the matchers can be moved
+/// in arbitrary order.
+fn combine_all_matchers(
+    matchers: &mut [Option<Box<dyn Matcher>>],
+    empty: &dyn Fn() -> Box<dyn Matcher>,
+    combine: &dyn Fn(Box<dyn Matcher>, Box<dyn Matcher>) -> Box<dyn Matcher>,
+) -> Box<dyn Matcher> {
+    match matchers {
+        [] => empty(),
+        [matcher] => matcher.take().expect("matcher should still be available"),
+        _ => {
+            let (left, right) = matchers.split_at_mut(matchers.len() / 2);
+            let m1 = combine_all_matchers(left, empty, combine);
+            let m2 = combine_all_matchers(right, empty, combine);
+            combine(m1, m2)
+        }
+    }
+}
+
 /// Tree that maps `RepoPath` to value of type `V`.
 #[derive(Clone, Default, Eq, PartialEq)]
 struct RepoPathTree<V> {
@@ -637,6 +697,36 @@ mod tests {
         assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
     }
 
+    #[test]
+    fn test_prefixmatcher_directory_entries() {
+        let m = PrefixMatcher::new([repo_path("foo/bar")]);
+
+        // Directories a tree walk should descend into to list the entries at
+        // and below the prefix
+        let visited_dirs = [
+            "foo",
+            "foo/bar",
+            "foo/bar/baz",
+            "foo/bar/baz/qux",
+            "foo/baz",
+            "bar",
+            "bar/foo/bar",
+        ]
+        .into_iter()
+        .filter(|dir| !m.visit(repo_path(dir)).is_nothing())
+        .collect_vec();
+        assert_eq!(
+            visited_dirs,
+            ["foo", "foo/bar", "foo/bar/baz", "foo/bar/baz/qux"]
+        );
+
+        // Directory entries at and below the prefix match, but the ancestor
+        // directory doesn't
+        assert!(m.matches(repo_path("foo/bar")));
+        assert!(m.matches(repo_path("foo/bar/baz")));
+        assert!(!m.matches(repo_path("foo")));
+    }
+
     #[test]
     fn test_prefixmatcher_nested_prefixes() {
         let m = PrefixMatcher::new([repo_path("foo"), repo_path("foo/bar/baz")]);
@@ -1036,4 +1126,61 @@ mod tests {
         );
         assert_eq!(m.visit(repo_path("foo/bar")), Visit::AllRecursively);
     }
+
+    #[test]
+    fn test_union_all_matchers() {
+        let m = union_all_matchers([]);
+        assert_eq!(format!("{m:?}"), format!("{:?}", NothingMatcher));
+
+        let m = union_all_matchers([
+            Box::new(FilesMatcher::new([repo_path("a")])) as Box<dyn Matcher>,
+            Box::new(PrefixMatcher::new([repo_path("b")])),
+            Box::new(FilesMatcher::new([repo_path("c")])),
+        ]);
+        let expected = UnionMatcher::new(
+            Box::new(FilesMatcher::new([repo_path("a")])) as Box<dyn Matcher>,
+            Box::new(UnionMatcher::new(
+                Box::new(PrefixMatcher::new([repo_path("b")])) as Box<dyn Matcher>,
+                Box::new(FilesMatcher::new([repo_path("c")])) as Box<dyn Matcher>,
+            )) as Box<dyn Matcher>,
+        );
+        assert_eq!(format!("{m:?}"), format!("{expected:?}"));
+        assert!(m.matches(repo_path("a")));
+        assert!(m.matches(repo_path("b/d")));
+        assert!(m.matches(repo_path("c")));
+        assert!(!m.matches(repo_path("c/d")));
+    }
+
+    #[test]
+    fn test_intersection_all_matchers() {
+        let m = intersection_all_matchers([]);
+        assert_eq!(format!("{m:?}"), format!("{:?}", EverythingMatcher));
+
+        let m = intersection_all_matchers([
+            Box::new(PrefixMatcher::new([repo_path("a")])) as Box<dyn Matcher>,
+            Box::new(PrefixMatcher::new([repo_path("a/b")])),
+        ]);
+        let expected = IntersectionMatcher::new(
+            Box::new(PrefixMatcher::new([repo_path("a")])) as Box<dyn Matcher>,
+            Box::new(PrefixMatcher::new([repo_path("a/b")])) as Box<dyn Matcher>,
+        );
+        assert_eq!(format!("{m:?}"), format!("{expected:?}"));
+        assert!(!m.matches(repo_path("a/c")));
+        assert!(m.matches(repo_path("a/b/c")));
+    }
+
+    #[test]
+    fn test_difference_matcher() {
+        let m = difference_matcher(
+            Box::new(PrefixMatcher::new([repo_path("a")])),
+            Box::new(FilesMatcher::new([repo_path("a/b")])),
+        );
+        let expected = DifferenceMatcher::new(
+            Box::new(PrefixMatcher::new([repo_path("a")])) as Box<dyn Matcher>,
+            Box::new(FilesMatcher::new([repo_path("a/b")])) as Box<dyn Matcher>,
+        );
+        assert_eq!(format!("{m:?}"), format!("{expected:?}"));
+        assert!(m.matches(repo_path("a/c")));
+        assert!(!m.matches(repo_path("a/b")));
+    }
 }
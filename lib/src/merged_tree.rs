@@ -1186,14 +1186,7 @@ impl MergedTreeBuilder {
 
     /// Create new tree(s) from the base tree(s) and overrides.
     pub fn write_tree(self, store: &Arc<Store>) -> BackendResult<MergedTreeId> {
-        let base_tree_ids = match self.base_tree_id.clone() {
-            MergedTreeId::Legacy(base_tree_id) => {
-                let legacy_base_tree = store.get_tree(RepoPath::root(), &base_tree_id)?;
-                let base_tree = MergedTree::from_legacy_tree(legacy_base_tree)?;
-                base_tree.id().to_merge()
-            }
-            MergedTreeId::Merge(base_tree_ids) => base_tree_ids,
-        };
+        let base_tree_ids = self.base_tree_ids(store)?;
         let new_tree_ids = self.write_merged_trees(base_tree_ids, store)?;
         match new_tree_ids.simplify().into_resolved() {
             Ok(single_tree_id) => Ok(MergedTreeId::resolved(single_tree_id)),
@@ -1205,6 +1198,27 @@ impl MergedTreeBuilder {
         }
     }
 
+    /// Create new tree(s) from the base tree(s) and overrides, without
+    /// simplifying or resolving the result. Unlike `write_tree()`, this
+    /// preserves the number of sides of the existing conflicts even if some of
+    /// the overrides cancel each other out.
+    pub fn write_tree_unsimplified(self, store: &Arc<Store>) -> BackendResult<MergedTreeId> {
+        let base_tree_ids = self.base_tree_ids(store)?;
+        let new_tree_ids = self.write_merged_trees(base_tree_ids, store)?;
+        Ok(MergedTreeId::Merge(new_tree_ids))
+    }
+
+    fn base_tree_ids(&self, store: &Arc<Store>) -> BackendResult<Merge<TreeId>> {
+        match self.base_tree_id.clone() {
+            MergedTreeId::Legacy(base_tree_id) => {
+                let legacy_base_tree = store.get_tree(RepoPath::root(), &base_tree_id)?;
+                let base_tree = MergedTree::from_legacy_tree(legacy_base_tree)?;
+                Ok(base_tree.id().to_merge())
+            }
+            MergedTreeId::Merge(base_tree_ids) => Ok(base_tree_ids),
+        }
+    }
+
     fn write_merged_trees(
         self,
         mut base_tree_ids: Merge<TreeId>,
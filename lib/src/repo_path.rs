@@ -438,6 +438,24 @@ impl PartialOrd for RepoPathBuf {
     }
 }
 
+impl serde::Serialize for RepoPathBuf {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for RepoPathBuf {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        if !is_valid_repo_path_str(&value) {
+            return Err(serde::de::Error::custom(format!(
+                "Invalid repo path: {value:?}"
+            )));
+        }
+        Ok(RepoPathBuf { value })
+    }
+}
+
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
 pub enum RelativePathParseError {
     #[error(r#"Invalid component "{component}" in repo-relative path "{path}""#)]
@@ -119,6 +119,32 @@ pub trait RevsetFilterExtension: std::fmt::Debug + Any {
 
     /// Returns true iff this filter matches the specified commit.
     fn matches_commit(&self, commit: &Commit) -> bool;
+
+    /// Returns a key identifying this filter, if any.
+    ///
+    /// Filters of the same type with the same key are considered equivalent,
+    /// which allows the optimizer to eliminate duplicated filters. Filters
+    /// without a key are only considered equivalent to the same instance.
+    fn dedup_key(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Returns true if the extension filters `a` and `b` are known to be
+/// equivalent.
+fn is_same_filter_extension(
+    a: &Rc<dyn RevsetFilterExtension>,
+    b: &Rc<dyn RevsetFilterExtension>,
+) -> bool {
+    if Rc::ptr_eq(a, b) {
+        return true;
+    }
+    match (a.dedup_key(), b.dedup_key()) {
+        (Some(key_a), Some(key_b)) => {
+            key_a == key_b && a.as_any().type_id() == b.as_any().type_id()
+        }
+        _ => false,
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1101,11 +1127,22 @@ fn internalize_filter(expression: &Rc<RevsetExpression>) -> TransformedExpressio
     })
 }
 
-/// Eliminates redundant nodes like `x & all()`, `~~x`.
+/// Eliminates redundant nodes like `x & all()`, `~~x`, and intersection or
+/// union of the same extension filters.
 ///
 /// This does not rewrite 'x & none()' to 'none()' because 'x' may be an invalid
 /// symbol.
 fn fold_redundant_expression(expression: &Rc<RevsetExpression>) -> TransformedExpression {
+    fn is_same_extension(expression1: &RevsetExpression, expression2: &RevsetExpression) -> bool {
+        match (expression1, expression2) {
+            (
+                RevsetExpression::Filter(RevsetFilterPredicate::Extension(ext1)),
+                RevsetExpression::Filter(RevsetFilterPredicate::Extension(ext2)),
+            ) => is_same_filter_extension(ext1, ext2),
+            _ => false,
+        }
+    }
+
     transform_expression_bottom_up(expression, |expression| match expression.as_ref() {
         RevsetExpression::NotIn(outer) => match outer.as_ref() {
             RevsetExpression::NotIn(inner) => Some(inner.clone()),
@@ -1115,13 +1152,62 @@ fn fold_redundant_expression(expression: &Rc<RevsetExpression>) -> TransformedEx
             match (expression1.as_ref(), expression2.as_ref()) {
                 (_, RevsetExpression::All) => Some(expression1.clone()),
                 (RevsetExpression::All, _) => Some(expression2.clone()),
+                (expr1, expr2) if is_same_extension(expr1, expr2) => Some(expression1.clone()),
                 _ => None,
             }
         }
+        RevsetExpression::Union(expression1, expression2)
+            if is_same_extension(expression1, expression2) =>
+        {
+            Some(expression1.clone())
+        }
         _ => None,
     })
 }
 
+/// Folds set operations whose operands are the same node, such as `x & x`
+/// built programmatically from a shared subexpression.
+///
+/// Operands are compared by identity, so this pass has to run before the
+/// other passes recreate the shared subtrees. `x ~ x` isn't rewritten to
+/// `none()` if `x` contains symbols because they may be invalid.
+fn fold_identical_operands(expression: &Rc<RevsetExpression>) -> TransformedExpression {
+    fn contains_commit_ref(expression: &Rc<RevsetExpression>) -> bool {
+        try_transform_expression(
+            expression,
+            |expression| match expression.as_ref() {
+                RevsetExpression::CommitRef(_) => Err(()),
+                _ => Ok(None),
+            },
+            |_| Ok(None),
+        )
+        .is_err()
+    }
+
+    try_transform_expression::<Infallible>(
+        expression,
+        |expression| {
+            Ok(match expression.as_ref() {
+                RevsetExpression::Union(expression1, expression2)
+                | RevsetExpression::Intersection(expression1, expression2)
+                    if Rc::ptr_eq(expression1, expression2) =>
+                {
+                    Some(fold_identical_operands(expression1).unwrap_or(expression1.clone()))
+                }
+                RevsetExpression::Difference(expression1, expression2)
+                    if Rc::ptr_eq(expression1, expression2)
+                        && !contains_commit_ref(expression1) =>
+                {
+                    Some(RevsetExpression::none())
+                }
+                _ => None,
+            })
+        },
+        |_| Ok(None),
+    )
+    .unwrap()
+}
+
 fn to_difference_range(
     expression: &Rc<RevsetExpression>,
     complement: &Rc<RevsetExpression>,
@@ -1288,6 +1374,7 @@ fn fold_generation(expression: &Rc<RevsetExpression>) -> TransformedExpression {
 /// Rewrites the given `expression` tree to reduce evaluation cost. Returns new
 /// tree.
 pub fn optimize(expression: Rc<RevsetExpression>) -> Rc<RevsetExpression> {
+    let expression = fold_identical_operands(&expression).unwrap_or(expression);
     let expression = unfold_difference(&expression).unwrap_or(expression);
     let expression = fold_redundant_expression(&expression).unwrap_or(expression);
     let expression = fold_generation(&expression).unwrap_or(expression);
@@ -2815,6 +2902,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_optimize_identical_operands() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+
+        let symbol = RevsetExpression::symbol("foo".to_owned());
+        insta::assert_debug_snapshot!(
+            optimize(symbol.intersection(&symbol)),
+            @r###"CommitRef(Symbol("foo"))"###);
+        insta::assert_debug_snapshot!(
+            optimize(symbol.union(&symbol)),
+            @r###"CommitRef(Symbol("foo"))"###);
+        // Symbol might be invalid, so it has to be kept.
+        insta::assert_debug_snapshot!(optimize(symbol.minus(&symbol)), @r###"
+        Difference(
+            CommitRef(Symbol("foo")),
+            CommitRef(Symbol("foo")),
+        )
+        "###);
+
+        let commits = RevsetExpression::commits(vec![]).ancestors();
+        insta::assert_debug_snapshot!(optimize(commits.minus(&commits)), @r###"
+        None
+        "###);
+        // Nested identical operands should be folded as well.
+        let nested = commits.intersection(&commits);
+        insta::assert_debug_snapshot!(optimize(nested.union(&nested)), @r###"
+        Ancestors {
+            heads: Commits([]),
+            generation: 0..18446744073709551615,
+        }
+        "###);
+
+        // Filter within the operand
+        let filter = RevsetExpression::filter(RevsetFilterPredicate::HasConflict);
+        let filtered = commits.intersection(&filter);
+        insta::assert_debug_snapshot!(optimize(filtered.intersection(&filtered)), @r###"
+        Intersection(
+            Ancestors {
+                heads: Commits([]),
+                generation: 0..18446744073709551615,
+            },
+            Filter(HasConflict),
+        )
+        "###);
+        insta::assert_debug_snapshot!(optimize(filtered.minus(&filtered)), @r###"
+        None
+        "###);
+
+        // Structurally equal, but not the same node.
+        insta::assert_debug_snapshot!(optimize(parse("foo & foo").unwrap()), @r###"
+        Intersection(
+            CommitRef(Symbol("foo")),
+            CommitRef(Symbol("foo")),
+        )
+        "###);
+    }
+
     #[test]
     fn test_optimize_difference() {
         let settings = insta_settings();
@@ -3212,6 +3357,75 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_optimize_filter_extension_dedup() {
+        #[derive(Debug)]
+        struct DedupFilter(u64);
+
+        impl RevsetFilterExtension for DedupFilter {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn matches_commit(&self, _commit: &Commit) -> bool {
+                true
+            }
+
+            fn dedup_key(&self) -> Option<u64> {
+                Some(self.0)
+            }
+        }
+
+        #[derive(Debug)]
+        struct OpaqueFilter;
+
+        impl RevsetFilterExtension for OpaqueFilter {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn matches_commit(&self, _commit: &Commit) -> bool {
+                true
+            }
+        }
+
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let ext = |filter: Rc<dyn RevsetFilterExtension>| {
+            RevsetExpression::filter(RevsetFilterPredicate::Extension(filter))
+        };
+
+        // Filters with the same key are deduplicated
+        insta::assert_debug_snapshot!(
+            optimize(ext(Rc::new(DedupFilter(1))).intersection(&ext(Rc::new(DedupFilter(1))))),
+            @r###"Filter(Extension(DedupFilter(1)))"###);
+        insta::assert_debug_snapshot!(
+            optimize(ext(Rc::new(DedupFilter(1))).union(&ext(Rc::new(DedupFilter(1))))),
+            @r###"Filter(Extension(DedupFilter(1)))"###);
+        // Filters with different keys are not
+        insta::assert_debug_snapshot!(
+            optimize(ext(Rc::new(DedupFilter(1))).intersection(&ext(Rc::new(DedupFilter(2))))),
+            @r###"
+        Intersection(
+            Filter(Extension(DedupFilter(1))),
+            Filter(Extension(DedupFilter(2))),
+        )
+        "###);
+        // Filters without keys are only deduplicated if they're the same instance
+        insta::assert_debug_snapshot!(
+            optimize(ext(Rc::new(OpaqueFilter)).intersection(&ext(Rc::new(OpaqueFilter)))),
+            @r###"
+        Intersection(
+            Filter(Extension(OpaqueFilter)),
+            Filter(Extension(OpaqueFilter)),
+        )
+        "###);
+        let opaque: Rc<dyn RevsetFilterExtension> = Rc::new(OpaqueFilter);
+        insta::assert_debug_snapshot!(
+            optimize(ext(opaque.clone()).intersection(&ext(opaque))),
+            @r###"Filter(Extension(OpaqueFilter))"###);
+    }
+
     #[test]
     fn test_optimize_filter_subtree() {
         let settings = insta_settings();
@@ -24,6 +24,7 @@ use thiserror::Error;
 
 use crate::backend::{BackendError, MergedTreeId};
 use crate::commit::Commit;
+use crate::conflicts::ConflictMarkerStyle;
 use crate::fsmonitor::FsmonitorSettings;
 use crate::gitignore::{GitIgnoreError, GitIgnoreFile};
 use crate::op_store::{OperationId, WorkspaceId};
@@ -102,7 +103,11 @@ pub trait LockedWorkingCopy {
     fn snapshot(&mut self, options: SnapshotOptions) -> Result<MergedTreeId, SnapshotError>;
 
     /// Check out the specified commit in the working copy.
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError>;
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError>;
 
     /// Update to another commit without touching the files in the working copy.
     fn reset(&mut self, commit: &Commit) -> Result<(), ResetError>;
@@ -212,6 +217,14 @@ impl SnapshotOptions<'_> {
     }
 }
 
+/// Options used when checking out a commit in the working copy. Some of them
+/// may be ignored by some `WorkingCopy` implementations.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CheckoutOptions {
+    /// The style of the conflict markers to write to conflicted files.
+    pub conflict_marker_style: ConflictMarkerStyle,
+}
+
 /// A callback for getting progress updates.
 pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 
@@ -38,8 +38,8 @@ use crate::settings::UserSettings;
 use crate::signing::{SignInitError, Signer};
 use crate::store::Store;
 use crate::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, WorkingCopy, WorkingCopyFactory,
-    WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, WorkingCopy,
+    WorkingCopyFactory, WorkingCopyStateError,
 };
 
 #[derive(Error, Debug)]
@@ -387,6 +387,7 @@ impl Workspace {
         operation_id: OperationId,
         old_tree_id: Option<&MergedTreeId>,
         commit: &Commit,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         let mut locked_ws =
             self.start_working_copy_mutation()
@@ -403,7 +404,7 @@ impl Workspace {
                 return Err(CheckoutError::ConcurrentCheckout);
             }
         }
-        let stats = locked_ws.locked_wc().check_out(commit)?;
+        let stats = locked_ws.locked_wc().check_out(commit, options)?;
         locked_ws
             .finish(operation_id)
             .map_err(|err| CheckoutError::Other {
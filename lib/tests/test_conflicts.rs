@@ -12,16 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::iter::zip;
+
+use assert_matches::assert_matches;
+use futures::TryStreamExt as _;
 use indoc::indoc;
+use itertools::Itertools;
 use jj_lib::backend::FileId;
 use jj_lib::conflicts::{
-    extract_as_single_hunk, materialize_merge_result, parse_conflict, update_from_content,
+    check_conflict_markers, choose_materialized_conflict_marker_len, extract_as_single_hunk,
+    materialize_merge_result, materialize_merge_result_async, materialize_merge_result_bounded,
+    materialize_merge_result_with_options, parse_conflict, parse_conflict_explicit,
+    parse_conflict_with_marker_len, update_from_content, update_from_content_reader,
+    update_from_content_with_errors, ConflictHunk, ConflictLineEnding, ConflictMarkerStyle,
+    ConflictMaterializeOptions, ConflictParseError, MIN_CONFLICT_MARKER_LEN,
 };
+use jj_lib::files;
+use jj_lib::files::{ContentHunk, MergeResult};
 use jj_lib::merge::Merge;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::store::Store;
 use pollster::FutureExt;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use testutils::TestRepo;
 
 #[test]
@@ -490,6 +504,400 @@ fn test_materialize_conflict_two_forward_diffs() {
     );
 }
 
+#[test]
+fn test_materialize_merge_result_async() {
+    let options = RandomConflictOptions {
+        max_sides: 3,
+        max_lines: 8,
+        missing_eol: true,
+        marker_like_lines: false,
+    };
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let merge = random_merge(&mut rng, &options);
+        for materialize_options in [
+            ConflictMaterializeOptions::default(),
+            ConflictMaterializeOptions {
+                added_line_ending: ConflictLineEnding::Crlf,
+                marker_style: ConflictMarkerStyle::Git,
+                conflict_hint: true,
+                ..Default::default()
+            },
+        ] {
+            let mut expected = vec![];
+            materialize_merge_result_with_options(&merge, &materialize_options, &mut expected)
+                .unwrap();
+            let mut actual = vec![];
+            materialize_merge_result_async(&merge, &materialize_options, &mut actual)
+                .block_on()
+                .unwrap();
+            assert_eq!(
+                String::from_utf8_lossy(&actual),
+                String::from_utf8_lossy(&expected)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_materialize_conflict_annotated() {
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"a\n".to_vec()), ContentHunk(b"b\n".to_vec())],
+        vec![
+            ContentHunk(b"a\nleft\n".to_vec()),
+            ContentHunk(b"right\n".to_vec()),
+            ContentHunk(b"b\n".to_vec()),
+        ],
+    );
+    let options = ConflictMaterializeOptions {
+        annotate_terms: true,
+        ..Default::default()
+    };
+    let mut annotated = vec![];
+    materialize_merge_result_with_options(&merge, &options, &mut annotated).unwrap();
+    insta::assert_snapshot!(String::from_utf8(annotated.clone()).unwrap(), @r###"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base #1 to side #1 [jj:diff from=1 to=1]
+     a
+    +left
+    +++++++ Contents of side #2 [jj:side=2]
+    right
+    %%%%%%% Changes from base #2 to side #3 [jj:diff from=2 to=3]
+     b
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+
+    // The annotations are ignored when parsing
+    let mut plain = vec![];
+    materialize_merge_result(&merge, &mut plain).unwrap();
+    let parsed = parse_conflict(&annotated, merge.num_sides());
+    assert!(parsed.is_some());
+    assert_eq!(parsed, parse_conflict(&plain, merge.num_sides()));
+
+    // Bases use the same numbering in snapshot markers
+    let snapshot_options = ConflictMaterializeOptions {
+        annotate_terms: true,
+        marker_style: ConflictMarkerStyle::Snapshot,
+        ..Default::default()
+    };
+    let mut annotated = vec![];
+    materialize_merge_result_with_options(&merge, &snapshot_options, &mut annotated).unwrap();
+    insta::assert_snapshot!(String::from_utf8(annotated).unwrap(), @r###"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1 [jj:side=1]
+    a
+    left
+    ------- Contents of base #1 [jj:base=1]
+    a
+    +++++++ Contents of side #2 [jj:side=2]
+    right
+    ------- Contents of base #2 [jj:base=2]
+    b
+    +++++++ Contents of side #3 [jj:side=3]
+    b
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+
+    // Bases are numbered the same way if there's only one of them
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"a\n".to_vec())],
+        vec![
+            ContentHunk(b"a\nleft\n".to_vec()),
+            ContentHunk(b"right\n".to_vec()),
+        ],
+    );
+    let mut annotated = vec![];
+    materialize_merge_result_with_options(&merge, &options, &mut annotated).unwrap();
+    insta::assert_snapshot!(String::from_utf8(annotated).unwrap(), @r###"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1 [jj:diff from=1 to=1]
+     a
+    +left
+    +++++++ Contents of side #2 [jj:side=2]
+    right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    let mut annotated = vec![];
+    materialize_merge_result_with_options(&merge, &snapshot_options, &mut annotated).unwrap();
+    insta::assert_snapshot!(String::from_utf8(annotated).unwrap(), @r###"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1 [jj:side=1]
+    a
+    left
+    ------- Contents of base [jj:base=1]
+    a
+    +++++++ Contents of side #2 [jj:side=2]
+    right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+}
+
+#[test]
+fn test_materialize_conflict_crlf_missing_eol() {
+    // The left side lacks a newline at the end of the file
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\r\nline 2\r\n".to_vec())],
+        vec![
+            ContentHunk(b"line 1\r\nleft".to_vec()),
+            ContentHunk(b"line 1\r\nright\r\n".to_vec()),
+        ],
+    );
+    let materialize = |added_line_ending| {
+        let options = ConflictMaterializeOptions {
+            added_line_ending,
+            ..Default::default()
+        };
+        let mut output = vec![];
+        materialize_merge_result_with_options(&merge, &options, &mut output).unwrap();
+        output
+    };
+
+    // By default, a bare "\n" is added
+    let materialized = materialize(ConflictLineEnding::Lf);
+    insta::assert_snapshot!(
+        String::from_utf8(materialized.clone()).unwrap().replace('\r', "\\r"),
+        @r###"
+    line 1\r
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -line 2\r
+    +left
+    +++++++ Contents of side #2
+    right\r
+    >>>>>>> Conflict 1 of 1 ends
+    "###
+    );
+
+    // The line ending can be detected from the terms
+    let materialized = materialize(ConflictLineEnding::Detect);
+    assert_eq!(materialized, materialize(ConflictLineEnding::Crlf));
+    insta::assert_snapshot!(
+        String::from_utf8(materialized.clone()).unwrap().replace('\r', "\\r"),
+        @r###"
+    line 1\r
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -line 2\r
+    +left\r
+    +++++++ Contents of side #2
+    right\r
+    >>>>>>> Conflict 1 of 1 ends
+    "###
+    );
+    // The added line ending is kept when parsing the conflict back
+    insta::assert_debug_snapshot!(parse_conflict(&materialized, merge.num_sides()), @r###"
+    Some(
+        [
+            Resolved(
+                "line 1\r\n",
+            ),
+            Conflicted(
+                [
+                    "left\r\n",
+                    "line 2\r\n",
+                    "right\r\n",
+                ],
+            ),
+        ],
+    )
+    "###);
+
+    // Files with mostly "\n" line endings get a "\n" when detecting
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\nline 2\r\nline 3\n".to_vec())],
+        vec![
+            ContentHunk(b"left".to_vec()),
+            ContentHunk(b"right\n".to_vec()),
+        ],
+    );
+    let options = ConflictMaterializeOptions {
+        added_line_ending: ConflictLineEnding::Detect,
+        ..Default::default()
+    };
+    let mut detected = vec![];
+    materialize_merge_result_with_options(&merge, &options, &mut detected).unwrap();
+    let mut default = vec![];
+    materialize_merge_result(&merge, &mut default).unwrap();
+    assert_eq!(detected, default);
+}
+
+#[test]
+fn test_materialize_conflict_marker_styles() {
+    let materialize = |merge: &Merge<ContentHunk>, marker_style| {
+        let options = ConflictMaterializeOptions {
+            marker_style,
+            ..Default::default()
+        };
+        let mut output = vec![];
+        materialize_merge_result_with_options(merge, &options, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\nline 2\n".to_vec())],
+        vec![
+            ContentHunk(b"line 1\nleft\n".to_vec()),
+            ContentHunk(b"line 1\nright\n".to_vec()),
+        ],
+    );
+    insta::assert_snapshot!(materialize(&merge, ConflictMarkerStyle::Snapshot), @r###"
+    line 1
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1
+    left
+    ------- Contents of base
+    line 2
+    +++++++ Contents of side #2
+    right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    insta::assert_snapshot!(materialize(&merge, ConflictMarkerStyle::BaseOurs), @r###"
+    line 1
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -line 2
+    +left
+    +++++++ Contents of side #2
+    right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    insta::assert_snapshot!(materialize(&merge, ConflictMarkerStyle::Git), @r###"
+    line 1
+    <<<<<<< Side #1 (Conflict 1 of 1)
+    left
+    ||||||| Base
+    line 2
+    =======
+    right
+    >>>>>>> Side #2 (Conflict 1 of 1 ends)
+    "###);
+
+    // Snapshot style conflicts can be parsed back
+    let parsed = parse_conflict(
+        materialize(&merge, ConflictMarkerStyle::Snapshot).as_bytes(),
+        merge.num_sides(),
+    );
+    assert!(parsed.is_some());
+    assert_eq!(
+        parsed,
+        parse_conflict(
+            materialize(&merge, ConflictMarkerStyle::Diff).as_bytes(),
+            merge.num_sides()
+        )
+    );
+
+    // The base is diffed against the first side even if the second side is more
+    // similar to it
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"a\nb\nc\n".to_vec())],
+        vec![
+            ContentHunk(b"left\n".to_vec()),
+            ContentHunk(b"a\nb\nright\n".to_vec()),
+        ],
+    );
+    insta::assert_snapshot!(materialize(&merge, ConflictMarkerStyle::Diff), @r###"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1
+    left
+    %%%%%%% Changes from base to side #2
+     a
+     b
+    -c
+    +right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    let materialized = materialize(&merge, ConflictMarkerStyle::BaseOurs);
+    insta::assert_snapshot!(materialized, @r###"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -a
+    -b
+    -c
+    +left
+    +++++++ Contents of side #2
+    a
+    b
+    right
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    assert_eq!(
+        parse_conflict(materialized.as_bytes(), merge.num_sides()),
+        Some(vec![merge.clone()])
+    );
+
+    // Git style falls back to snapshot style for conflicts with more than 2
+    // sides
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"a\n".to_vec()), ContentHunk(b"b\n".to_vec())],
+        vec![
+            ContentHunk(b"a\nleft\n".to_vec()),
+            ContentHunk(b"right\n".to_vec()),
+            ContentHunk(b"b\n".to_vec()),
+        ],
+    );
+    insta::assert_snapshot!(materialize(&merge, ConflictMarkerStyle::Git), @r###"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1
+    a
+    left
+    ------- Contents of base #1
+    a
+    +++++++ Contents of side #2
+    right
+    ------- Contents of base #2
+    b
+    +++++++ Contents of side #3
+    b
+    >>>>>>> Conflict 1 of 1 ends
+    "###);
+    assert_eq!(
+        materialize(&merge, ConflictMarkerStyle::Git),
+        materialize(&merge, ConflictMarkerStyle::Snapshot)
+    );
+}
+
+#[test]
+fn test_materialize_merge_result_bounded() {
+    // Every other line is changed on both sides, so there's a conflict hunk per
+    // changed line
+    let base = (0..1000).map(|i| format!("line {i}\n")).join("");
+    let side = |name: &str| {
+        (0..1000)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!("line {i} {name}\n")
+                } else {
+                    format!("line {i}\n")
+                }
+            })
+            .join("")
+    };
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(base.into_bytes())],
+        vec![
+            ContentHunk(side("left").into_bytes()),
+            ContentHunk(side("right").into_bytes()),
+        ],
+    );
+    let mut expected = vec![];
+    materialize_merge_result(&merge, &mut expected).unwrap();
+    assert!(expected.len() > 50_000);
+
+    // The full output is written if it fits
+    let mut output = vec![];
+    materialize_merge_result_bounded(&merge, expected.len(), &mut output).unwrap();
+    assert_eq!(output, expected);
+
+    // Otherwise, writing stops at the limit
+    let mut output = vec![];
+    let err = materialize_merge_result_bounded(&merge, 10_000, &mut output).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    insta::assert_snapshot!(err, @r###"
+    Materialized conflict exceeds the limit of 10000 bytes
+    "###);
+    assert!(output.len() <= 10_000);
+    assert!(expected.starts_with(&output));
+}
+
 #[test]
 fn test_parse_conflict_resolved() {
     assert_eq!(
@@ -583,6 +991,99 @@ fn test_parse_conflict_simple() {
     )
 }
 
+#[test]
+fn test_parse_conflict_explicit() {
+    let parsed = parse_conflict_explicit(
+        indoc! {b"
+            <<<<<<<
+            %%%%%%%
+            -line 1
+            +left
+            +++++++
+            right
+            >>>>>>>
+            line 2
+            "},
+        2,
+        MIN_CONFLICT_MARKER_LEN,
+    )
+    .unwrap();
+    // The conflict at the start of the file has no leading context
+    assert_matches!(
+        parsed.hunks.as_slice(),
+        [ConflictHunk::Conflict(_), ConflictHunk::Resolved(_)]
+    );
+    insta::assert_debug_snapshot!(parsed, @r###"
+    ParsedConflict {
+        hunks: [
+            Conflict(
+                Conflicted(
+                    [
+                        "left\n",
+                        "line 1\n",
+                        "right\n",
+                    ],
+                ),
+            ),
+            Resolved(
+                "line 2\n",
+            ),
+        ],
+    }
+    "###);
+
+    // Converting back gives the same hunks as parse_conflict()
+    let merges = parsed
+        .hunks
+        .into_iter()
+        .map(ConflictHunk::into_merge)
+        .collect_vec();
+    assert_eq!(
+        Some(merges),
+        parse_conflict(
+            indoc! {b"
+                <<<<<<<
+                %%%%%%%
+                -line 1
+                +left
+                +++++++
+                right
+                >>>>>>>
+                line 2
+                "},
+            2
+        )
+    );
+
+    // A resolved-looking conflict between markers is still classified as a
+    // conflict
+    let parsed = parse_conflict_explicit(
+        indoc! {b"
+            line 1
+            <<<<<<<
+            +++++++
+            same
+            +++++++
+            same
+            -------
+            base
+            >>>>>>>
+            "},
+        2,
+        MIN_CONFLICT_MARKER_LEN,
+    )
+    .unwrap();
+    assert_matches!(
+        parsed.hunks.as_slice(),
+        [ConflictHunk::Resolved(_), ConflictHunk::Conflict(_)]
+    );
+
+    assert_eq!(
+        parse_conflict_explicit(b"line 1\n", 2, MIN_CONFLICT_MARKER_LEN),
+        None
+    );
+}
+
 #[test]
 fn test_parse_conflict_multi_way() {
     insta::assert_debug_snapshot!(
@@ -721,65 +1222,535 @@ fn test_parse_conflict_malformed_marker() {
 }
 
 #[test]
-fn test_parse_conflict_malformed_diff() {
-    // The diff part is invalid (missing space before "line 4")
+fn test_parse_conflict_mixed_marker_chars() {
+    // Lines mixing marker characters, or not followed by a space, aren't
+    // conflict markers, so they're parsed as contents of the conflict.
+    let mixed_lines = [
+        "<<<<<<<>>>>>>>",
+        ">>>>>>><<<<<<<",
+        "+++++++-------",
+        "-------+++++++",
+        "%%%%%%%+++++++",
+        "<<<<<<<%",
+        "+++++++\tfoo",
+        "<<<>>>",
+    ];
+    for mixed_line in mixed_lines {
+        let content = format!("<<<<<<<\n%%%%%%%\n-base\n+left\n+++++++\n{mixed_line}\n>>>>>>>\n");
+        let hunks = parse_conflict(content.as_bytes(), 2)
+            .unwrap_or_else(|| panic!("failed to parse with {mixed_line:?}"));
+        assert_eq!(
+            hunks,
+            [Merge::from_removes_adds(
+                vec![ContentHunk(b"base\n".to_vec())],
+                vec![
+                    ContentHunk(b"left\n".to_vec()),
+                    ContentHunk(format!("{mixed_line}\n").into_bytes())
+                ]
+            )],
+            "{mixed_line:?}"
+        );
+    }
+
+    // A marker followed by a space and any text is still a marker
     assert_eq!(
         parse_conflict(
             indoc! {b"
-            line 1
-            <<<<<<<
-            %%%%%%%
-             line 2
-            -line 3
+            <<<<<<< Conflict 1 of 1
+            %%%%%%% >>>>>>>
+            -base
             +left
-            line 4
-            +++++++
+            +++++++ -------
             right
-            >>>>>>>
-            line 5
+            >>>>>>> <<<<<<<
             "},
             2
         ),
-        None
-    )
+        Some(vec![Merge::from_removes_adds(
+            vec![ContentHunk(b"base\n".to_vec())],
+            vec![
+                ContentHunk(b"left\n".to_vec()),
+                ContentHunk(b"right\n".to_vec())
+            ]
+        )])
+    );
 }
 
 #[test]
-fn test_update_conflict_from_content() {
-    let test_repo = TestRepo::init();
-    let store = test_repo.repo.store();
-
-    let path = RepoPath::from_internal_string("dir/file");
-    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\nline 3\n");
-    let left_file_id = testutils::write_file(store, path, "left 1\nline 2\nleft 3\n");
-    let right_file_id = testutils::write_file(store, path, "right 1\nline 2\nright 3\n");
-    let conflict = Merge::from_removes_adds(
-        vec![Some(base_file_id.clone())],
-        vec![Some(left_file_id.clone()), Some(right_file_id.clone())],
+fn test_parse_conflict_crlf_markers() {
+    // Marker lines without trailing text may end with "\r\n"
+    let expected = Some(vec![Merge::from_removes_adds(
+        vec![ContentHunk(b"base\r\n".to_vec())],
+        vec![
+            ContentHunk(b"left\r\n".to_vec()),
+            ContentHunk(b"right\r\n".to_vec()),
+        ],
+    )]);
+    assert_eq!(
+        parse_conflict(
+            b"<<<<<<<\r\n%%%%%%%\r\n-base\r\n+left\r\n+++++++\r\nright\r\n>>>>>>>\r\n",
+            2
+        ),
+        expected
+    );
+    // The "\r" isn't part of the text after a marker
+    assert_eq!(
+        parse_conflict(
+            b"<<<<<<< Conflict 1 of 1\r\n%%%%%%% Changes from base to side #1\r\n-base\r\n\
+              +left\r\n+++++++ Contents of side #2\r\nright\r\n>>>>>>> Conflict 1 of 1 ends\r\n",
+            2
+        ),
+        expected
+    );
+    // "\r" followed by other text isn't the end of a marker line
+    assert_eq!(
+        parse_conflict(
+            b"<<<<<<<\r\n%%%%%%%\r\n-base\r\n+left\r\n+++++++\r\n+++++++\rfoo\r\n>>>>>>>\r\n",
+            2
+        ),
+        Some(vec![Merge::from_removes_adds(
+            vec![ContentHunk(b"base\r\n".to_vec())],
+            vec![
+                ContentHunk(b"left\r\n".to_vec()),
+                ContentHunk(b"+++++++\rfoo\r\n".to_vec()),
+            ],
+        )])
     );
+}
 
-    // If the content is unchanged compared to the materialized value, we get the
-    // old conflict id back.
-    let materialized = materialize_conflict_string(store, path, &conflict);
-    let parse = |content| {
-        update_from_content(&conflict, store, path, content)
-            .block_on()
+#[test]
+fn test_materialize_parse_roundtrip_crlf() {
+    // All terms use "\r\n", and the left side lacks a newline at the end
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\r\nline 2\r\nline 3\r\n".to_vec())],
+        vec![
+            ContentHunk(b"line 1\r\nleft 2\r\nleft 3".to_vec()),
+            ContentHunk(b"line 1\r\nright 2\r\nline 3\r\n".to_vec()),
+        ],
+    );
+    // An editor on Windows may convert the marker lines to "\r\n" too
+    let to_crlf = |content: &[u8]| {
+        String::from_utf8(content.to_vec())
             .unwrap()
+            .replace("\r\n", "\n")
+            .replace('\n', "\r\n")
+            .into_bytes()
     };
-    assert_eq!(parse(materialized.as_bytes()), conflict);
+    for marker_style in [ConflictMarkerStyle::Diff, ConflictMarkerStyle::Snapshot] {
+        let options = ConflictMaterializeOptions {
+            added_line_ending: ConflictLineEnding::Crlf,
+            marker_style,
+            ..Default::default()
+        };
+        let mut materialized = vec![];
+        materialize_merge_result_with_options(&merge, &options, &mut materialized).unwrap();
+        let parsed = parse_conflict(&materialized, merge.num_sides());
+        assert!(parsed.is_some(), "{marker_style:?}");
+        assert_eq!(
+            parse_conflict(&to_crlf(&materialized), merge.num_sides()),
+            parsed,
+            "{marker_style:?}"
+        );
+        insta::allow_duplicates! {
+            insta::assert_debug_snapshot!(parsed, @r###"
+            Some(
+                [
+                    Resolved(
+                        "line 1\r\n",
+                    ),
+                    Conflicted(
+                        [
+                            "left 2\r\nleft 3\r\n",
+                            "line 2\r\nline 3\r\n",
+                            "right 2\r\nline 3\r\n",
+                        ],
+                    ),
+                ],
+            )
+            "###);
+        }
 
-    // If the conflict is resolved, we get None back to indicate that.
-    let expected_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nresolved 3\n");
-    assert_eq!(
-        parse(b"resolved 1\nline 2\nresolved 3\n"),
-        Merge::normal(expected_file_id)
-    );
+        // Materializing the parsed conflict again gives the same result
+        let hunks = parsed.unwrap();
+        let reparsed = flatten_hunks(&hunks, merge.num_sides());
+        let mut rematerialized = vec![];
+        materialize_merge_result_with_options(&reparsed, &options, &mut rematerialized).unwrap();
+        assert_eq!(
+            parse_conflict(&rematerialized, merge.num_sides()),
+            Some(hunks),
+            "{marker_style:?}"
+        );
+    }
+}
 
-    // If the conflict is partially resolved, we get a new conflict back.
-    let new_conflict = parse(
-        b"resolved 1\nline 2\n<<<<<<<\n%%%%%%%\n-line 3\n+left 3\n+++++++\nright 3\n>>>>>>>\n",
-    );
-    assert_ne!(new_conflict, conflict);
+#[test]
+fn test_materialize_parse_roundtrip_conflict_hint() {
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"context\nline 1\nline 2\nline 3\n".to_vec())],
+        vec![
+            ContentHunk(b"context\nleft 1\nline 2\nleft 3\n".to_vec()),
+            ContentHunk(b"context\nright 1\nline 2\nright 3\n".to_vec()),
+        ],
+    );
+    let options = ConflictMaterializeOptions {
+        conflict_hint: true,
+        ..Default::default()
+    };
+    let mut materialized = vec![];
+    materialize_merge_result_with_options(&merge, &options, &mut materialized).unwrap();
+    insta::assert_snapshot!(String::from_utf8(materialized.clone()).unwrap(), @r###"
+    context
+    # jj: 2 conflicts below; edit the markers or run `jj resolve`
+    <<<<<<< Conflict 1 of 2
+    %%%%%%% Changes from base to side #1
+    -line 1
+    +left 1
+    +++++++ Contents of side #2
+    right 1
+    >>>>>>> Conflict 1 of 2 ends
+    line 2
+    <<<<<<< Conflict 2 of 2
+    %%%%%%% Changes from base to side #1
+    -line 3
+    +left 3
+    +++++++ Contents of side #2
+    right 3
+    >>>>>>> Conflict 2 of 2 ends
+    "###);
+
+    // parse_conflict() doesn't know whether the hint was written, so it's kept
+    // as resolved context
+    insta::assert_debug_snapshot!(
+        parse_conflict(&materialized, merge.num_sides()).unwrap()[0], @r###"
+    Resolved(
+        "context\n# jj: 2 conflicts below; edit the markers or run `jj resolve`\n",
+    )
+    "###);
+
+    // No hint is written if there are no conflicts
+    let resolved = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\nline 2\nline 3\n".to_vec())],
+        vec![
+            ContentHunk(b"left 1\nline 2\nline 3\n".to_vec()),
+            ContentHunk(b"line 1\nline 2\nright 3\n".to_vec()),
+        ],
+    );
+    let mut materialized = vec![];
+    materialize_merge_result_with_options(&resolved, &options, &mut materialized).unwrap();
+    insta::assert_snapshot!(String::from_utf8(materialized).unwrap(), @r###"
+    left 1
+    line 2
+    right 3
+    "###);
+}
+
+#[test]
+fn test_parse_conflict_git_style() {
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"line 1\nline 2\nline 3\n".to_vec())],
+        vec![
+            ContentHunk(b"line 1\nleft\nline 3\n".to_vec()),
+            ContentHunk(b"line 1\nright\nline 3\n".to_vec()),
+        ],
+    );
+    let expected = parse_conflict(
+        materialize_conflict_string_with_style(&merge, ConflictMarkerStyle::Diff, false).as_bytes(),
+        2,
+    );
+    assert!(expected.is_some());
+
+    // Round trip without and with a comment after the separator
+    let materialized =
+        materialize_conflict_string_with_style(&merge, ConflictMarkerStyle::Git, false);
+    insta::assert_snapshot!(materialized, @r###"
+    line 1
+    <<<<<<< Side #1 (Conflict 1 of 1)
+    left
+    ||||||| Base
+    line 2
+    =======
+    right
+    >>>>>>> Side #2 (Conflict 1 of 1 ends)
+    line 3
+    "###);
+    assert_eq!(parse_conflict(materialized.as_bytes(), 2), expected);
+    let materialized =
+        materialize_conflict_string_with_style(&merge, ConflictMarkerStyle::Git, true);
+    insta::assert_snapshot!(materialized, @r###"
+    line 1
+    <<<<<<< Side #1 (Conflict 1 of 1)
+    left
+    ||||||| Base
+    line 2
+    ======= Side #2
+    right
+    >>>>>>> Side #2 (Conflict 1 of 1 ends)
+    line 3
+    "###);
+    assert_eq!(parse_conflict(materialized.as_bytes(), 2), expected);
+
+    // Text after the markers written by other tools is ignored
+    assert_eq!(
+        parse_conflict(
+            indoc! {b"
+            line 1
+            <<<<<<< HEAD
+            left
+            ||||||| merged common ancestors
+            line 2
+            ======= theirs
+            right
+            >>>>>>> branch
+            line 3
+            "},
+            2
+        ),
+        expected
+    );
+
+    // The first side may be empty
+    insta::assert_debug_snapshot!(
+        parse_conflict(
+            indoc! {b"
+            <<<<<<<
+            |||||||
+            base
+            =======
+            right
+            >>>>>>>
+            "},
+            2
+        ),
+        @r###"
+    Some(
+        [
+            Conflicted(
+                [
+                    "",
+                    "base\n",
+                    "right\n",
+                ],
+            ),
+        ],
+    )
+    "###
+    );
+
+    // Lines that look like markers inside jj style conflicts are content
+    insta::assert_debug_snapshot!(
+        parse_conflict(
+            indoc! {b"
+            <<<<<<<
+            %%%%%%%
+            -base
+            +left
+            +++++++
+            =======
+            |||||||
+            >>>>>>>
+            "},
+            2
+        ),
+        @r###"
+    Some(
+        [
+            Conflicted(
+                [
+                    "left\n",
+                    "base\n",
+                    "=======\n|||||||\n",
+                ],
+            ),
+        ],
+    )
+    "###
+    );
+
+    // Missing or repeated markers
+    for content in [
+        "<<<<<<<\nleft\n|||||||\nbase\n>>>>>>>\n",
+        "<<<<<<<\nleft\n|||||||\nbase\n=======\nright\n=======\n>>>>>>>\n",
+        "<<<<<<<\nleft\n=======\n|||||||\nbase\n>>>>>>>\n",
+        "<<<<<<<\nleft\n|||||||\nbase\n|||||||\n=======\nright\n>>>>>>>\n",
+    ] {
+        assert_eq!(parse_conflict(content.as_bytes(), 2), None, "{content:?}");
+        assert_eq!(
+            check_conflict_markers(content.as_bytes(), 2, MIN_CONFLICT_MARKER_LEN),
+            [(1, ConflictParseError::MalformedGitConflict)],
+            "{content:?}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_conflict_malformed_diff() {
+    // The diff part is invalid (missing space before "line 4")
+    assert_eq!(
+        parse_conflict(
+            indoc! {b"
+            line 1
+            <<<<<<<
+            %%%%%%%
+             line 2
+            -line 3
+            +left
+            line 4
+            +++++++
+            right
+            >>>>>>>
+            line 5
+            "},
+            2
+        ),
+        None
+    )
+}
+
+#[test]
+fn test_check_conflict_markers() {
+    // Valid conflict
+    let valid = indoc! {b"
+        <<<<<<<
+        %%%%%%%
+        -line 1
+        +left
+        +++++++
+        right
+        >>>>>>>
+        "};
+    assert_eq!(
+        check_conflict_markers(valid, 2, MIN_CONFLICT_MARKER_LEN),
+        vec![]
+    );
+    insta::assert_debug_snapshot!(check_conflict_markers(valid, 3, MIN_CONFLICT_MARKER_LEN), @r###"
+    [
+        (
+            1,
+            WrongNumberOfSides {
+                expected: 3,
+                actual: 2,
+            },
+        ),
+    ]
+    "###);
+
+    // Malformed diff section, content before the first section, and too many
+    // removes
+    let malformed = indoc! {b"
+        line 1
+        <<<<<<<
+        %%%%%%%
+        -line 2
+        line 3
+        +++++++
+        right
+        >>>>>>>
+        <<<<<<<
+        stray
+        +++++++
+        right
+        >>>>>>>
+        <<<<<<<
+        -------
+        base 1
+        -------
+        base 2
+        +++++++
+        right
+        >>>>>>>
+        "};
+    let errors = check_conflict_markers(malformed, 2, MIN_CONFLICT_MARKER_LEN);
+    insta::assert_debug_snapshot!(errors, @r###"
+    [
+        (
+            2,
+            UnexpectedDiffLine {
+                line: "line 3",
+            },
+        ),
+        (
+            9,
+            ContentBeforeSection,
+        ),
+        (
+            14,
+            AddRemoveCountMismatch {
+                adds: 1,
+                removes: 2,
+            },
+        ),
+    ]
+    "###);
+    let messages = errors
+        .iter()
+        .map(|(line, err)| format!("{line}: {err}"))
+        .join("\n");
+    insta::assert_snapshot!(messages, @r###"
+    2: Unexpected line in diff section: "line 3"
+    9: Content before the first section of the conflict
+    14: Expected one more added side than removed sides, but found 1 added and 2 removed
+    "###);
+}
+
+#[test]
+fn test_parse_conflict_wrong_number_of_removes() {
+    // There are two negative terms but no more positive terms
+    assert_eq!(
+        parse_conflict(
+            indoc! {b"
+            line 1
+            <<<<<<<
+            -------
+            base 1
+            -------
+            base 2
+            +++++++
+            right
+            >>>>>>>
+            line 5
+            "},
+            1
+        ),
+        None
+    )
+}
+
+#[test]
+fn test_update_conflict_from_content() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("dir/file");
+    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\nline 3\n");
+    let left_file_id = testutils::write_file(store, path, "left 1\nline 2\nleft 3\n");
+    let right_file_id = testutils::write_file(store, path, "right 1\nline 2\nright 3\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_file_id.clone())],
+        vec![Some(left_file_id.clone()), Some(right_file_id.clone())],
+    );
+
+    // If the content is unchanged compared to the materialized value, we get the
+    // old conflict id back.
+    let materialized = materialize_conflict_string(store, path, &conflict);
+    let parse = |content| {
+        update_from_content(&conflict, store, path, content)
+            .block_on()
+            .unwrap()
+    };
+    assert_eq!(parse(materialized.as_bytes()), conflict);
+
+    // If the conflict is resolved, we get None back to indicate that.
+    let expected_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nresolved 3\n");
+    assert_eq!(
+        parse(b"resolved 1\nline 2\nresolved 3\n"),
+        Merge::normal(expected_file_id)
+    );
+
+    // If the conflict is partially resolved, we get a new conflict back.
+    let new_conflict = parse(
+        b"resolved 1\nline 2\n<<<<<<<\n%%%%%%%\n-line 3\n+left 3\n+++++++\nright 3\n>>>>>>>\n",
+    );
+    assert_ne!(new_conflict, conflict);
     // Calculate expected new FileIds
     let new_base_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nline 3\n");
     let new_left_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nleft 3\n");
@@ -796,6 +1767,343 @@ fn test_update_conflict_from_content() {
     );
 }
 
+#[test]
+fn test_update_conflict_from_content_with_errors() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("dir/file");
+    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\nline 3\n");
+    let left_file_id = testutils::write_file(store, path, "left 1\nline 2\nleft 3\n");
+    let right_file_id = testutils::write_file(store, path, "right 1\nline 2\nright 3\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_file_id)],
+        vec![Some(left_file_id), Some(right_file_id)],
+    );
+    let parse = |content: &[u8]| {
+        update_from_content_with_errors(
+            &conflict,
+            store,
+            path,
+            content,
+            &ConflictMaterializeOptions::default(),
+        )
+        .block_on()
+        .unwrap()
+    };
+
+    // Unchanged and valid conflicts have no errors
+    let materialized = materialize_conflict_string(store, path, &conflict);
+    assert_eq!(parse(materialized.as_bytes()), (conflict.clone(), vec![]));
+
+    // A malformed hunk next to a valid one is kept as resolved content
+    let content = indoc! {b"
+        <<<<<<<
+        %%%%%%%
+        -line 1
+        left 1
+        +++++++
+        right 1
+        >>>>>>>
+        line 2
+        <<<<<<<
+        %%%%%%%
+        -line 3
+        +left 3
+        +++++++
+        right 3
+        >>>>>>>
+    "};
+    let (new_conflict, errors) = parse(content);
+    assert!(new_conflict.as_resolved().is_none());
+    insta::assert_debug_snapshot!(errors, @r###"
+    [
+        (
+            1,
+            UnexpectedDiffLine {
+                line: "left 1",
+            },
+        ),
+    ]
+    "###);
+
+    // If no hunk can be parsed, the file is resolved and all errors are reported
+    let content = indoc! {b"
+        <<<<<<<
+        +++++++
+        left 1
+        -------
+        line 1
+        -------
+        line 1
+        +++++++
+        right 1
+        >>>>>>>
+        line 2
+        <<<<<<<
+        stray
+        >>>>>>>
+    "};
+    let (new_conflict, errors) = parse(content);
+    let expected_file_id =
+        testutils::write_file(store, path, std::str::from_utf8(content).unwrap());
+    assert_eq!(new_conflict, Merge::normal(expected_file_id));
+    insta::assert_debug_snapshot!(errors, @r###"
+    [
+        (
+            1,
+            AddRemoveCountMismatch {
+                adds: 2,
+                removes: 2,
+            },
+        ),
+        (
+            12,
+            ContentBeforeSection,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_update_conflict_from_content_with_conflict_hint() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("dir/file");
+    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\nline 3\n");
+    let left_file_id = testutils::write_file(store, path, "left 1\nline 2\nleft 3\n");
+    let right_file_id = testutils::write_file(store, path, "right 1\nline 2\nright 3\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_file_id)],
+        vec![Some(left_file_id), Some(right_file_id)],
+    );
+    let contents = extract_as_single_hunk(&conflict, store, path)
+        .block_on()
+        .unwrap();
+    let options = ConflictMaterializeOptions {
+        conflict_hint: true,
+        ..Default::default()
+    };
+    let mut materialized = vec![];
+    materialize_merge_result_with_options(&contents, &options, &mut materialized).unwrap();
+
+    let update = |content: &str, options: &ConflictMaterializeOptions| {
+        let (new_conflict, errors) =
+            update_from_content_with_errors(&conflict, store, path, content.as_bytes(), options)
+                .block_on()
+                .unwrap();
+        assert_eq!(errors, vec![]);
+        new_conflict
+    };
+    let materialized = String::from_utf8(materialized).unwrap();
+
+    // The hint isn't written to the file contents
+    assert_eq!(update(&materialized, &options), conflict);
+
+    // The hint is removed if one of the conflicts is resolved
+    let partly_resolved = materialized.replacen(
+        indoc! {"
+            <<<<<<< Conflict 1 of 2
+            %%%%%%% Changes from base to side #1
+            -line 1
+            +left 1
+            +++++++ Contents of side #2
+            right 1
+            >>>>>>> Conflict 1 of 2 ends
+        "},
+        "resolved 1\n",
+        1,
+    );
+    let new_conflict = update(&partly_resolved, &options);
+    let new_base_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nline 3\n");
+    let new_left_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nleft 3\n");
+    let new_right_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nright 3\n");
+    assert_eq!(
+        new_conflict,
+        Merge::from_removes_adds(
+            vec![Some(new_base_file_id)],
+            vec![Some(new_left_file_id), Some(new_right_file_id)]
+        )
+    );
+
+    // The hint is removed if all the conflicts are resolved
+    let fully_resolved = indoc! {"
+        # jj: 2 conflicts below; edit the markers or run `jj resolve`
+        resolved 1
+        line 2
+        resolved 3
+    "};
+    let new_file_id = testutils::write_file(store, path, "resolved 1\nline 2\nresolved 3\n");
+    assert_eq!(update(fully_resolved, &options), Merge::normal(new_file_id));
+
+    // An edited hint is kept
+    let edited = fully_resolved.replace("edit the markers", "fix the markers");
+    let edited_file_id = testutils::write_file(store, path, &edited);
+    assert_eq!(update(&edited, &options), Merge::normal(edited_file_id));
+
+    // The hint-like line is kept if the old content was materialized without
+    // the hint
+    let fully_resolved_file_id = testutils::write_file(store, path, fully_resolved);
+    assert_eq!(
+        update(fully_resolved, &ConflictMaterializeOptions::default()),
+        Merge::normal(fully_resolved_file_id)
+    );
+}
+
+#[test]
+fn test_choose_materialized_conflict_marker_len() {
+    let choose = |terms: &[&str]| {
+        let merge = Merge::from_vec(terms.iter().map(|term| term.as_bytes()).collect_vec());
+        choose_materialized_conflict_marker_len(&merge)
+    };
+    assert_eq!(choose(&["a\n", "b\n", "c\n"]), 7);
+    assert_eq!(choose(&["======\n", "b\n", "c\n"]), 7);
+    assert_eq!(choose(&["a\n", "=======\n", "c\n"]), 8);
+    assert_eq!(choose(&["a\n", "b\n", "<<<<<<<<<< text\n"]), 11);
+    // The line may lack a newline at the end of the term
+    assert_eq!(choose(&["a\n", "b\n", "|||||||"]), 8);
+    // Lines starting with - or + may get another - or + prefix in a diff
+    assert_eq!(choose(&["------\n", "b\n", "c\n"]), 8);
+    assert_eq!(choose(&["+++++++\n", "b\n", "c\n"]), 9);
+    // Lines mixing marker characters aren't marker-like
+    assert_eq!(choose(&["<<<<<<<>>>>>>>\n", "=======x\n", "c\n"]), 7);
+
+    // The length can be overridden
+    let merge = Merge::from_removes_adds(
+        vec![ContentHunk(b"a\n".to_vec())],
+        vec![ContentHunk(b"b\n".to_vec()), ContentHunk(b"c\n".to_vec())],
+    );
+    let options = ConflictMaterializeOptions {
+        marker_len: Some(10),
+        ..Default::default()
+    };
+    let mut materialized = vec![];
+    materialize_merge_result_with_options(&merge, &options, &mut materialized).unwrap();
+    insta::assert_snapshot!(String::from_utf8(materialized.clone()).unwrap(), @r###"
+    <<<<<<<<<< Conflict 1 of 1
+    %%%%%%%%%% Changes from base to side #1
+    -a
+    +b
+    ++++++++++ Contents of side #2
+    c
+    >>>>>>>>>> Conflict 1 of 1 ends
+    "###);
+    assert_eq!(
+        parse_conflict(&materialized, 2),
+        parse_conflict_with_marker_len(&materialized, 2, 10)
+    );
+    assert_eq!(parse_conflict_with_marker_len(&materialized, 2, 11), None);
+}
+
+#[test]
+fn test_update_conflict_from_content_with_marker_like_lines() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    // The left side contains lines that look like conflict markers
+    let path = RepoPath::from_internal_string("dir/file");
+    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\n");
+    let left_file_id = testutils::write_file(store, path, "line 1\n<<<<<<<\n=======\nleft\n");
+    let right_file_id = testutils::write_file(store, path, "line 1\nright\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_file_id)],
+        vec![Some(left_file_id), Some(right_file_id)],
+    );
+    let materialized = materialize_conflict_string(store, path, &conflict);
+    insta::assert_snapshot!(materialized, @r###"
+    line 1
+    <<<<<<<< Conflict 1 of 1
+    ++++++++ Contents of side #1
+    <<<<<<<
+    =======
+    left
+    %%%%%%%% Changes from base to side #2
+    -line 2
+    +right
+    >>>>>>>> Conflict 1 of 1 ends
+    "###);
+    let parse = |content: &[u8]| {
+        update_from_content(&conflict, store, path, content)
+            .block_on()
+            .unwrap()
+    };
+    assert_eq!(parse(materialized.as_bytes()), conflict);
+
+    // Editing a side keeps the marker-like lines as content
+    let edited = materialized.replace("+right\n", "+right\n+=======\n");
+    let new_conflict = parse(edited.as_bytes());
+    let new_right_file_id = testutils::write_file(store, path, "line 1\nright\n=======\n");
+    assert_eq!(new_conflict.get_add(1).unwrap(), &Some(new_right_file_id));
+
+    // Materializing the new conflict round-trips as well
+    let rematerialized = materialize_conflict_string(store, path, &new_conflict);
+    assert_eq!(
+        update_from_content(&new_conflict, store, path, rematerialized.as_bytes())
+            .block_on()
+            .unwrap(),
+        new_conflict
+    );
+}
+
+#[test]
+fn test_update_conflict_from_content_reader() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("dir/file");
+    let base_file_id = testutils::write_file(store, path, "line 1\nline 2\nline 3\n");
+    let left_file_id = testutils::write_file(store, path, "left 1\nline 2\nleft 3\n");
+    let right_file_id = testutils::write_file(store, path, "right 1\nline 2\nright 3\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_file_id.clone())],
+        vec![Some(left_file_id.clone()), Some(right_file_id.clone())],
+    );
+
+    // Feeds the content in chunks of the given size
+    let parse_chunked = |content: &[u8], chunk_size: usize| {
+        let chunks = content
+            .chunks(chunk_size)
+            .map(|chunk| Ok::<_, std::io::Error>(chunk.to_vec()))
+            .collect_vec();
+        let mut reader = futures::stream::iter(chunks).into_async_read();
+        update_from_content_reader(&conflict, store, path, &mut reader)
+            .block_on()
+            .unwrap()
+    };
+    let parse = |content: &[u8]| {
+        update_from_content(&conflict, store, path, content)
+            .block_on()
+            .unwrap()
+    };
+
+    let materialized = materialize_conflict_string(store, path, &conflict);
+    let resolved = b"resolved 1\nline 2\nresolved 3\n";
+    let partially_resolved =
+        b"resolved 1\nline 2\n<<<<<<<\n%%%%%%%\n-line 3\n+left 3\n+++++++\nright 3\n>>>>>>>\n";
+    for chunk_size in [1, 3, 7, 1000] {
+        // If the content is unchanged, we get the old conflict back.
+        assert_eq!(parse_chunked(materialized.as_bytes(), chunk_size), conflict);
+
+        // A prefix or an extension of the unchanged content is a change
+        let prefix = &materialized.as_bytes()[..materialized.len() - 1];
+        assert_eq!(parse_chunked(prefix, chunk_size), parse(prefix));
+        let extended = format!("{materialized}more\n");
+        assert_eq!(
+            parse_chunked(extended.as_bytes(), chunk_size),
+            parse(extended.as_bytes())
+        );
+
+        // Otherwise, the result should be the same as update_from_content()
+        assert_eq!(parse_chunked(resolved, chunk_size), parse(resolved));
+        assert_eq!(
+            parse_chunked(partially_resolved, chunk_size),
+            parse(partially_resolved)
+        );
+        assert_eq!(parse_chunked(b"", chunk_size), parse(b""));
+    }
+}
+
 #[test]
 fn test_update_conflict_from_content_modify_delete() {
     let test_repo = TestRepo::init();
@@ -985,6 +2293,21 @@ fn test_update_conflict_from_content_simplified_conflict() {
     );
 }
 
+fn materialize_conflict_string_with_style(
+    merge: &Merge<ContentHunk>,
+    marker_style: ConflictMarkerStyle,
+    git_separator_comment: bool,
+) -> String {
+    let options = ConflictMaterializeOptions {
+        marker_style,
+        git_separator_comment,
+        ..Default::default()
+    };
+    let mut result = vec![];
+    materialize_merge_result_with_options(merge, &options, &mut result).unwrap();
+    String::from_utf8(result).unwrap()
+}
+
 fn materialize_conflict_string(
     store: &Store,
     path: &RepoPath,
@@ -997,3 +2320,229 @@ fn materialize_conflict_string(
     materialize_merge_result(&contents, &mut result).unwrap();
     String::from_utf8(result).unwrap()
 }
+
+/// Options for generating random conflicts in the materialize/parse round-trip
+/// test.
+#[derive(Clone, Copy, Debug)]
+struct RandomConflictOptions {
+    /// Maximum number of sides (adds) of the generated merge.
+    max_sides: usize,
+    /// Maximum number of lines in the base content.
+    max_lines: usize,
+    /// Whether the last line of a term may lack a trailing newline.
+    missing_eol: bool,
+    /// Whether lines looking like conflict markers may be generated.
+    marker_like_lines: bool,
+}
+
+/// Generates a random line. Lines are picked from a small alphabet so that
+/// independently generated terms share lines with each other.
+fn random_line(rng: &mut impl Rng, options: &RandomConflictOptions) -> Vec<u8> {
+    const MARKER_LIKE_LINES: &[&str] = &[
+        "<<<<<<<",
+        ">>>>>>>",
+        "%%%%%%%",
+        "-------",
+        "+++++++",
+        "<<<<<<< Conflict 1 of 1",
+        "+++++++ Contents of side #1",
+        "%%%%%%% Changes from base to side #1",
+        ">>>>>>> Conflict 1 of 1 ends",
+        "------",
+        "++++++",
+    ];
+    if options.marker_like_lines && rng.gen_ratio(1, 8) {
+        let line = MARKER_LIKE_LINES[rng.gen_range(0..MARKER_LIKE_LINES.len())];
+        return format!("{line}\n").into_bytes();
+    }
+    format!("line {}\n", rng.gen_range(0..5)).into_bytes()
+}
+
+/// Generates a random term by randomly editing the lines of `base`.
+fn random_term_from(
+    rng: &mut impl Rng,
+    base: &[Vec<u8>],
+    options: &RandomConflictOptions,
+) -> Vec<u8> {
+    let mut lines = vec![];
+    for line in base {
+        match rng.gen_range(0..6) {
+            0 => {} // delete
+            1 => lines.push(random_line(rng, options)),
+            2 => {
+                lines.push(line.clone());
+                lines.push(random_line(rng, options));
+            }
+            _ => lines.push(line.clone()),
+        }
+    }
+    if rng.gen_ratio(1, 4) {
+        lines.push(random_line(rng, options));
+    }
+    let mut content = lines.concat();
+    if options.missing_eol && content.ends_with(b"\n") && rng.gen_ratio(1, 3) {
+        content.pop();
+    }
+    content
+}
+
+/// Generates a random merge of `ContentHunk`s. The sides and bases are derived
+/// from a common base so that the merge contains both resolved and conflicted
+/// regions.
+fn random_merge(rng: &mut impl Rng, options: &RandomConflictOptions) -> Merge<ContentHunk> {
+    let num_lines = rng.gen_range(0..=options.max_lines);
+    let base = (0..num_lines)
+        .map(|_| random_line(rng, options))
+        .collect_vec();
+    let num_sides = rng.gen_range(1..=options.max_sides);
+    let terms = (0..num_sides * 2 - 1)
+        .map(|_| ContentHunk(random_term_from(rng, &base, options)))
+        .collect_vec();
+    Merge::from_vec(terms)
+}
+
+/// Concatenates the terms of each hunk, returning a merge of the full contents.
+fn flatten_hunks(hunks: &[Merge<ContentHunk>], num_sides: usize) -> Merge<ContentHunk> {
+    let mut contents = Merge::from_vec(vec![ContentHunk(vec![]); num_sides * 2 - 1]);
+    for hunk in hunks {
+        if let Some(slice) = hunk.as_resolved() {
+            for content in contents.iter_mut() {
+                content.0.extend_from_slice(&slice.0);
+            }
+        } else {
+            for (content, slice) in zip(contents.iter_mut(), hunk.iter()) {
+                content.0.extend_from_slice(&slice.0);
+            }
+        }
+    }
+    contents
+}
+
+/// Adds a newline to the end of each non-empty term lacking one. A missing
+/// newline at the end of a conflicted term can't be represented by conflict
+/// markers, so it's added by `materialize_merge_result()`.
+fn add_missing_eols(hunk: &Merge<ContentHunk>) -> Merge<ContentHunk> {
+    hunk.map(|content| {
+        let mut content = content.0.clone();
+        if !content.is_empty() && !content.ends_with(b"\n") {
+            content.push(b'\n');
+        }
+        ContentHunk(content)
+    })
+}
+
+/// Checks that parsing the materialized `merge` produces the same hunks as
+/// the merge itself. Returns a description of the mismatch if any.
+fn check_materialize_parse_roundtrip(
+    merge: &Merge<ContentHunk>,
+    marker_style: ConflictMarkerStyle,
+) -> Result<(), String> {
+    let options = ConflictMaterializeOptions {
+        marker_style,
+        ..Default::default()
+    };
+    let mut materialized = vec![];
+    materialize_merge_result_with_options(merge, &options, &mut materialized).unwrap();
+    let marker_len = choose_materialized_conflict_marker_len(merge);
+    let parsed = parse_conflict_with_marker_len(&materialized, merge.num_sides(), marker_len)
+        .map(|hunks| flatten_hunks(&hunks, merge.num_sides()));
+    let slices = merge.map(|content| content.0.as_slice());
+    let expected = match files::merge(&slices) {
+        MergeResult::Resolved(content) => {
+            if materialized != content.0 {
+                return Err("resolved content was not materialized as is".to_owned());
+            }
+            None
+        }
+        MergeResult::Conflict(hunks) => {
+            let hunks = hunks
+                .iter()
+                .map(|hunk| {
+                    if hunk.is_resolved() {
+                        hunk.clone()
+                    } else {
+                        add_missing_eols(hunk)
+                    }
+                })
+                .collect_vec();
+            Some(flatten_hunks(&hunks, merge.num_sides()))
+        }
+    };
+    if parsed != expected {
+        return Err(format!(
+            "materialized:\n{}\nexpected: {expected:#?}\nparsed: {parsed:#?}",
+            String::from_utf8_lossy(&materialized)
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the round-trip check on many random merges. Merges are generated with
+/// increasing sizes so that the first reported failure is a small one.
+fn run_materialize_parse_roundtrip(
+    options: RandomConflictOptions,
+    marker_style: ConflictMarkerStyle,
+    seed: u64,
+) {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    for max_lines in 0..=options.max_lines {
+        for max_sides in 1..=options.max_sides {
+            let options = RandomConflictOptions {
+                max_sides,
+                max_lines,
+                ..options
+            };
+            for _ in 0..50 {
+                let merge = random_merge(&mut rng, &options);
+                if let Err(message) = check_materialize_parse_roundtrip(&merge, marker_style) {
+                    panic!("round-trip failed for {merge:#?} with {options:?}:\n{message}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_materialize_parse_roundtrip_random() {
+    let options = RandomConflictOptions {
+        max_sides: 4,
+        max_lines: 8,
+        missing_eol: false,
+        marker_like_lines: false,
+    };
+    run_materialize_parse_roundtrip(options, ConflictMarkerStyle::Diff, 0);
+}
+
+#[test]
+fn test_materialize_parse_roundtrip_random_missing_eol() {
+    let options = RandomConflictOptions {
+        max_sides: 3,
+        max_lines: 8,
+        missing_eol: true,
+        marker_like_lines: false,
+    };
+    run_materialize_parse_roundtrip(options, ConflictMarkerStyle::Diff, 1);
+}
+
+#[test]
+fn test_materialize_parse_roundtrip_random_marker_like_lines() {
+    let options = RandomConflictOptions {
+        max_sides: 3,
+        max_lines: 8,
+        missing_eol: false,
+        marker_like_lines: true,
+    };
+    run_materialize_parse_roundtrip(options, ConflictMarkerStyle::Diff, 2);
+}
+
+#[test]
+fn test_materialize_parse_roundtrip_random_base_ours() {
+    // The style is meant for 3-term conflicts
+    let options = RandomConflictOptions {
+        max_sides: 2,
+        max_lines: 8,
+        missing_eol: true,
+        marker_like_lines: true,
+    };
+    run_materialize_parse_roundtrip(options, ConflictMarkerStyle::BaseOurs, 3);
+}
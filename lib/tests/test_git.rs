@@ -26,7 +26,7 @@ use jj_lib::commit_builder::CommitBuilder;
 use jj_lib::git;
 use jj_lib::git::{
     FailedRefExportReason, GitBranchPushTargets, GitFetchError, GitImportError, GitPushError,
-    GitRefUpdate, RefName, SubmoduleConfig,
+    GitRefUpdate, GitRefUpdateRejection, RefName, SubmoduleConfig,
 };
 use jj_lib::git_backend::GitBackend;
 use jj_lib::object_id::ObjectId;
@@ -2221,6 +2221,7 @@ fn test_fetch_empty_repo() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2247,6 +2248,7 @@ fn test_fetch_initial_commit() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2297,6 +2299,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2320,6 +2323,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2362,6 +2366,57 @@ fn test_fetch_success() {
     );
 }
 
+#[test]
+fn test_fetch_skip_tags() {
+    let test_data = GitRepoData::create();
+    let git_settings = GitSettings::default();
+    let initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
+    test_data
+        .origin_repo
+        .reference("refs/tags/v1.0", initial_git_commit.id(), false, "")
+        .unwrap();
+
+    let mut tx = test_data.repo.start_transaction(&test_data.settings);
+    git::fetch(
+        tx.mut_repo(),
+        &test_data.git_repo,
+        "origin",
+        &[StringPattern::everything()],
+        git::GitFetchTags::Skip,
+        git::RemoteCallbacks::default(),
+        &git_settings,
+    )
+    .unwrap();
+    // The tag is neither fetched into the Git repo nor imported
+    assert!(test_data.git_repo.find_reference("refs/tags/v1.0").is_err());
+    let initial_commit_target = RefTarget::normal(jj_id(&initial_git_commit));
+    assert_eq!(
+        *tx.mut_repo().view().git_refs(),
+        btreemap! {
+            "refs/remotes/origin/main".to_string() => initial_commit_target.clone(),
+        }
+    );
+    assert!(tx.mut_repo().view().tags().is_empty());
+
+    // The tag is fetched once tags aren't skipped
+    git::fetch(
+        tx.mut_repo(),
+        &test_data.git_repo,
+        "origin",
+        &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
+        git::RemoteCallbacks::default(),
+        &git_settings,
+    )
+    .unwrap();
+    assert_eq!(
+        *tx.mut_repo().view().tags(),
+        btreemap! {
+            "v1.0".to_string() => initial_commit_target,
+        }
+    );
+}
+
 #[test]
 fn test_fetch_prune_deleted_ref() {
     let test_data = GitRepoData::create();
@@ -2377,6 +2432,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2400,6 +2456,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2427,6 +2484,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2450,6 +2508,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2471,6 +2530,7 @@ fn test_fetch_empty_refspecs() {
         &test_data.git_repo,
         "origin",
         &[],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2497,6 +2557,7 @@ fn test_fetch_no_such_remote() {
         &test_data.git_repo,
         "invalid-remote",
         &[StringPattern::everything()],
+        git::GitFetchTags::Auto,
         git::RemoteCallbacks::default(),
         &git_settings,
     );
@@ -3027,6 +3088,40 @@ fn test_push_updates_success() {
     assert_eq!(new_target, Some(new_oid));
 }
 
+#[test]
+fn test_push_updates_new_ref() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let clone_repo = get_git_repo(&setup.jj_repo);
+
+    // A brand-new ref has no lease base. It's pushed on the condition that the
+    // ref doesn't exist on the remote.
+    let attempt_push_new_ref = || {
+        git::push_updates(
+            setup.jj_repo.as_ref(),
+            &clone_repo,
+            "origin",
+            &[GitRefUpdate {
+                qualified_name: "refs/heads/new-branch".to_string(),
+                expected_current_target: None,
+                new_target: Some(setup.sideways_commit.id().clone()),
+            }],
+            git::RemoteCallbacks::default(),
+        )
+    };
+    assert_eq!(attempt_push_new_ref(), Ok(()));
+    let source_repo = git2::Repository::open(&setup.source_repo_dir).unwrap();
+    let new_target = source_repo
+        .find_reference("refs/heads/new-branch")
+        .unwrap()
+        .target();
+    assert_eq!(new_target, Some(git_id(&setup.sideways_commit)));
+
+    // Pushing the same update again is a no-op, which is allowed.
+    assert_eq!(attempt_push_new_ref(), Ok(()));
+}
+
 #[test]
 fn test_push_updates_no_such_remote() {
     let settings = testutils::user_settings();
@@ -3065,6 +3160,25 @@ fn test_push_updates_invalid_remote() {
     assert!(matches!(result, Err(GitPushError::NoSuchRemote(_))));
 }
 
+#[test]
+fn test_push_error_ref_update_rejected_display() {
+    let err = GitPushError::RefUpdateRejected(vec![
+        GitRefUpdateRejection {
+            qualified_name: "refs/heads/main".to_owned(),
+            reason: Some("pre-receive hook declined".to_owned()),
+        },
+        GitRefUpdateRejection {
+            qualified_name: "refs/heads/other".to_owned(),
+            reason: None,
+        },
+    ]);
+    assert_eq!(
+        err.to_string(),
+        "Remote rejected the update of some refs (do you have permission to push to \
+         [\"refs/heads/main\", \"refs/heads/other\"]?)"
+    );
+}
+
 #[test]
 fn test_bulk_update_extra_on_import_refs() {
     let settings = testutils::user_settings();
@@ -32,7 +32,7 @@ use jj_lib::repo::{ReadonlyRepo, Repo};
 use jj_lib::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent};
 use jj_lib::secret_backend::SecretBackend;
 use jj_lib::settings::UserSettings;
-use jj_lib::working_copy::{CheckoutStats, SnapshotError, SnapshotOptions};
+use jj_lib::working_copy::{CheckoutOptions, CheckoutStats, SnapshotError, SnapshotOptions};
 use jj_lib::workspace::{default_working_copy_factories, LockedWorkspace, Workspace};
 use test_case::test_case;
 use testutils::{
@@ -226,10 +226,20 @@ fn test_checkout_file_transitions(backend: TestRepoBackend) {
     let right_commit = commit_with_tree(&store, right_tree_id.clone());
 
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &left_commit)
-        .unwrap();
-    ws.check_out(repo.op_id().clone(), None, &right_commit)
-        .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &left_commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &right_commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
 
     // Check that the working copy is clean.
     let new_tree = test_workspace.snapshot().unwrap();
@@ -325,9 +335,20 @@ fn test_conflict_subdirectory() {
     let merged_commit = commit_with_tree(repo.store(), merged_tree.id());
     let repo = &test_workspace.repo;
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
-    ws.check_out(repo.op_id().clone(), None, &merged_commit)
-        .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &merged_commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -371,13 +392,25 @@ fn test_acl() {
         &default_working_copy_factories(),
     )
     .unwrap();
-    ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
     assert!(!secret_modified_path.to_fs_path(&workspace_root).is_file());
     assert!(!secret_added_path.to_fs_path(&workspace_root).is_file());
     assert!(!secret_deleted_path.to_fs_path(&workspace_root).is_file());
     assert!(became_secret_path.to_fs_path(&workspace_root).is_file());
     assert!(!became_public_path.to_fs_path(&workspace_root).is_file());
-    ws.check_out(repo.op_id().clone(), None, &commit2).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit2,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
     assert!(!secret_modified_path.to_fs_path(&workspace_root).is_file());
     assert!(!secret_added_path.to_fs_path(&workspace_root).is_file());
     assert!(!secret_deleted_path.to_fs_path(&workspace_root).is_file());
@@ -396,7 +429,13 @@ fn test_tree_builder_file_directory_transition() {
     let mut check_out_tree = |tree_id: &TreeId| {
         let tree = repo.store().get_tree(RepoPath::root(), tree_id).unwrap();
         let commit = commit_with_tree(repo.store(), MergedTreeId::Legacy(tree.id().clone()));
-        ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+        ws.check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default(),
+        )
+        .unwrap();
     };
 
     let parent_path = RepoPath::from_internal_string("foo/bar");
@@ -473,7 +512,14 @@ fn test_conflicting_changes_on_disk() {
     )
     .unwrap();
 
-    let stats = ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    let stats = ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default(),
+        )
+        .unwrap();
     assert_eq!(
         stats,
         CheckoutStats {
@@ -519,7 +565,13 @@ fn test_reset() {
 
     let ws = &mut test_workspace.workspace;
     let commit = commit_with_tree(repo.store(), tree_with_file.id());
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
 
     // Test the setup: the file should exist on disk and in the tree state.
     assert!(ignored_path.to_fs_path(&workspace_root).is_file());
@@ -571,7 +623,13 @@ fn test_checkout_discard() {
     let commit2 = commit_with_tree(repo.store(), tree2.id());
 
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
     let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
     let state_path = wc.state_path().to_path_buf();
 
@@ -582,7 +640,10 @@ fn test_checkout_discard() {
 
     // Start a checkout
     let mut locked_ws = ws.start_working_copy_mutation().unwrap();
-    locked_ws.locked_wc().check_out(&commit2).unwrap();
+    locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::default())
+        .unwrap();
     // The change should be reflected in the working copy but not saved
     assert!(!file1_path.to_fs_path(&workspace_root).is_file());
     assert!(file2_path.to_fs_path(&workspace_root).is_file());
@@ -629,7 +690,14 @@ fn test_materialize_snapshot_conflicted_files() {
         .unwrap();
     let commit = commit_with_tree(repo.store(), merged_tree.id());
 
-    let stats = ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    let stats = ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default(),
+        )
+        .unwrap();
     assert_eq!(
         stats,
         CheckoutStats {
@@ -876,7 +944,8 @@ fn test_gitignores_in_ignored_dir() {
     let tree1 = create_tree(&test_workspace.repo, &[(gitignore_path, "ignored\n")]);
     let commit1 = commit_with_tree(test_workspace.repo.store(), tree1.id());
     let ws = &mut test_workspace.workspace;
-    ws.check_out(op_id.clone(), None, &commit1).unwrap();
+    ws.check_out(op_id.clone(), None, &commit1, &CheckoutOptions::default())
+        .unwrap();
 
     testutils::write_working_copy_file(&workspace_root, nested_gitignore_path, "!file\n");
     testutils::write_working_copy_file(&workspace_root, ignored_path, "contents");
@@ -928,7 +997,14 @@ fn test_gitignores_checkout_never_overwrites_ignored() {
     // "contents". The exiting contents ("garbage") shouldn't be replaced in the
     // working copy.
     let ws = &mut test_workspace.workspace;
-    assert!(ws.check_out(repo.op_id().clone(), None, &commit).is_ok());
+    assert!(ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default()
+        )
+        .is_ok());
 
     // Check that the old contents are in the working copy
     let path = workspace_root.join("modified");
@@ -998,7 +1074,13 @@ fn test_gitignores_ignored_directory_already_tracked() {
 
     // Check out the tree with the files in `ignored/`
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
 
     // Make some changes inside the ignored directory and check that they are
     // detected when we snapshot. The files that are still there should not be
@@ -1103,7 +1185,13 @@ fn test_gitsubmodule() {
     let tree = store.get_root_tree(&tree_id).unwrap();
     let commit = commit_with_tree(repo.store(), tree.id());
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::default(),
+    )
+    .unwrap();
 
     std::fs::create_dir(submodule_path.to_fs_path(&workspace_root)).unwrap();
 
@@ -1147,7 +1235,14 @@ fn test_existing_directory_symlink() {
 
     // Checkout should fail because "parent" already exists and is a symlink.
     let ws = &mut test_workspace.workspace;
-    assert!(ws.check_out(repo.op_id().clone(), None, &commit).is_err());
+    assert!(ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default()
+        )
+        .is_err());
 
     // Therefore, "../escaped" shouldn't be created.
     assert!(!workspace_root.parent().unwrap().join("escaped").exists());
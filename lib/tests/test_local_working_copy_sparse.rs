@@ -17,7 +17,7 @@ use jj_lib::local_working_copy::LocalWorkingCopy;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
-use jj_lib::working_copy::{CheckoutStats, WorkingCopy};
+use jj_lib::working_copy::{CheckoutOptions, CheckoutStats, WorkingCopy};
 use testutils::{commit_with_tree, create_tree, TestWorkspace};
 
 fn to_owned_path_vec(paths: &[&RepoPath]) -> Vec<RepoPathBuf> {
@@ -56,7 +56,12 @@ fn test_sparse_checkout() {
 
     test_workspace
         .workspace
-        .check_out(repo.op_id().clone(), None, &commit)
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default(),
+        )
         .unwrap();
     let ws = &mut test_workspace.workspace;
 
@@ -169,7 +174,12 @@ fn test_sparse_commit() {
     let commit = commit_with_tree(repo.store(), tree.id());
     test_workspace
         .workspace
-        .check_out(repo.op_id().clone(), None, &commit)
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::default(),
+        )
         .unwrap();
 
     // Set sparse patterns to only dir1/